@@ -1,10 +1,118 @@
-use clap::{Parser};
+use clap::{Args, Parser, Subcommand};
+
+use chip_8::{Quirks, DEFAULT_CYCLES_PER_FRAME};
+use chip_8::disasm::Syntax;
+use chip_8::frontend::{KeyPress, MAX_CYCLES_PER_FRAME, MIN_CYCLES_PER_FRAME};
+use chip_8::{debugger, trace};
 
 
 /// CHIP-8 Emulator
 #[derive(Parser, Debug)]
-
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 pub struct Chip8Args {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    // Running a ROM is the default, so "chip-8 <ROM>" is the same as "chip-8 run <ROM>"
+    #[command(flatten)]
+    pub run: RunArgs,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Run a ROM (the default when no command is given)
+    Run(Box<RunArgs>),
+
+    /// Print the instructions and data of a ROM
+    Disasm(DisasmArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct RunArgs {
+    /// path to ROM file
+    #[arg(required_unless_present = "dap")]
+    pub rom: Option<String>,
+
+    /// quirk profile of the interpreter the ROM was written for: modern, vip, chip48, schip or xochip
+    #[arg(short, long, default_value = "modern")]
+    pub quirks: Quirks,
+
+    /// instructions run per frame, at 60 frames per second (9 is 540 instructions/s); it can be changed while running with - and =
+    #[arg(short, long, visible_alias = "ipf", default_value_t = DEFAULT_CYCLES_PER_FRAME,
+        value_parser = clap::value_parser!(u32).range(MIN_CYCLES_PER_FRAME as i64..=MAX_CYCLES_PER_FRAME as i64))]
+    pub speed: u32,
+
+    /// seed of the random number generator used by CXNN, to reproduce a run (random by default)
+    #[arg(long)]
+    pub seed: Option<u64>,
+
+    /// save state file to load after the ROM, such as one saved to a slot with Shift + F1-F9
+    #[arg(long, value_name = "FILE")]
+    pub load_state: Option<String>,
+
+    /// record every keypad and speed input to an input movie, along with the seed, speed and quirks
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["load_state", "replay"])]
+    pub record: Option<String>,
+
+    /// replay an input movie recorded with --record, using its seed, speed and quirks
+    #[arg(long, value_name = "FILE", conflicts_with = "load_state")]
+    pub replay: Option<String>,
+
+    /// run without a window, audio or keyboard, as fast as possible, then print the display
+    #[arg(long, requires = "frames")]
+    pub headless: bool,
+
+    /// number of frames run in headless mode (60 frames per second)
+    #[arg(long, requires = "headless")]
+    pub frames: Option<u64>,
+
+    /// key press in headless mode, as FRAME:KEY or FRAME:KEY:HELD_FRAMES with the key in hexadecimal (held for 6 frames by default); can be repeated
+    #[arg(long = "key", value_name = "PRESS", requires = "headless")]
+    pub keys: Vec<KeyPress>,
+
+    /// file the display is written to in headless mode instead of printing it: a .txt file for text, or a .pgm or .png image
+    #[arg(short, long, value_name = "FILE", requires = "headless")]
+    pub output: Option<String>,
+
+    /// animated GIF the display is recorded to in headless mode, from the first frame to the last
+    #[arg(long, value_name = "FILE", requires = "headless")]
+    pub record_gif: Option<String>,
+
+    /// WAV file the buzzer is recorded to, rendered from the sound timer of each frame
+    #[arg(long, value_name = "FILE")]
+    pub record_wav: Option<String>,
+
+    /// how many times larger than the display images are: screenshots and GIFs (10 by default) and headless display images (1 by default)
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..=100))]
+    pub scale: Option<u32>,
+
+    /// file a line is written to for each instruction run: cycle number, PC, opcode, mnemonic, V0-VF, I, SP and timers
+    #[arg(long, value_name = "FILE")]
+    pub trace: Option<String>,
+
+    /// only trace the instructions at an address of a range, as ADDR or ADDR-END in hexadecimal (both included)
+    #[arg(long, value_name = "RANGE", requires = "trace", value_parser = debugger::parse_range)]
+    pub trace_range: Option<(u16, u16)>,
+
+    /// only trace the instructions run during a window of frames, as FRAME or START-END (both included, the first frame is 0)
+    #[arg(long, value_name = "FRAMES", requires = "trace", value_parser = trace::parse_frame_range)]
+    pub trace_frames: Option<(u64, u64)>,
+
+    /// pause in the debugger before the first instruction, reading commands from the terminal (F10 pauses while running)
+    #[arg(long)]
+    pub debug: bool,
+
+    /// wait for a Debug Adapter Protocol client, such as VS Code, on the chosen port of localhost, and let it launch and debug the ROM
+    #[arg(long, value_name = "PORT", conflicts_with = "debug")]
+    pub dap: Option<u16>,
+}
+
+#[derive(Args, Debug)]
+pub struct DisasmArgs {
     /// path to ROM file
     pub rom: String,
-}
\ No newline at end of file
+
+    /// assembly syntax of the instructions: classic (LD V0, 0x12) or octo (v0 := 0x12)
+    #[arg(long, default_value = "classic")]
+    pub syntax: Syntax,
+}
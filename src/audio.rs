@@ -0,0 +1,77 @@
+// Audio is generated as samples at this rate (samples per second)
+pub const SAMPLE_RATE: u32 = 44100;
+
+// XO-CHIP audio patterns are 16 bytes (128 1-bit samples) long
+pub const AUDIO_PATTERN_SIZE: usize = 16;
+
+/* Pattern played when a program never loads one: at the default pitch, it is a
+ * 250Hz square wave, the buzzer sound of the original CHIP-8 */
+pub const DEFAULT_AUDIO_PATTERN: [u8; AUDIO_PATTERN_SIZE] = [
+    0xFF, 0x00, 0xFF, 0x00, 0xFF, 0x00, 0xFF, 0x00,
+    0xFF, 0x00, 0xFF, 0x00, 0xFF, 0x00, 0xFF, 0x00,
+    ];
+
+// Pitch used by XO-CHIP until a program sets a new one, which plays the pattern at 4000Hz
+pub const DEFAULT_PITCH: u8 = 64;
+
+// Returns how many pattern bits are played per second at the chosen pitch
+pub fn playback_rate(pitch: u8) -> f32 {
+    4000.0 * 2f32.powf((pitch as f32 - 64.0) / 48.0)
+}
+
+
+// Plays the XO-CHIP 1-bit audio pattern buffer in a loop at the programmed pitch
+pub struct PatternPlayer {
+    pattern: [u8; AUDIO_PATTERN_SIZE],
+    pitch: u8,
+
+    // Position in the pattern, in bits
+    position: f32,
+
+    // How many bits the position advances on each sample
+    position_inc: f32,
+
+    sample_rate: u32,
+    volume: f32,
+}
+
+impl PatternPlayer {
+
+    // Creates new player that generates samples at the chosen rate
+    pub fn new(sample_rate: u32, volume: f32) -> Self {
+        Self {
+            pattern: DEFAULT_AUDIO_PATTERN,
+            pitch: DEFAULT_PITCH,
+            position: 0.0,
+            position_inc: playback_rate(DEFAULT_PITCH) / sample_rate as f32,
+            sample_rate,
+            volume,
+        }
+    }
+
+    // Changes the pattern and pitch, keeping the current position to avoid clicks
+    pub fn set_pattern(&mut self, pattern: [u8; AUDIO_PATTERN_SIZE], pitch: u8) {
+        self.pattern = pattern;
+        if pitch != self.pitch {
+            self.pitch = pitch;
+            self.position_inc = playback_rate(pitch) / self.sample_rate as f32;
+        }
+    }
+
+    // Fills the buffer with the next samples of the pattern
+    pub fn fill(&mut self, out: &mut [f32]) {
+        let pattern_bits = (AUDIO_PATTERN_SIZE * 8) as f32;
+
+        for x in out.iter_mut() {
+            let bit = self.position as usize;
+            let sample = (self.pattern[bit / 8] >> (7 - bit % 8)) & 1;
+
+            *x = if sample == 1 {
+                self.volume
+            } else {
+                -self.volume
+            };
+            self.position = (self.position + self.position_inc) % pattern_bits;
+        }
+    }
+}
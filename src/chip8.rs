@@ -1,45 +1,254 @@
-use crate::cpu::Cpu;
-use std::time;
+use crate::audio::AUDIO_PATTERN_SIZE;
+use crate::cpu::{Cpu, Register};
+use crate::error::{CpuError, StateError};
+use crate::quirks::Quirks;
+use crate::screen::{self, Palette};
+use crate::state::{self, StateReader, StateWriter, STATE_MAGIC, STATE_VERSION};
+use crate::watchpoint::{WatchHit, Watchpoint};
+
+/* A frame lasts 1/60 of a second, the rate at which the delay and sound timers decrement
+ * and the display refreshes */
+pub const FRAME_RATE: u32 = 60;
+
+/* By default, instructions are run at a rate of 540Hz, which is 9 instructions per frame.
+ * (540Hz / 60Hz = 9) */
+pub const DEFAULT_CYCLES_PER_FRAME: u32 = 9;
 
 
 pub struct Chip8 {
     cpu: Cpu,
-    pub tick_period: time::Instant,
+
+    // How many frames have been run since the ROM was loaded
+    frame_count: u64,
+
+    // Hash of the loaded ROM, stored in save states
+    rom_hash: u64,
+
+    // Seed the random number generator of the CPU started from
+    seed: u64,
+
+    // Instructions already run in the current frame, when a frame was stopped before its end
+    cycles_run: u32,
 }
 
 impl Chip8 {
 
-    // Creating new instance of CHIP-8
-    pub fn new() -> Self {
+    // Creating new instance of CHIP-8 that follows the chosen quirks, with a random seed
+    pub fn new(quirks: Quirks) -> Self {
+        let mut cpu = Cpu::new();
+        cpu.set_quirks(quirks);
+
+        let seed = rand::random();
+        cpu.set_seed(seed);
+
         Self {
-            cpu: Cpu::new(),
-            tick_period: std::time::Instant::now(),
+            cpu,
+            frame_count: 0,
+            rom_hash: state::rom_hash(&[]),
+            seed,
+            cycles_run: 0,
         }
     }
 
+    // Seeds the random number generator of the CPU, so runs can be reproduced
+    pub fn set_seed(&mut self, seed: u64) {
+        self.cpu.set_seed(seed);
+        self.seed = seed;
+    }
+
+    // Returns the seed the random number generator started from
+    pub fn get_seed(&self) -> u64 {
+        self.seed
+    }
+
+    // Returns the interpreter behaviours used by the ambiguous instructions
+    pub fn get_quirks(&self) -> Quirks {
+        self.cpu.get_quirks()
+    }
+
     // Loads ROM using CPU method
     pub fn load_rom(&mut self, rom_data: &[u8]) -> Result<(), String> {
-        self.cpu.load_rom_in_memory(rom_data)
+        self.cpu.load_rom_in_memory(rom_data)?;
+        self.rom_hash = state::rom_hash(rom_data);
+        Ok(())
+    }
+
+    // Returns the hash of the loaded ROM
+    pub fn get_rom_hash(&self) -> u64 {
+        self.rom_hash
+    }
+
+    // Returns a save state with the whole machine state, which can be loaded later
+    pub fn save_state(&self) -> Vec<u8> {
+        let mut writer = StateWriter::default();
+        writer.write_bytes(&STATE_MAGIC);
+        writer.write_u16(STATE_VERSION);
+        writer.write_u64(self.rom_hash);
+        writer.write_u64(self.frame_count);
+        self.cpu.write_state(&mut writer);
+        writer.into_bytes()
+    }
+
+    /* Restores the machine state from a save state.
+     * States of a different format version or taken from a different ROM are rejected,
+     * and the machine is left unchanged when loading fails */
+    pub fn load_state(&mut self, state: &[u8]) -> Result<(), StateError> {
+        let mut reader = StateReader::new(state);
+
+        if reader.read_array::<4>()? != STATE_MAGIC {
+            return Err(StateError::InvalidFormat);
+        }
+        let version = reader.read_u16()?;
+        if version != STATE_VERSION {
+            return Err(StateError::UnsupportedVersion { found: version, expected: STATE_VERSION });
+        }
+        let rom_hash = reader.read_u64()?;
+        if rom_hash != self.rom_hash {
+            return Err(StateError::RomMismatch { found: rom_hash, expected: self.rom_hash });
+        }
+
+        let frame_count = reader.read_u64()?;
+        let mut cpu = self.cpu.clone();
+        cpu.read_state(&mut reader)?;
+        reader.finish()?;
+
+        self.cpu = cpu;
+        self.frame_count = frame_count;
+        self.cycles_run = 0;
+        Ok(())
+    }
+
+    // Runs a single instruction, stopping with an error if the CPU cannot continue
+    pub fn step(&mut self) -> Result<(), CpuError> {
+        self.cpu.run()
+    }
+
+    /* Runs a frame: the chosen number of instructions, then the timers are decremented once.
+     * Emulation only depends on the ROM, the input and the number of cycles per frame,
+     * so running the same frames always gives the same result. Pacing the frames at 60Hz
+     * is left to the frontend.
+     *
+     * The frame ends early if the program exits */
+    pub fn run_frame(&mut self, cycles_per_frame: u32) -> Result<(), CpuError> {
+        self.run_frame_until(cycles_per_frame, |_| false)?;
+        Ok(())
+    }
+
+    /* Runs the rest of the current frame like run_frame, unless `stop` returns true before one of
+     * its instructions. Returns whether the frame ended; a stopped frame is resumed by the next call,
+     * so stopping does not change the result of the frame */
+    pub fn run_frame_until<F>(&mut self, cycles_per_frame: u32, mut stop: F) -> Result<bool, CpuError>
+    where
+        F: FnMut(&Chip8) -> bool,
+    {
+        while self.cycles_run < cycles_per_frame && !self.cpu.has_exited() {
+            if stop(self) {
+                return Ok(false);
+            }
+            self.cpu.run()?;
+            self.cycles_run += 1;
+        }
+
+        self.cpu.update_timers();
+        self.frame_count += 1;
+        self.cycles_run = 0;
+        Ok(true)
+    }
+
+    // Returns how many frames have been run
+    pub fn get_frame_count(&self) -> u64 {
+        self.frame_count
+    }
+
+    // Returns how many instructions of the current frame have been run, if it was stopped before its end
+    pub fn get_cycles_run(&self) -> u32 {
+        self.cycles_run
+    }
+
+    // Returns the value of a register, timer or stack level of the CPU
+    pub fn get_register(&self, register: Register) -> u16 {
+        self.cpu.get_register(register)
+    }
+
+    // Changes the value of a register, timer or stack level of the CPU
+    pub fn set_register(&mut self, register: Register, value: u16) -> Result<(), String> {
+        self.cpu.set_register(register, value)
+    }
+
+    // Returns the return addresses on the stack, from the oldest call to the latest one
+    pub fn get_stack(&self) -> &[u16] {
+        self.cpu.get_stack()
+    }
+
+    // Returns the whole memory
+    pub fn get_memory(&self) -> &[u8] {
+        self.cpu.get_memory()
+    }
+
+    // Writes bytes to memory starting at the chosen address
+    pub fn set_memory(&mut self, address: usize, bytes: &[u8]) -> Result<(), String> {
+        self.cpu.set_memory(address, bytes)
+    }
+
+    // Adds a watchpoint, whose accesses are recorded by the instruction that makes them
+    pub fn add_watchpoint(&mut self, watchpoint: Watchpoint) {
+        self.cpu.add_watchpoint(watchpoint)
     }
 
-    // Runs CHIP-8
-    pub fn run(&mut self) {
-        self.cpu.run();
+    // Removes a watchpoint, returning whether it had been added
+    pub fn remove_watchpoint(&mut self, watchpoint: &Watchpoint) -> bool {
+        self.cpu.remove_watchpoint(watchpoint)
+    }
+
+    // Returns the watchpoints, in the order they were added
+    pub fn get_watchpoints(&self) -> &[Watchpoint] {
+        self.cpu.get_watchpoints()
+    }
+
+    // Returns the watched accesses made by the last instruction run
+    pub fn get_watch_hits(&self) -> &[WatchHit] {
+        self.cpu.get_watch_hits()
+    }
+
+    // Returns the registers, timers and stack of the CPU formatted for the user
+    pub fn get_machine_state(&self) -> String {
+        self.cpu.to_string()
     }
 
     // Returns the display using the CPU method
-    pub fn get_display(&self) -> &[bool] {
+    pub fn get_display(&self) -> &[u8] {
         self.cpu.get_display()
     }
 
+    // Returns the width and height of the display in the current resolution
+    pub fn get_display_size(&self) -> (usize, usize) {
+        self.cpu.get_display_size()
+    }
+
+    // Returns the current display as a PNG image drawn with the colors of the palette, scaled up by the chosen factor
+    pub fn screenshot_png(&self, scale: u32, palette: &Palette) -> Result<Vec<u8>, String> {
+        let (width, height) = self.get_display_size();
+        screen::to_png(self.get_display(), width, height, scale, palette)
+    }
+
+    // Returns the exit flag, set when the program asks the interpreter to exit
+    pub fn has_exited(&self) -> bool {
+        self.cpu.has_exited()
+    }
+
+    // Returns the XO-CHIP audio pattern buffer and pitch register
+    pub fn get_audio_pattern(&self) -> ([u8; AUDIO_PATTERN_SIZE], u8) {
+        (self.cpu.get_audio_pattern(), self.cpu.get_pitch())
+    }
+
     // Returns the beep sound flag
     pub fn get_beep_state(&self) -> bool {
         self.cpu.get_beep_state()
     }
 
-    // Returns the display update flag
-    pub fn get_display_state(&self) -> bool {
-        self.cpu.get_display_state()
+    // Returns which keys of the keypad are pressed, by index
+    pub fn get_keypad(&self) -> [bool; 16] {
+        self.cpu.get_keypad()
     }
 
     // Sets key of chosen index as pressed
@@ -52,4 +261,8 @@ impl Chip8 {
         self.cpu.set_key(keypad_idx, false);
     }
 
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+#[path ="./chip8_test.rs"]
+mod chip8_test;
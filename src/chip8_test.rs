@@ -0,0 +1,176 @@
+use super::*;
+
+// Counts down the delay timer from 5, increasing V1 on every instruction until it reaches 0
+const COUNTDOWN_ROM: [u8; 14] = [
+    0x60, 0x05, // V0 = 5
+    0xF0, 0x15, // Delay timer = V0
+    0x71, 0x01, // V1 += 1
+    0xF0, 0x07, // V0 = delay timer
+    0x30, 0x00, // Skips the jump once the delay timer reaches 0
+    0x12, 0x04, // Jumps back to increasing V1
+    0x00, 0xFD, // Exits
+];
+
+#[test]
+fn test_run_frame() -> Result<(), String> {
+    let mut chip8 = Chip8::new(Quirks::default());
+    chip8.load_rom(&[0x70, 0x01, 0x12, 0x00])?; // V0 += 1, then jumps back forever
+
+    chip8.run_frame(10).unwrap();
+    assert_eq!(chip8.get_frame_count(), 1);
+    assert!(chip8.get_machine_state().contains("V0: 0x05"));
+
+    chip8.run_frame(4).unwrap();
+    assert_eq!(chip8.get_frame_count(), 2);
+    assert!(chip8.get_machine_state().contains("V0: 0x07"));
+    Ok(())
+}
+
+#[test]
+fn test_run_frame_until() -> Result<(), String> {
+    let mut chip8 = Chip8::new(Quirks::default());
+    chip8.load_rom(&[0x70, 0x01, 0x12, 0x00])?; // V0 += 1, then jumps back forever
+
+    // Stops before the 5th instruction, then the next call runs the rest of the frame
+    let mut checks = 0;
+    assert_eq!(chip8.run_frame_until(10, |_| { checks += 1; checks == 5 }), Ok(false));
+    assert_eq!(chip8.get_cycles_run(), 4);
+    assert_eq!(chip8.get_frame_count(), 0);
+    assert!(chip8.get_machine_state().contains("V0: 0x02"));
+
+    assert_eq!(chip8.run_frame_until(10, |_| false), Ok(true));
+    assert_eq!(chip8.get_cycles_run(), 0);
+    assert_eq!(chip8.get_frame_count(), 1);
+    assert!(chip8.get_machine_state().contains("V0: 0x05"));
+    Ok(())
+}
+
+#[test]
+fn test_run_frame_timers() -> Result<(), String> {
+    let mut chip8 = Chip8::new(Quirks::default());
+    chip8.load_rom(&COUNTDOWN_ROM)?;
+
+    // The delay timer decrements exactly once per frame, whatever the host load is
+    let mut frames = 0;
+    while !chip8.has_exited() {
+        chip8.run_frame(DEFAULT_CYCLES_PER_FRAME).unwrap();
+        frames += 1;
+    }
+    assert_eq!(frames, 6);
+    assert_eq!(chip8.get_frame_count(), 6);
+    Ok(())
+}
+
+#[test]
+fn test_run_frame_reproducible() -> Result<(), String> {
+    let mut first = Chip8::new(Quirks::default());
+    let mut second = Chip8::new(Quirks::default());
+    first.load_rom(&COUNTDOWN_ROM)?;
+    second.load_rom(&COUNTDOWN_ROM)?;
+
+    for _ in 0..3 {
+        first.run_frame(DEFAULT_CYCLES_PER_FRAME).unwrap();
+        second.run_frame(DEFAULT_CYCLES_PER_FRAME).unwrap();
+    }
+    assert_eq!(first.get_machine_state(), second.get_machine_state());
+    Ok(())
+}
+
+#[test]
+fn test_save_load_state() -> Result<(), String> {
+    let mut chip8 = Chip8::new(Quirks::default());
+    chip8.set_seed(7);
+    chip8.load_rom(&[
+        0xC0, 0xFF, // V0 = random number
+        0xF0, 0x29, // I = sprite of the last digit of V0
+        0xD1, 0x15, // Draws it at (V1, V1)
+        0x71, 0x01, // V1 += 1
+        0x12, 0x00, // Jumps back to the start
+    ])?;
+    chip8.press_key(0x5);
+    chip8.run_frame(20).unwrap();
+    let state = chip8.save_state();
+
+    // Running on from the state gives the same result, random numbers included
+    chip8.run_frame(20).unwrap();
+    let expected_state = chip8.get_machine_state();
+    let expected_display = chip8.get_display().to_vec();
+
+    chip8.release_key(0x5);
+    chip8.run_frame(35).unwrap();
+    assert_eq!(chip8.load_state(&state), Ok(()));
+    assert_eq!(chip8.get_frame_count(), 1);
+
+    chip8.run_frame(20).unwrap();
+    assert_eq!(chip8.get_machine_state(), expected_state);
+    assert_eq!(chip8.get_display(), &expected_display[..]);
+    Ok(())
+}
+
+#[test]
+fn test_load_state_errors() -> Result<(), String> {
+    let mut chip8 = Chip8::new(Quirks::default());
+    chip8.load_rom(&COUNTDOWN_ROM)?;
+    chip8.run_frame(3).unwrap();
+    let state = chip8.save_state();
+    let machine_state = chip8.get_machine_state();
+
+    // Taken from a different ROM
+    let mut other = Chip8::new(Quirks::default());
+    other.load_rom(&[0x12, 0x00])?;
+    assert!(matches!(other.load_state(&state), Err(StateError::RomMismatch { .. })));
+
+    // Incompatible format version
+    let mut newer = state.clone();
+    newer[4..6].copy_from_slice(&(STATE_VERSION + 1).to_le_bytes());
+    assert_eq!(
+        chip8.load_state(&newer),
+        Err(StateError::UnsupportedVersion { found: STATE_VERSION + 1, expected: STATE_VERSION })
+    );
+
+    // Not a save state, or cut short
+    assert_eq!(chip8.load_state(b"not a save state"), Err(StateError::InvalidFormat));
+    assert_eq!(chip8.load_state(&state[..(state.len() - 1)]), Err(StateError::Truncated));
+
+    // The machine is left unchanged when loading fails
+    assert_eq!(chip8.get_machine_state(), machine_state);
+    Ok(())
+}
+
+#[test]
+fn test_screenshot_png() -> Result<(), String> {
+    let mut chip8 = Chip8::new(Quirks::default());
+    chip8.load_rom(&[
+        0xF0, 0x29, // I = sprite of character 0
+        0xD1, 0x15, // Draws character 0 at (V1, V1) = (0, 0)
+        0x00, 0xFD, // Exits
+    ])?;
+    chip8.run_frame(3).unwrap();
+
+    let palette = Palette { main: [0, 255, 0], ..Palette::default() };
+    let image = chip8.screenshot_png(2, &palette)?;
+    let mut reader = png::Decoder::new(image.as_slice()).read_info().map_err(|e| e.to_string())?;
+    let mut pixels = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut pixels).map_err(|e| e.to_string())?;
+    assert_eq!((info.width, info.height), (128, 64));
+
+    // The top-left pixel is set by character 0, and the one next to the sprite is not
+    assert_eq!(pixels[..3], [0, 255, 0]);
+    assert_eq!(pixels[4 * 2 * 3..4 * 2 * 3 + 3], [0, 0, 0]);
+    Ok(())
+}
+
+#[test]
+fn test_state_quirks() -> Result<(), String> {
+    for quirks in [Quirks::modern(), Quirks::cosmac_vip(), Quirks::chip48()] {
+        let mut chip8 = Chip8::new(quirks);
+        chip8.load_rom(&COUNTDOWN_ROM)?;
+        let state = chip8.save_state();
+
+        let mut other = Chip8::new(Quirks::default());
+        other.load_rom(&COUNTDOWN_ROM)?;
+        assert_eq!(other.load_state(&state), Ok(()));
+        assert_eq!(other.get_quirks(), quirks);
+    }
+    Ok(())
+}
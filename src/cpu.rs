@@ -1,19 +1,33 @@
-use rand::{self, Rng};
-use std::time;
-
-// CHIP-8 can access 4KB (4096 bytes) of RAM
-const MEMORY_SIZE: usize = 4096;
+use rand::{self, Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::audio::{AUDIO_PATTERN_SIZE, DEFAULT_AUDIO_PATTERN, DEFAULT_PITCH};
+use crate::error::{CpuError, StateError};
+use crate::instruction::Instruction;
+use crate::quirks::{LoadStoreIncrement, Quirks};
+use crate::state::{StateReader, StateWriter};
+use crate::watchpoint::{Access, WatchHit, Watchpoint};
+
+/* CHIP-8 can access 4KB (4096 bytes) of RAM.
+ * XO-CHIP extends it to 64KB (65536 bytes), which is what is allocated */
+const CHIP8_MEMORY_SIZE: usize = 4096;
+const MEMORY_SIZE: usize = 65536;
 
 // The display should be 64 pixels wide and 32 pixels tall
 pub const DISPLAY_WIDTH: usize = 64;
 pub const DISPLAY_HEIGHT: usize = 32;
 
-// After loading, CHIP-8 programs start at address 0x200
-const START_ADDRESS: u16 = 0x200;
+// In SUPER-CHIP high-resolution mode, the display is 128 pixels wide and 64 pixels tall
+pub const HIRES_DISPLAY_WIDTH: usize = 128;
+pub const HIRES_DISPLAY_HEIGHT: usize = 64;
 
-/* The delay and sound timers decrement at a rate of 60Hz (60 times per second)
- * Therefore, (1 / 60) = 0.0166666667s = 16667μs */
-const TIMER_RATE: u64 = 16667;
+// The display buffer is big enough to hold the high-resolution display
+const DISPLAY_BUFFER_SIZE: usize = HIRES_DISPLAY_WIDTH * HIRES_DISPLAY_HEIGHT;
+
+// After loading, CHIP-8 programs start at address 0x200
+pub const START_ADDRESS: u16 = 0x200;
 
 const CHIP8_FONT: [u8; 80] = [
     0xF0, 0x90, 0x90, 0x90, 0xF0, // 0
@@ -34,6 +48,85 @@ const CHIP8_FONT: [u8; 80] = [
     0xF0, 0x80, 0xF0, 0x80, 0x80, // F
     ];
 
+// SUPER-CHIP big font (8x10 pixels per character), stored right after the regular font
+const BIG_FONT_ADDRESS: usize = 0xA0;
+
+const SCHIP_BIG_FONT: [u8; 160] = [
+    0x3C, 0x7E, 0xE7, 0xC3, 0xC3, 0xC3, 0xC3, 0xE7, 0x7E, 0x3C, // 0
+    0x18, 0x38, 0x58, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x3C, // 1
+    0x3E, 0x7F, 0xC3, 0x06, 0x0C, 0x18, 0x30, 0x60, 0xFF, 0xFF, // 2
+    0x3C, 0x7E, 0xC3, 0x03, 0x0E, 0x0E, 0x03, 0xC3, 0x7E, 0x3C, // 3
+    0x06, 0x0E, 0x1E, 0x36, 0x66, 0xC6, 0xFF, 0xFF, 0x06, 0x06, // 4
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFC, 0xFE, 0x03, 0xC3, 0x7E, 0x3C, // 5
+    0x3E, 0x7C, 0xE0, 0xC0, 0xFC, 0xFE, 0xC3, 0xC3, 0x7E, 0x3C, // 6
+    0xFF, 0xFF, 0x03, 0x06, 0x0C, 0x18, 0x30, 0x60, 0x60, 0x60, // 7
+    0x3C, 0x7E, 0xC3, 0xC3, 0x7E, 0x7E, 0xC3, 0xC3, 0x7E, 0x3C, // 8
+    0x3C, 0x7E, 0xC3, 0xC3, 0x7F, 0x3F, 0x03, 0x03, 0x3E, 0x7C, // 9
+    0x7E, 0xFF, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, 0xC3, 0xC3, 0xC3, // A
+    0xFC, 0xFC, 0xC3, 0xC3, 0xFC, 0xFC, 0xC3, 0xC3, 0xFC, 0xFC, // B
+    0x3C, 0xFF, 0xC3, 0xC0, 0xC0, 0xC0, 0xC0, 0xC3, 0xFF, 0x3C, // C
+    0xFC, 0xFE, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xFE, 0xFC, // D
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, // E
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xC0, 0xC0, // F
+    ];
+
+// Registers, timers and stack levels the user can read and change, such as in the debugger
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Register {
+
+    // General-purpose register V0 to VF
+    V(u8),
+    I,
+    Pc,
+    Sp,
+
+    // Delay and sound timers
+    Dt,
+    St,
+
+    // Level of the stack (1 to F), holding a return address while SP is at least the level
+    Stack(u8),
+}
+
+impl FromStr for Register {
+    type Err = String;
+
+    // Parses the names written by Display, ignoring case: "v0" to "vf", "i", "pc", "sp", "dt", "st" and "stack1" to "stackf"
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.to_ascii_lowercase();
+        let index = |digit: &str| u8::from_str_radix(digit, 16).ok().filter(|_| digit.len() == 1);
+
+        let register = match name.as_str() {
+            "i" => Some(Register::I),
+            "pc" => Some(Register::Pc),
+            "sp" => Some(Register::Sp),
+            "dt" => Some(Register::Dt),
+            "st" => Some(Register::St),
+            _ => match (name.strip_prefix("stack"), name.strip_prefix('v')) {
+                (Some(level), _) => index(level).map(Register::Stack),
+                (None, Some(idx)) => index(idx).map(Register::V),
+                _ => None,
+            },
+        };
+        register.ok_or_else(|| format!("unknown register '{}' (expected V0-VF, I, PC, SP, DT, ST or STACK1-STACKF)", s))
+    }
+}
+
+impl fmt::Display for Register {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Register::V(idx) => write!(f, "V{:X}", idx),
+            Register::I => write!(f, "I"),
+            Register::Pc => write!(f, "PC"),
+            Register::Sp => write!(f, "SP"),
+            Register::Dt => write!(f, "DT"),
+            Register::St => write!(f, "ST"),
+            Register::Stack(level) => write!(f, "STACK{:X}", level),
+        }
+    }
+}
+
+#[derive(Clone)]
 pub struct Cpu {
 
     // RAM, writable memory
@@ -53,6 +146,10 @@ pub struct Cpu {
     
     // Stack pointer which is used to point to the topmost level of the stack
     sp: usize,
+
+    // Address and opcode of the instruction being executed, reported by errors
+    instruction_address: u16,
+    opcode: u16,
     
     // Delay timer which is decremented at a rate of 60 Hz until it reaches 0
     delay_timer: u8,
@@ -60,22 +157,56 @@ pub struct Cpu {
     // Sound timer which gives off a beeping sound as long as it’s not 0
     sound_timer: u8,
     
-    // A display that updates at 60 Hz and whose each pixel can be on or off
-    display: [bool; DISPLAY_WIDTH * DISPLAY_HEIGHT],
+    /* A display that updates at 60 Hz and whose each pixel can be on or off.
+     * Rows are stored with the width of the current resolution.
+     * 
+     * XO-CHIP has two bitplanes: bit 0 of each pixel is the first plane and bit 1 is the second,
+     * so each pixel is one of four colours */
+    display: [u8; DISPLAY_BUFFER_SIZE],
+
+    // Bitplanes selected by XO-CHIP FN01, which drawing, clearing and scrolling apply to
+    planes: u8,
+
+    // Flag to check if the SUPER-CHIP high-resolution mode (128x64) is enabled
+    hires: bool,
     
     /* CHIP-8 uses a hexadecimal keypad that had 16 keys, labelled 0 through F,
      * and were arranged in a 4x4 grid */
     keypad: [bool; 16],
 
-    // The period of time the CPU uses to finish a cycle
-    tick_period: time::Instant,
-
-    // Flag to check if the display has been updated and needs to be redrawn
-    display_updated: bool,
-
     // Flag to check if the emulator should beep
     should_beep: bool,
-    
+
+    // Interpreter behaviours used by the ambiguous instructions
+    quirks: Quirks,
+
+    // Flag to check if the display has refreshed since the last sprite was drawn
+    vblank: bool,
+
+    // SUPER-CHIP RPL user flags, which registers can be saved to and loaded from
+    rpl_flags: [u8; 16],
+
+    // Flag to check if the program has exited through the SUPER-CHIP 00FD instruction
+    exited: bool,
+
+    // XO-CHIP 1-bit audio pattern buffer, played while the sound timer is not 0
+    audio_pattern: [u8; AUDIO_PATTERN_SIZE],
+
+    // XO-CHIP pitch register, which sets the playback rate of the audio pattern
+    pitch: u8,
+
+    /* Random number generator used by CXNN. Seeding it with a chosen seed makes
+     * every run of the same ROM with the same input identical */
+    rng: ChaCha8Rng,
+
+    /* Instructions already decoded at each address, so loops are only decoded once.
+     * It is not part of the machine state, since it can always be rebuilt from memory */
+    decode_cache: Box<[Option<Instruction>]>,
+
+    /* Memory ranges whose accesses are recorded, and the accesses recorded by the last instruction.
+     * Like the decode cache, they are not part of the machine state */
+    watchpoints: Vec<Watchpoint>,
+    watch_hits: Vec<WatchHit>,
 }
 
 impl Cpu {
@@ -83,9 +214,11 @@ impl Cpu {
     // Creating new instance of CPU
     pub fn new() -> Self {
         
-        // Initializing memory with 0's and storing font data at 0x50 ~ 0x9F address interval
+        /* Initializing memory with 0's, storing font data at 0x50 ~ 0x9F address interval
+         * and big font data at 0xA0 ~ 0x13F address interval */
         let mut aux_memory: [u8; MEMORY_SIZE] = [0; MEMORY_SIZE];
         aux_memory[0x50..(0x50 + CHIP8_FONT.len())].clone_from_slice(&CHIP8_FONT);
+        aux_memory[BIG_FONT_ADDRESS..(BIG_FONT_ADDRESS + SCHIP_BIG_FONT.len())].clone_from_slice(&SCHIP_BIG_FONT);
 
         Self {
             memory: aux_memory,
@@ -94,13 +227,25 @@ impl Cpu {
             i: 0,
             stack: [0; 16],
             sp: 0,
+            instruction_address: START_ADDRESS,
+            opcode: 0,
             delay_timer: 0,
             sound_timer: 0,
-            display: [false; DISPLAY_WIDTH * DISPLAY_HEIGHT],
+            display: [0; DISPLAY_BUFFER_SIZE],
+            planes: 1, // Only the first plane is selected, as in CHIP-8 and SUPER-CHIP
+            hires: false,
             keypad: [false; 16], // Keys start as not pressed
-            tick_period: time::Instant::now(), // Storing when the CPU cycle begins
-            display_updated: false,
             should_beep: false,
+            quirks: Quirks::default(),
+            vblank: true,
+            rpl_flags: [0; 16],
+            exited: false,
+            audio_pattern: DEFAULT_AUDIO_PATTERN,
+            pitch: DEFAULT_PITCH,
+            rng: ChaCha8Rng::seed_from_u64(rand::random()), // Random seed until one is chosen
+            decode_cache: vec![None; MEMORY_SIZE].into_boxed_slice(),
+            watchpoints: Vec::new(),
+            watch_hits: Vec::new(),
         }
     }
 
@@ -110,17 +255,45 @@ impl Cpu {
         let initial_address = START_ADDRESS as usize;
         let final_address = START_ADDRESS as usize + rom_data.len();
 
-        if rom_data.len() > self.memory[initial_address..].len() {
+        if rom_data.len() > self.get_memory_size() - initial_address {
             Err(String::from("The chosen file is not a valid CHIP-8 ROM file."))
         } else {
             self.memory[initial_address..final_address].copy_from_slice(rom_data);
+            self.decode_cache.fill(None);
+            self.watch_hits.clear();
             Ok(())
         }
     }
 
-    // Returns the display
-    pub fn get_display(&self) -> &[bool] {
-        &self.display
+    /* Returns the display, with as many pixels as the current resolution has.
+     * Each pixel holds the bits of both planes (0 to 3) */
+    pub fn get_display(&self) -> &[u8] {
+        let (width, height) = self.get_display_size();
+        &self.display[..(width * height)]
+    }
+
+    // Returns the width and height of the display in the current resolution
+    pub fn get_display_size(&self) -> (usize, usize) {
+        if self.hires {
+            (HIRES_DISPLAY_WIDTH, HIRES_DISPLAY_HEIGHT)
+        } else {
+            (DISPLAY_WIDTH, DISPLAY_HEIGHT)
+        }
+    }
+
+    // Returns the exit flag
+    pub fn has_exited(&self) -> bool {
+        self.exited
+    }
+
+    // Returns the XO-CHIP audio pattern buffer
+    pub fn get_audio_pattern(&self) -> [u8; AUDIO_PATTERN_SIZE] {
+        self.audio_pattern
+    }
+
+    // Returns the XO-CHIP pitch register
+    pub fn get_pitch(&self) -> u8 {
+        self.pitch
     }
 
     // Returns the beep sound flag
@@ -128,9 +301,111 @@ impl Cpu {
         self.should_beep
     }
 
-    // Returns the display update flag
-    pub fn get_display_state(&self) -> bool {
-        self.display_updated
+    // Restarts the random number generator from the chosen seed
+    pub fn set_seed(&mut self, seed: u64) {
+        self.rng = ChaCha8Rng::seed_from_u64(seed);
+    }
+
+    // Returns the interpreter behaviours used by the ambiguous instructions
+    pub fn get_quirks(&self) -> Quirks {
+        self.quirks
+    }
+
+    // Sets the interpreter behaviours used by the ambiguous instructions
+    pub fn set_quirks(&mut self, quirks: Quirks) {
+        self.quirks = quirks;
+    }
+
+    // Returns the value of a register, timer or stack level
+    pub fn get_register(&self, register: Register) -> u16 {
+        match register {
+            Register::V(idx) => self.v[idx as usize & 0xF] as u16,
+            Register::I => self.i,
+            Register::Pc => self.pc,
+            Register::Sp => self.sp as u16,
+            Register::Dt => self.delay_timer as u16,
+            Register::St => self.sound_timer as u16,
+            Register::Stack(level) => self.stack[level as usize & 0xF],
+        }
+    }
+
+    // Changes the value of a register, timer or stack level, failing if the value does not fit in it
+    pub fn set_register(&mut self, register: Register, value: u16) -> Result<(), String> {
+        let byte = || u8::try_from(value).map_err(|_| format!("{:#X} does not fit in {}, which is a byte", value, register));
+
+        match register {
+            Register::V(idx) => self.v[idx as usize & 0xF] = byte()?,
+            Register::I => self.i = value,
+            Register::Pc => self.pc = value,
+            Register::Sp if value as usize >= self.stack.len() => {
+                return Err(format!("SP must be lower than {}", self.stack.len()));
+            },
+            Register::Sp => self.sp = value as usize,
+            Register::Dt => self.delay_timer = byte()?,
+            Register::St => self.sound_timer = byte()?,
+            Register::Stack(level) => self.stack[level as usize & 0xF] = value,
+        }
+        Ok(())
+    }
+
+    // Returns the return addresses on the stack, from the oldest call to the latest one
+    pub fn get_stack(&self) -> &[u16] {
+        &self.stack[1..=self.sp]
+    }
+
+    // Returns how many bytes of memory the interpreter can access: 64KB with XO-CHIP, 4KB otherwise
+    pub fn get_memory_size(&self) -> usize {
+        if self.quirks.extended_memory {
+            MEMORY_SIZE
+        } else {
+            CHIP8_MEMORY_SIZE
+        }
+    }
+
+    // Returns the whole memory the interpreter can access
+    pub fn get_memory(&self) -> &[u8] {
+        &self.memory[..self.get_memory_size()]
+    }
+
+    // Writes bytes to memory starting at the chosen address, failing if they do not fit
+    pub fn set_memory(&mut self, address: usize, bytes: &[u8]) -> Result<(), String> {
+        if address.saturating_add(bytes.len()) > self.get_memory_size() {
+            return Err(format!("{} bytes at address {:#06X} do not fit in memory", bytes.len(), address));
+        }
+        // Unlike instructions, the debugger writing to memory does not trigger watchpoints
+        for (offset, byte) in bytes.iter().enumerate() {
+            self.store_byte(address + offset, *byte);
+        }
+        Ok(())
+    }
+
+    // Adds a watchpoint, unless the same one was already added
+    pub fn add_watchpoint(&mut self, watchpoint: Watchpoint) {
+        if !self.watchpoints.contains(&watchpoint) {
+            self.watchpoints.push(watchpoint);
+        }
+    }
+
+    // Removes a watchpoint, returning whether it had been added
+    pub fn remove_watchpoint(&mut self, watchpoint: &Watchpoint) -> bool {
+        let count = self.watchpoints.len();
+        self.watchpoints.retain(|w| w != watchpoint);
+        self.watchpoints.len() != count
+    }
+
+    // Returns the watchpoints, in the order they were added
+    pub fn get_watchpoints(&self) -> &[Watchpoint] {
+        &self.watchpoints
+    }
+
+    // Returns the watched accesses made by the last instruction run, in the order they were made
+    pub fn get_watch_hits(&self) -> &[WatchHit] {
+        &self.watch_hits
+    }
+
+    // Returns which keys of the keypad are pressed
+    pub fn get_keypad(&self) -> [bool; 16] {
+        self.keypad
     }
 
     // Sets keypad key of chosen index as pressed/released
@@ -138,107 +413,205 @@ impl Cpu {
         self.keypad[idx] = pressed;
     }
 
-    // Fetching the instruction from memory at the current PC
-    fn fetch(&mut self) -> u16 {
-        
+    /* Reading the two bytes at the chosen address as an opcode, if both fit in memory.
+     * It only peeks at memory without accessing it, so skips can tell the length of the next instruction */
+    fn read_opcode(&self, address: u16) -> Option<u16> {
+        let address = address as usize;
+
         /* An instruction is two bytes. Therefore, two consecutive bytes
          * from memory are read and combined into one 2-bytes instruction */
-        let op1 = self.memory[self.pc as usize];
-        let op2 = self.memory[(self.pc + 1) as usize];
+        let op1 = *self.get_memory().get(address)?;
+        let op2 = *self.get_memory().get(address + 1)?;
         
         /* To get the opcode, the first byte should be shifted to the left by 8 bits
          * and then combined with the second byte by an logical OR operation */
-        let instruction_opcode = (op1 as u16) << 8 | op2 as u16;
+        Some((op1 as u16) << 8 | op2 as u16)
+    }
+
+    // Fetching the instruction from memory at the current PC
+    fn fetch(&mut self) -> Result<u16, CpuError> {
+        let address = self.pc as usize;
+        if address + 2 > self.get_memory_size() {
+            return Err(CpuError::PcOutOfRange { pc: address, opcode: self.opcode });
+        }
 
-        instruction_opcode
+        /* An instruction is two bytes. Therefore, two consecutive bytes
+         * from memory are read and combined into one 2-bytes instruction */
+        let op1 = self.access_memory(Access::Execute, address, None)?;
+        let op2 = self.access_memory(Access::Execute, address + 1, None)?;
+        Ok((op1 as u16) << 8 | op2 as u16)
     }
 
-    // Decoding the instruction and calling its individual execution method
-    pub fn decode(&mut self, opcode: u16) {
-        
-        // The fourth nibble of the instruction (lowest 4 bits)
-        let n = opcode & 0x000F;
-        // The second byte of the instruction (lowest 8 bits)
-        let nn = opcode & 0x00FF;
-        // The second, third and fourth nibbles of the instruction (lowest 12 bits)
-        let nnn = opcode & 0x0FFF;
-
-        // The second nibble. Used as index for one of the 16 registers (Vx)
-        let x = ((opcode & 0x0F00) >> 8) as usize;
-
-        // The third nibble. Used as index for one of the 16 registers (Vy)
-        let y = ((opcode & 0x00F0) >> 4) as usize;
-
-        // Dividing opcode in nibbles to select the instruction to be executed based on them
-        let op1 = ((opcode & 0xF000) >> 12) as u8;
-        let op2 = ((opcode & 0x0F00) >> 8) as u8;
-        let op3 = ((opcode & 0x00F0) >> 4) as u8;
-        let op4 = (opcode & 0x000F) as u8;
-
-        // Control flow of instructions
-        match (op1, op2, op3, op4) {
-            (0x0, 0x0, 0xE, 0x0) => self.instruction_00e0(),
-            (0x0, 0x0, 0xE, 0xE) => self.instruction_00ee(),
-            (0x1, _, _, _) => self.instruction_1nnn(nnn),
-            (0x2, _, _, _) => self.instruction_2nnn(nnn),
-            (0x3, _, _, _) => self.instruction_3xnn(x, nn),
-            (0x4, _, _, _) => self.instruction_4xnn(x, nn),
-            (0x5, _, _, 0x0) => self.instruction_5xy0(x, y),
-            (0x6, _, _, _) => self.instruction_6xnn(x, nn),
-            (0x7, _, _, _) => self.instruction_7xnn(x, nn),
-            (0x8, _, _, 0x0) => self.instruction_8xy0(x, y),
-            (0x8, _, _, 0x1) => self.instruction_8xy1(x, y),
-            (0x8, _, _, 0x2) => self.instruction_8xy2(x, y),
-            (0x8, _, _, 0x3) => self.instruction_8xy3(x, y),
-            (0x8, _, _, 0x4) => self.instruction_8xy4(x, y),
-            (0x8, _, _, 0x5) => self.instruction_8xy5(x, y),
-            (0x8, _, _, 0x6) => self.instruction_8xy6(x),
-            (0x8, _, _, 0x7) => self.instruction_8xy7(x, y),
-            (0x8, _, _, 0xE) => self.instruction_8xye(x),
-            (0x9, _, _, 0x0) => self.instruction_9xy0(x, y),
-            (0xA, _, _, _) => self.instruction_annn(nnn),
-            (0xB, _, _, _) => self.instruction_bnnn(nnn),
-            (0xC, _, _, _) => self.instruction_cxnn(x, nn),
-            (0xD, _, _, _) => self.instruction_dxyn(x, y, n),
-            (0xE, _, 0x9, 0xE) => self.instruction_ex9e(x),
-            (0xE, _, 0xA, 0x1) => self.instruction_exa1(x),
-            (0xF, _, 0x0, 0x7) => self.instruction_fx07(x),
-            (0xF, _, 0x0, 0xA) => self.instruction_fx0a(x),
-            (0xF, _, 0x1, 0x5) => self.instruction_fx15(x),
-            (0xF, _, 0x1, 0x8) => self.instruction_fx18(x),
-            (0xF, _, 0x1, 0xE) => self.instruction_fx1e(x),
-            (0xF, _, 0x2, 0x9) => self.instruction_fx29(x),
-            (0xF, _, 0x3, 0x3) => self.instruction_fx33(x),
-            (0xF, _, 0x5, 0x5) => self.instruction_fx55(x),
-            (0xF, _, 0x6, 0x5) => self.instruction_fx65(x),
-            _ => panic!("Unknown instruction {:#06X}", opcode),
-        }
-
-    }
-
-    // Running the CPU cycle
-    pub fn run(&mut self) {
-
-        self.display_updated = false;
-
-        let opcode = self.fetch();
+    /* Accessing a byte of memory, writing the chosen value if there is one, and returning the byte
+     * as it was before. Every read, write and fetch made by instructions goes through here, which
+     * checks the address and records the access if a watchpoint covers it */
+    fn access_memory(&mut self, access: Access, address: usize, value: Option<u8>) -> Result<u8, CpuError> {
+        let old = *self.get_memory().get(address).ok_or(CpuError::MemoryOutOfBounds {
+            pc: self.instruction_address,
+            opcode: self.opcode,
+            address,
+        })?;
+
+        if let Some(value) = value {
+            self.store_byte(address, value);
+        }
 
-        // PC is incremented by 2 to be ready to fetch the next instruction 
-        self.pc += 2;
+        if self.watchpoints.iter().any(|w| w.matches(access, address as u16)) {
+            self.watch_hits.push(WatchHit {
+                access,
+                address: address as u16,
+                pc: self.instruction_address,
+                opcode: self.opcode,
+                old,
+                new: value.unwrap_or(old),
+            });
+        }
+        Ok(old)
+    }
+
+    // Writing a byte of memory, which forgets the instructions decoded over it
+    fn store_byte(&mut self, address: usize, value: u8) {
+        self.memory[address] = value;
+        self.decode_cache[address] = None;
+        if address > 0 {
+            self.decode_cache[address - 1] = None;
+        }
+    }
 
-        self.decode(opcode);
+    // Reading a byte of memory as data
+    fn read_memory(&mut self, address: usize) -> Result<u8, CpuError> {
+        self.access_memory(Access::Read, address, None)
+    }
+
+    // Writing a byte to memory
+    fn write_memory(&mut self, address: usize, value: u8) -> Result<(), CpuError> {
+        self.access_memory(Access::Write, address, Some(value))?;
+        Ok(())
+    }
 
-        /* If the time elapsed is greater or equal to the timer rate, the timers are decremented.
-         * This ensures the timer rate is kept at 60Hz.  */
-        if self.tick_period.elapsed() >= time::Duration::from_micros(TIMER_RATE) {
-            self.display_updated = true; // The display should update when the timers update
-            self.update_timers();
-            self.tick_period = time::Instant::now(); // Updating tick period after a cycle ends
+    // Checking that the memory range with the chosen length starting at the address fits in memory
+    fn check_memory_range(&self, address: usize, length: usize) -> Result<(), CpuError> {
+        let size = self.get_memory_size();
+        if address + length > size {
+            Err(CpuError::MemoryOutOfBounds {
+                pc: self.instruction_address,
+                opcode: self.opcode,
+                address: address.max(size),
+            })
+        } else {
+            Ok(())
         }
     }
 
-    // Decrementing timers when they are greater than zero
-    fn update_timers(&mut self) {
+    /* Decoding the opcode and executing the instruction.
+     * The PC is expected to already point to the next instruction */
+    pub fn execute_opcode(&mut self, opcode: u16) -> Result<(), CpuError> {
+        self.instruction_address = self.pc.wrapping_sub(2);
+        self.opcode = opcode;
+        self.watch_hits.clear();
+
+        let instruction = Instruction::decode(opcode)
+            .map_err(|e| CpuError::UnknownOpcode { pc: self.instruction_address, opcode: e.opcode })?;
+        self.execute(instruction)
+    }
+
+    /* Calling the individual execution method of a decoded instruction.
+     * The PC is expected to already point to the next instruction */
+    pub fn execute(&mut self, instruction: Instruction) -> Result<(), CpuError> {
+        match instruction {
+            Instruction::ScrollDown { n } => self.instruction_00cn(n as u16),
+            Instruction::ScrollUp { n } => self.instruction_00dn(n as u16),
+            Instruction::ClearScreen => self.instruction_00e0(),
+            Instruction::Return => self.instruction_00ee()?,
+            Instruction::ScrollRight => self.instruction_00fb(),
+            Instruction::ScrollLeft => self.instruction_00fc(),
+            Instruction::Exit => self.instruction_00fd(),
+            Instruction::LowRes => self.instruction_00fe(),
+            Instruction::HighRes => self.instruction_00ff(),
+            Instruction::Jump { nnn } => self.instruction_1nnn(nnn),
+            Instruction::Call { nnn } => self.instruction_2nnn(nnn)?,
+            Instruction::SkipIfEqualByte { x, nn } => self.instruction_3xnn(x as usize, nn as u16)?,
+            Instruction::SkipIfNotEqualByte { x, nn } => self.instruction_4xnn(x as usize, nn as u16)?,
+            Instruction::SkipIfEqual { x, y } => self.instruction_5xy0(x as usize, y as usize)?,
+            Instruction::StoreRange { x, y } => self.instruction_5xy2(x as usize, y as usize)?,
+            Instruction::LoadRange { x, y } => self.instruction_5xy3(x as usize, y as usize)?,
+            Instruction::SetByte { x, nn } => self.instruction_6xnn(x as usize, nn as u16),
+            Instruction::AddByte { x, nn } => self.instruction_7xnn(x as usize, nn as u16),
+            Instruction::Set { x, y } => self.instruction_8xy0(x as usize, y as usize),
+            Instruction::Or { x, y } => self.instruction_8xy1(x as usize, y as usize),
+            Instruction::And { x, y } => self.instruction_8xy2(x as usize, y as usize),
+            Instruction::Xor { x, y } => self.instruction_8xy3(x as usize, y as usize),
+            Instruction::Add { x, y } => self.instruction_8xy4(x as usize, y as usize),
+            Instruction::Sub { x, y } => self.instruction_8xy5(x as usize, y as usize),
+            Instruction::ShiftRight { x, y } => self.instruction_8xy6(x as usize, y as usize),
+            Instruction::SubReverse { x, y } => self.instruction_8xy7(x as usize, y as usize),
+            Instruction::ShiftLeft { x, y } => self.instruction_8xye(x as usize, y as usize),
+            Instruction::SkipIfNotEqual { x, y } => self.instruction_9xy0(x as usize, y as usize)?,
+            Instruction::SetIndex { nnn } => self.instruction_annn(nnn),
+            Instruction::JumpOffset { x, nnn } => self.instruction_bnnn(x as usize, nnn),
+            Instruction::Random { x, nn } => self.instruction_cxnn(x as usize, nn as u16),
+            Instruction::Draw { x, y, n } => self.instruction_dxyn(x as usize, y as usize, n as u16)?,
+            Instruction::SkipIfKey { x } => self.instruction_ex9e(x as usize)?,
+            Instruction::SkipIfNotKey { x } => self.instruction_exa1(x as usize)?,
+            Instruction::SetIndexLong => self.instruction_f000()?,
+            Instruction::SelectPlanes { n } => self.instruction_fn01(n as usize),
+            Instruction::LoadAudioPattern => self.instruction_f002()?,
+            Instruction::GetDelayTimer { x } => self.instruction_fx07(x as usize),
+            Instruction::WaitKey { x } => self.instruction_fx0a(x as usize),
+            Instruction::SetDelayTimer { x } => self.instruction_fx15(x as usize),
+            Instruction::SetSoundTimer { x } => self.instruction_fx18(x as usize),
+            Instruction::AddIndex { x } => self.instruction_fx1e(x as usize),
+            Instruction::SetIndexFont { x } => self.instruction_fx29(x as usize),
+            Instruction::SetIndexBigFont { x } => self.instruction_fx30(x as usize),
+            Instruction::StoreBcd { x } => self.instruction_fx33(x as usize)?,
+            Instruction::SetPitch { x } => self.instruction_fx3a(x as usize),
+            Instruction::Store { x } => self.instruction_fx55(x as usize)?,
+            Instruction::Load { x } => self.instruction_fx65(x as usize)?,
+            Instruction::SaveFlags { x } => self.instruction_fx75(x as usize),
+            Instruction::LoadFlags { x } => self.instruction_fx85(x as usize),
+        }
+
+        Ok(())
+    }
+
+    /* Returns the instruction at the chosen address, decoding it only the first time it is run.
+     * Writing to memory forgets the instructions decoded at the written addresses */
+    fn decode_cached(&mut self, address: u16, opcode: u16) -> Result<Instruction, CpuError> {
+        if let Some(instruction) = self.decode_cache[address as usize] {
+            return Ok(instruction);
+        }
+        let instruction = Instruction::decode(opcode)
+            .map_err(|e| CpuError::UnknownOpcode { pc: address, opcode: e.opcode })?;
+        self.decode_cache[address as usize] = Some(instruction);
+        Ok(instruction)
+    }
+
+    /* Running the CPU cycle, which executes a single instruction.
+     * The timers are not touched, since they are decremented once per frame by update_timers */
+    pub fn run(&mut self) -> Result<(), CpuError> {
+
+        self.instruction_address = self.pc;
+        self.watch_hits.clear();
+        let opcode = self.fetch()?;
+        self.opcode = opcode;
+
+        // The opcode of the fetch accesses is only known once both bytes are fetched
+        for hit in &mut self.watch_hits {
+            hit.opcode = opcode;
+        }
+
+        // PC is incremented by 2 to be ready to fetch the next instruction 
+        self.advance_pc(2)?;
+
+        let instruction = self.decode_cached(self.instruction_address, opcode)?;
+        self.execute(instruction)
+    }
+
+    /* Decrementing timers when they are greater than zero.
+     * It should be called once per frame (60 times per emulated second), which also refreshes the display */
+    pub fn update_timers(&mut self) {
+        self.vblank = true;
+
         if self.delay_timer > 0 {
             self.delay_timer -= 1;
         }
@@ -250,18 +623,102 @@ impl Cpu {
         }
     }
 
+    /* Skips the next instruction.
+     * The XO-CHIP F000 NNNN instruction is four bytes long, so it is skipped entirely */
+    fn skip_next_instruction(&mut self) -> Result<(), CpuError> {
+        let length = if self.read_opcode(self.pc) == Some(0xF000) { 4 } else { 2 };
+        self.advance_pc(length)
+    }
+
+    // Moves the PC forward by the chosen number of bytes, failing if it would go past the last address
+    fn advance_pc(&mut self, length: u16) -> Result<(), CpuError> {
+        self.pc = self.pc.checked_add(length).ok_or(CpuError::PcOutOfRange {
+            pc: self.pc as usize + length as usize,
+            opcode: self.opcode,
+        })?;
+        Ok(())
+    }
+
+    /* Moves the pixels of the selected planes by (dx, dy) pixels.
+     * Pixels moved out of the display are lost and the uncovered area is left unset */
+    fn scroll_display(&mut self, dx: isize, dy: isize) {
+        let (width, height) = self.get_display_size();
+        let previous_display = self.display;
+
+        for y_coord in 0..height {
+            for x_coord in 0..width {
+                let source_x = x_coord as isize - dx;
+                let source_y = y_coord as isize - dy;
+
+                let source_pixel = if (0..width as isize).contains(&source_x) && (0..height as isize).contains(&source_y) {
+                    previous_display[source_y as usize * width + source_x as usize]
+                } else {
+                    0
+                };
+
+                let index = y_coord * width + x_coord;
+                self.display[index] = (self.display[index] & !self.planes) | (source_pixel & self.planes);
+            }
+        }
+    }
+
     /* EXECUTION OF INDIVIDUAL INSTRUCTIONS */
 
-    // 	Clears the display
+    // Scrolls the display down by N pixels
+    fn instruction_00cn(&mut self, n: u16) {
+        self.scroll_display(0, n as isize);
+    }
+
+    // Scrolls the display up by N pixels
+    fn instruction_00dn(&mut self, n: u16) {
+        self.scroll_display(0, -(n as isize));
+    }
+
+    // 	Clears the selected planes of the display
     fn instruction_00e0(&mut self) {
-        self.display = [false; DISPLAY_WIDTH * DISPLAY_HEIGHT];
+        for pixel in self.display.iter_mut() {
+            *pixel &= !self.planes;
+        }
     }
 
     /* Returns from a subroutine, setting the PC to the address at the top of the stack
      * and then subtracting 1 from the stack pointer. */
-    fn instruction_00ee(&mut self) {
+    fn instruction_00ee(&mut self) -> Result<(), CpuError> {
+        if self.sp == 0 {
+            return Err(CpuError::StackUnderflow { pc: self.instruction_address, opcode: self.opcode });
+        }
         self.pc = self.stack[self.sp];
         self.sp -= 1;
+        Ok(())
+    }
+
+    // Scrolls the display right by 4 pixels
+    fn instruction_00fb(&mut self) {
+        self.scroll_display(4, 0);
+    }
+
+    // Scrolls the display left by 4 pixels
+    fn instruction_00fc(&mut self) {
+        self.scroll_display(-4, 0);
+    }
+
+    /* Exits the interpreter.
+     * The PC loops back to this instruction, so the program does not continue running */
+    fn instruction_00fd(&mut self) {
+        self.exited = true;
+        self.pc = self.pc.wrapping_sub(2);
+    }
+
+    // Disables high-resolution mode, going back to 64x32 pixels, and clears the display
+    fn instruction_00fe(&mut self) {
+        self.hires = false;
+        self.display = [0; DISPLAY_BUFFER_SIZE];
+    }
+
+    // Enables high-resolution mode (128x64 pixels) and clears the display
+    fn instruction_00ff(&mut self) {
+        self.hires = true;
+        self.display = [0; DISPLAY_BUFFER_SIZE];
     }
 
     // Jumps to address NNN
@@ -273,31 +730,64 @@ impl Cpu {
      * on the top of the stack.
      * 
      * The PC is then set to NNN. */
-    fn instruction_2nnn(&mut self, nnn: u16) {
+    fn instruction_2nnn(&mut self, nnn: u16) -> Result<(), CpuError> {
+        if self.sp + 1 >= self.stack.len() {
+            return Err(CpuError::StackOverflow { pc: self.instruction_address, opcode: self.opcode });
+        }
         self.sp += 1;
         self.stack[self.sp] = self.pc;
         self.pc = nnn;
+        Ok(())
     }
 
     // Skips the next instruction if Vx equals NN
-    fn instruction_3xnn(&mut self, x: usize, nn: u16) {
+    fn instruction_3xnn(&mut self, x: usize, nn: u16) -> Result<(), CpuError> {
         if self.v[x] as u16 == nn {
-            self.pc += 2;
+            self.skip_next_instruction()?;
         }
+        Ok(())
     }
 
     // Skips the next instruction if Vx does not equal NN
-    fn instruction_4xnn(&mut self, x: usize, nn: u16) {
+    fn instruction_4xnn(&mut self, x: usize, nn: u16) -> Result<(), CpuError> {
         if self.v[x] as u16 != nn {
-            self.pc += 2;
+            self.skip_next_instruction()?;
         }
+        Ok(())
     }
 
     // Skips the next instruction if Vx equals Vy
-    fn instruction_5xy0(&mut self, x: usize, y: usize) {
+    fn instruction_5xy0(&mut self, x: usize, y: usize) -> Result<(), CpuError> {
         if self.v[x] == self.v[y] {
-            self.pc += 2;
+            self.skip_next_instruction()?;
         }
+        Ok(())
+    }
+
+    /* Stores registers Vx through Vy in memory starting at location I, without changing I.
+     * If X is greater than Y, the registers are stored in reverse order */
+    fn instruction_5xy2(&mut self, x: usize, y: usize) -> Result<(), CpuError> {
+        let count = x.abs_diff(y) + 1;
+        self.check_memory_range(self.i as usize, count)?;
+
+        for offset in 0..count {
+            let register = if x <= y { x + offset } else { x - offset };
+            self.write_memory(self.i as usize + offset, self.v[register])?;
+        }
+        Ok(())
+    }
+
+    /* Reads registers Vx through Vy from memory starting at location I, without changing I.
+     * If X is greater than Y, the registers are read in reverse order */
+    fn instruction_5xy3(&mut self, x: usize, y: usize) -> Result<(), CpuError> {
+        let count = x.abs_diff(y) + 1;
+        self.check_memory_range(self.i as usize, count)?;
+
+        for offset in 0..count {
+            let register = if x <= y { x + offset } else { x - offset };
+            self.v[register] = self.read_memory(self.i as usize + offset)?;
+        }
+        Ok(())
     }
 
     // Sets Vx to NN
@@ -315,19 +805,28 @@ impl Cpu {
         self.v[x] = self.v[y];
     }
 
-    // Sets Vx to Vx OR Vy
+    // Sets Vx to Vx OR Vy. With the VF reset quirk, VF is set to 0
     fn instruction_8xy1(&mut self, x: usize, y: usize) {
-        self.v[x] = self.v[x] | self.v[y];
+        self.v[x] |= self.v[y];
+        if self.quirks.vf_reset {
+            self.v[0xF] = 0;
+        }
     }
 
-    // Sets Vx to Vx AND Vy
+    // Sets Vx to Vx AND Vy. With the VF reset quirk, VF is set to 0
     fn instruction_8xy2(&mut self, x: usize, y: usize) {
-        self.v[x] = self.v[x] & self.v[y];
+        self.v[x] &= self.v[y];
+        if self.quirks.vf_reset {
+            self.v[0xF] = 0;
+        }
     }
     
-    // Sets Vx to Vx XOR Vy
+    // Sets Vx to Vx XOR Vy. With the VF reset quirk, VF is set to 0
     fn instruction_8xy3(&mut self, x: usize, y: usize) {
-        self.v[x] = self.v[x] ^ self.v[y];
+        self.v[x] ^= self.v[y];
+        if self.quirks.vf_reset {
+            self.v[0xF] = 0;
+        }
     }
 
     // Adds Vy to Vx. VF is set to 1 when there's a carry, and to 0 when there is not
@@ -350,10 +849,12 @@ impl Cpu {
         self.v[x] = self.v[x].wrapping_sub(self.v[y]);
     }
 
-    // Stores the least significant bit of Vx in VF and then shifts Vx to the right by 1
-    fn instruction_8xy6(&mut self, x: usize) {
-        self.v[0xF] = self.v[x] & 1; // Getting LSB
-        self.v[x] >>= 1;
+    /* Stores the least significant bit of Vx in VF and then shifts Vx to the right by 1.
+     * With the shift quirk, Vy is shifted instead and the result is stored in Vx */
+    fn instruction_8xy6(&mut self, x: usize, y: usize) {
+        let value = if self.quirks.shift_uses_vy { self.v[y] } else { self.v[x] };
+        self.v[x] = value >> 1;
+        self.v[0xF] = value & 1; // Getting LSB
     }
 
     // Sets Vx to Vy minus Vx. VF is set to 0 when there's a borrow, and 1 when there is not
@@ -366,17 +867,20 @@ impl Cpu {
         self.v[x] = self.v[y].wrapping_sub(self.v[x]);
     }
 
-    // Stores the most significant bit of Vx in VF and then shifts Vx to the left by 1
-    fn instruction_8xye(&mut self, x: usize) {
-        self.v[0xF] = (self.v[x] >> 7) & 1; // Getting MSB
-        self.v[x] <<= 1;
+    /* Stores the most significant bit of Vx in VF and then shifts Vx to the left by 1.
+     * With the shift quirk, Vy is shifted instead and the result is stored in Vx */
+    fn instruction_8xye(&mut self, x: usize, y: usize) {
+        let value = if self.quirks.shift_uses_vy { self.v[y] } else { self.v[x] };
+        self.v[x] = value << 1;
+        self.v[0xF] = (value >> 7) & 1; // Getting MSB
     }
 
     // Skips the next instruction if Vx does not equal Vy
-    fn instruction_9xy0(&mut self, x: usize, y: usize) {
+    fn instruction_9xy0(&mut self, x: usize, y: usize) -> Result<(), CpuError> {
         if self.v[x] != self.v[y] {
-            self.pc += 2;
+            self.skip_next_instruction()?;
         }
+        Ok(())
     }
 
     // 	Sets I to the address NNN
@@ -384,17 +888,20 @@ impl Cpu {
         self.i = nnn;
     }
 
-    // Jumps to the address NNN plus V0
-    fn instruction_bnnn(&mut self, nnn: u16) {
-        self.pc = nnn + self.v[0] as u16;
+    /* Jumps to the address NNN plus V0.
+     * With the jump quirk, it jumps to the address XNN plus Vx instead */
+    fn instruction_bnnn(&mut self, x: usize, nnn: u16) {
+        let offset = if self.quirks.jump_uses_vx { self.v[x] } else { self.v[0] };
+        self.pc = nnn + offset as u16;
     }
 
     // Sets Vx to the result of a bitwise AND operation on a random number from 0 to 255 and NN
     fn instruction_cxnn(&mut self, x: usize, nn: u16) {
-        self.v[x] = (nn as u8) & rand::thread_rng().gen::<u8>();
+        self.v[x] = (nn as u8) & self.rng.gen::<u8>();
     }
 
     /* Draws a sprite starting at coordinate (Vx, Vy) that has a width of 8 pixels and a height of N pixels.
+     * If N is 0, a SUPER-CHIP 16x16 sprite is drawn instead, where each row is made of two bytes.
      * 
      * Sprites are XORed onto the existing display.
      * 
@@ -402,59 +909,128 @@ impl Cpu {
      * I value does not change after the execution of this instruction.
      * 
      * If the sprite is positioned so part of it is outside the coordinates of the display,
-     * it wraps around to the opposite side of the display.
+     * it wraps around to the opposite side of the display. With the clipping quirk, the part
+     * outside the display is not drawn instead.
+     * 
+     * With the display wait quirk, the instruction is repeated until the display refreshes.
      * 
      * VF is set to 1 if any display pixels are flipped from set to unset when the sprite is drawn,
      * and to 0 if that does not happen. */
-    fn instruction_dxyn(&mut self, x: usize, y: usize, n: u16) {
+    fn instruction_dxyn(&mut self, x: usize, y: usize, n: u16) -> Result<(), CpuError> {
 
-        let height = n as usize;
+        if self.quirks.display_wait {
+            if !self.vblank {
+                self.pc = self.pc.wrapping_sub(2); // PC loops back to this instruction until the display refreshes
+                return Ok(());
+            }
+            self.vblank = false;
+        }
 
-        // Initially, VF is set to 0
-        self.v[0xF] = 0;
+        let (display_width, display_height) = self.get_display_size();
+        let (sprite_width, sprite_height) = if n == 0 { (16, 16) } else { (8, n as usize) };
 
-        for byte in 0..height {
-            let y_coord = (self.v[y] as usize + byte) % DISPLAY_HEIGHT;
+        // The sprite data of every selected plane should fit in memory
+        let sprite_size = sprite_height * sprite_width / 8;
+        self.check_memory_range(self.i as usize, sprite_size * self.planes.count_ones() as usize)?;
 
-            // Accessing the current row of sprite pixels from RAM memory
-            let pixels = self.memory[self.i as usize + byte];
+        // The starting coordinates always wrap around the display
+        let x_start = self.v[x] as usize % display_width;
+        let y_start = self.v[y] as usize % display_height;
 
-            for bit in 0..8 {   
-                let x_coord = (self.v[x] as usize + bit) % DISPLAY_WIDTH;
+        // Initially, VF is set to 0
+        self.v[0xF] = 0;
 
-                /* Accessing specific pixel from the current row of sprite pixels
-                 * (most significant to least significant bit) */
-                let current_pixel = (pixels >> (7 - bit)) & 0x001;
-                
-                // Current sprite pixel is on
-                if current_pixel == 1 {
+        /* Each selected plane is drawn with its own sprite data.
+         * The sprite of the second plane is stored right after the sprite of the first one */
+        let mut sprite_address = self.i as usize;
 
-                    // Getting index of current display pixel for the 1D display array
-                    let index = (DISPLAY_WIDTH * y_coord) + x_coord;
+        for plane in [1, 2] {
+            if self.planes & plane == 0 {
+                continue;
+            }
 
-                    /* If the sprite pixel and display pixel are both on, the display pixel will flip
-                     * from set to unset and VF should be set to 1 */
-                    if self.display[index] == true {
-                        self.v[0xF] = 1;
+            for row in 0..sprite_height {
+                if self.quirks.clip_sprites && y_start + row >= display_height {
+                    break;
+                }
+                let y_coord = (y_start + row) % display_height;
+
+                /* Accessing the current row of sprite pixels from RAM memory.
+                 * 8 pixels wide rows are stored in the upper byte */
+                let pixels = if sprite_width == 16 {
+                    let address = sprite_address + row * 2;
+                    (self.read_memory(address)? as u16) << 8 | self.read_memory(address + 1)? as u16
+                } else {
+                    (self.read_memory(sprite_address + row)? as u16) << 8
+                };
+
+                for bit in 0..sprite_width {   
+                    if self.quirks.clip_sprites && x_start + bit >= display_width {
+                        break;
+                    }
+                    let x_coord = (x_start + bit) % display_width;
+
+                    /* Accessing specific pixel from the current row of sprite pixels
+                     * (most significant to least significant bit) */
+                    let current_pixel = (pixels >> (15 - bit)) & 0x001;
+                    
+                    // Current sprite pixel is on
+                    if current_pixel == 1 {
+
+                        // Getting index of current display pixel for the 1D display array
+                        let index = (display_width * y_coord) + x_coord;
+
+                        /* If the sprite pixel and display pixel are both on, the display pixel will flip
+                         * from set to unset and VF should be set to 1 */
+                        if self.display[index] & plane != 0 {
+                            self.v[0xF] = 1;
+                        }
+                        self.display[index] ^= plane; // XOR operation that flips the display pixel
                     }
-                    self.display[index] ^= true; // XOR operation that flips the display pixel
                 }
             }
+
+            sprite_address += sprite_size;
         }
+        Ok(())
     }
 
     // Skips the next instruction if the key stored in Vx is pressed 
-    fn instruction_ex9e(&mut self, x: usize) {
-        if self.keypad[self.v[x] as usize] {
-            self.pc += 2;
+    fn instruction_ex9e(&mut self, x: usize) -> Result<(), CpuError> {
+        if self.keypad[(self.v[x] & 0xF) as usize] {
+            self.skip_next_instruction()?;
         }
+        Ok(())
     }
 
     // Skips the next instruction if the key stored in Vx is not pressed
-    fn instruction_exa1(&mut self, x: usize) {
-        if !self.keypad[self.v[x] as usize] {
-            self.pc += 2;
+    fn instruction_exa1(&mut self, x: usize) -> Result<(), CpuError> {
+        if !self.keypad[(self.v[x] & 0xF) as usize] {
+            self.skip_next_instruction()?;
+        }
+        Ok(())
+    }
+
+    // Sets I to the 16-bit address NNNN, stored in the two bytes after this instruction
+    fn instruction_f000(&mut self) -> Result<(), CpuError> {
+        self.i = self.fetch()?;
+        self.advance_pc(2)?;
+        Ok(())
+    }
+
+    // Selects the bitplanes N (0 to 3) used by drawing, clearing and scrolling
+    fn instruction_fn01(&mut self, n: usize) {
+        self.planes = (n & 0x3) as u8;
+    }
+
+    // Loads the 16 bytes starting at location I into the audio pattern buffer
+    fn instruction_f002(&mut self) -> Result<(), CpuError> {
+        let address = self.i as usize;
+        self.check_memory_range(address, AUDIO_PATTERN_SIZE)?;
+        for idx in 0..AUDIO_PATTERN_SIZE {
+            self.audio_pattern[idx] = self.read_memory(address + idx)?;
         }
+        Ok(())
     }
 
     // 	Sets Vx to the value of the delay timer
@@ -468,7 +1044,7 @@ impl Cpu {
 
         for idx in 0..self.keypad.len() {
             if self.keypad[idx] {
-                self.pc += 2; // A keypress is found; PC can continue to the next instruction
+                // A keypress is found; PC already points to the next instruction
                 self.v[x] = idx as u8;
                 keypress = true;
                 break;
@@ -476,7 +1052,7 @@ impl Cpu {
         }
 
         if !keypress {
-            self.pc -= 2; // If no keypresses are detected, PC loops back to this instruction
+            self.pc = self.pc.wrapping_sub(2); // If no keypresses are detected, PC loops back to this instruction
         }
     }
 
@@ -492,37 +1068,204 @@ impl Cpu {
 
     // Adds Vx to I
     fn instruction_fx1e(&mut self, x: usize) {
-        self.i += self.v[x] as u16;
+        self.i = self.i.wrapping_add(self.v[x] as u16);
     }
 
     // Sets I to the location of the sprite for the character in Vx
     fn instruction_fx29(&mut self, x: usize) {
 
         // Multiplying by 5 because each sprite takes up 5 bytes in memory
-        self.i = 0x50 + (self.v[x] * 5) as u16; // 0x50 is the initial address where fonts are stored in memory
+        self.i = 0x50 + (self.v[x] & 0xF) as u16 * 5; // 0x50 is the initial address where fonts are stored in memory
+    }
+
+    // Sets I to the location of the SUPER-CHIP big sprite for the character in Vx
+    fn instruction_fx30(&mut self, x: usize) {
+
+        // Multiplying by 10 because each big sprite takes up 10 bytes in memory
+        self.i = BIG_FONT_ADDRESS as u16 + (self.v[x] & 0xF) as u16 * 10;
     }
 
     // Stores the binary-coded decimal representation of Vx in memory locations I, I+1, and I+2
-    fn instruction_fx33(&mut self, x: usize) {
-        self.memory[self.i as usize] = self.v[x] / 100;
-        self.memory[self.i as usize + 1] = (self.v[x] / 10) % 10;
-        self.memory[self.i as usize + 2] = self.v[x] % 10;
+    fn instruction_fx33(&mut self, x: usize) -> Result<(), CpuError> {
+        self.check_memory_range(self.i as usize, 3)?;
+        self.write_memory(self.i as usize, self.v[x] / 100)?;
+        self.write_memory(self.i as usize + 1, (self.v[x] / 10) % 10)?;
+        self.write_memory(self.i as usize + 2, self.v[x] % 10)?;
+        Ok(())
     }
 
-    // Store registers V0 through Vx in memory starting at location I
-    fn instruction_fx55(&mut self, x: usize) {
+    // Sets the pitch register to Vx, which changes the playback rate of the audio pattern
+    fn instruction_fx3a(&mut self, x: usize) {
+        self.pitch = self.v[x];
+    }
+
+    /* Store registers V0 through Vx in memory starting at location I.
+     * I is then incremented as the load/store quirk chooses */
+    fn instruction_fx55(&mut self, x: usize) -> Result<(), CpuError> {
+        self.check_memory_range(self.i as usize, x + 1)?;
         for idx in 0..(x + 1) {
-            self.memory[self.i as usize + idx] = self.v[idx];
+            self.write_memory(self.i as usize + idx, self.v[idx])?;
         }
+        self.increment_i_after_load_store(x);
+        Ok(())
     }
 
-    // Read registers V0 through Vx from memory starting at location I
-    fn instruction_fx65(&mut self, x: usize) {
+    /* Read registers V0 through Vx from memory starting at location I.
+     * I is then incremented as the load/store quirk chooses */
+    fn instruction_fx65(&mut self, x: usize) -> Result<(), CpuError> {
+        self.check_memory_range(self.i as usize, x + 1)?;
         for idx in 0..(x + 1) {
-            self.v[idx] = self.memory[self.i as usize + idx];
+            self.v[idx] = self.read_memory(self.i as usize + idx)?;
+        }
+        self.increment_i_after_load_store(x);
+        Ok(())
+    }
+
+    // Moves I past the registers V0 through Vx stored/loaded by FX55/FX65, depending on the quirk
+    fn increment_i_after_load_store(&mut self, x: usize) {
+        match self.quirks.load_store_increment {
+            LoadStoreIncrement::None => (),
+            LoadStoreIncrement::X => self.i = self.i.wrapping_add(x as u16),
+            LoadStoreIncrement::XPlusOne => self.i = self.i.wrapping_add(x as u16 + 1),
         }
     }
 
+    // Stores registers V0 through Vx in the RPL user flags
+    fn instruction_fx75(&mut self, x: usize) {
+        self.rpl_flags[..(x + 1)].copy_from_slice(&self.v[..(x + 1)]);
+    }
+
+    // Reads registers V0 through Vx from the RPL user flags
+    fn instruction_fx85(&mut self, x: usize) {
+        self.v[..(x + 1)].copy_from_slice(&self.rpl_flags[..(x + 1)]);
+    }
+
+    // Writes the whole machine state to a save state
+    pub(crate) fn write_state(&self, writer: &mut StateWriter) {
+        writer.write_bytes(&self.memory);
+        writer.write_u16(self.pc);
+        writer.write_bytes(&self.v);
+        writer.write_u16(self.i);
+        for address in self.stack {
+            writer.write_u16(address);
+        }
+        writer.write_u8(self.sp as u8);
+        writer.write_u16(self.instruction_address);
+        writer.write_u16(self.opcode);
+        writer.write_u8(self.delay_timer);
+        writer.write_u8(self.sound_timer);
+        writer.write_bytes(&self.display);
+        writer.write_u8(self.planes);
+        writer.write_bool(self.hires);
+        for key in self.keypad {
+            writer.write_bool(key);
+        }
+        writer.write_bool(self.should_beep);
+
+        writer.write_bool(self.quirks.shift_uses_vy);
+        // The load/store increment used to be a flag, so 1 still stands for X + 1
+        writer.write_u8(match self.quirks.load_store_increment {
+            LoadStoreIncrement::None => 0,
+            LoadStoreIncrement::XPlusOne => 1,
+            LoadStoreIncrement::X => 2,
+        });
+        writer.write_bool(self.quirks.jump_uses_vx);
+        writer.write_bool(self.quirks.vf_reset);
+        writer.write_bool(self.quirks.clip_sprites);
+        writer.write_bool(self.quirks.display_wait);
+        writer.write_bool(self.quirks.extended_memory);
+
+        writer.write_bool(self.vblank);
+        writer.write_bytes(&self.rpl_flags);
+        writer.write_bool(self.exited);
+        writer.write_bytes(&self.audio_pattern);
+        writer.write_u8(self.pitch);
+
+        // The generator is restored from its seed, stream and position in the stream
+        writer.write_bytes(&self.rng.get_seed());
+        writer.write_u64(self.rng.get_stream());
+        writer.write_u128(self.rng.get_word_pos());
+    }
+
+    /* Reads the whole machine state from a save state, in the order it was written.
+     * If it fails, the CPU may be partially overwritten */
+    pub(crate) fn read_state(&mut self, reader: &mut StateReader) -> Result<(), StateError> {
+        self.memory.copy_from_slice(reader.read_bytes(MEMORY_SIZE)?);
+        self.decode_cache.fill(None);
+        self.watch_hits.clear();
+        self.pc = reader.read_u16()?;
+        self.v = reader.read_array()?;
+        self.i = reader.read_u16()?;
+        for address in self.stack.iter_mut() {
+            *address = reader.read_u16()?;
+        }
+        self.sp = reader.read_u8()? as usize;
+        if self.sp >= self.stack.len() {
+            return Err(StateError::InvalidFormat);
+        }
+        self.instruction_address = reader.read_u16()?;
+        self.opcode = reader.read_u16()?;
+        self.delay_timer = reader.read_u8()?;
+        self.sound_timer = reader.read_u8()?;
+        self.display.copy_from_slice(reader.read_bytes(DISPLAY_BUFFER_SIZE)?);
+        self.planes = reader.read_u8()?;
+        self.hires = reader.read_bool()?;
+        for key in self.keypad.iter_mut() {
+            *key = reader.read_bool()?;
+        }
+        self.should_beep = reader.read_bool()?;
+
+        self.quirks = Quirks {
+            shift_uses_vy: reader.read_bool()?,
+            load_store_increment: match reader.read_u8()? {
+                0 => LoadStoreIncrement::None,
+                1 => LoadStoreIncrement::XPlusOne,
+                2 => LoadStoreIncrement::X,
+                _ => return Err(StateError::InvalidFormat),
+            },
+            jump_uses_vx: reader.read_bool()?,
+            vf_reset: reader.read_bool()?,
+            clip_sprites: reader.read_bool()?,
+            display_wait: reader.read_bool()?,
+            extended_memory: reader.read_bool()?,
+        };
+
+        self.vblank = reader.read_bool()?;
+        self.rpl_flags = reader.read_array()?;
+        self.exited = reader.read_bool()?;
+        self.audio_pattern = reader.read_array()?;
+        self.pitch = reader.read_u8()?;
+
+        self.rng = ChaCha8Rng::from_seed(reader.read_array()?);
+        self.rng.set_stream(reader.read_u64()?);
+        self.rng.set_word_pos(reader.read_u128()?);
+        Ok(())
+    }
+
+}
+
+impl Default for Cpu {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Formats the registers, timers and stack, so the machine state can be shown to the user
+impl fmt::Display for Cpu {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "PC: {:#06X}  I: {:#06X}  SP: {}  DT: {}  ST: {}", self.pc, self.i, self.sp, self.delay_timer, self.sound_timer)?;
+
+        for (idx, value) in self.v.iter().enumerate() {
+            write!(f, "V{:X}: {:#04X}", idx, value)?;
+            write!(f, "{}", if idx % 8 == 7 { "\n" } else { "  " })?;
+        }
+
+        write!(f, "Stack:")?;
+        for address in &self.stack[1..=self.sp] {
+            write!(f, " {:#06X}", address)?;
+        }
+        Ok(())
+    }
 }
 
 #[cfg(test)]
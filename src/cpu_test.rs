@@ -17,10 +17,15 @@ fn test_cpu_initial_state() {
         [0xF0, 0x80, 0xF0, 0x80, 0x80]
     );
 
+    // Testing first and last lines of big font data in memory
+    const BIG_FONT_FINAL_IDX: usize = BIG_FONT_ADDRESS + SCHIP_BIG_FONT.len();
+    assert_eq!(cpu.memory[BIG_FONT_ADDRESS..(BIG_FONT_ADDRESS + 2)], [0x3C, 0x7E]);
+    assert_eq!(cpu.memory[(BIG_FONT_FINAL_IDX - 2)..BIG_FONT_FINAL_IDX], [0xC0, 0xC0]);
+
     // Testing if memory after font data is correct
     assert_eq!(
-        cpu.memory[(0x9F + 1)..],
-        [0; (MEMORY_SIZE - FONT_FINAL_IDX)]
+        cpu.memory[BIG_FONT_FINAL_IDX..],
+        [0; (MEMORY_SIZE - BIG_FONT_FINAL_IDX)]
     );
     assert_eq!(cpu.pc, 0x200);
     assert_eq!(cpu.v, [0; 16]);
@@ -29,11 +34,13 @@ fn test_cpu_initial_state() {
     assert_eq!(cpu.sp, 0);
     assert_eq!(cpu.delay_timer, 0);
     assert_eq!(cpu.sound_timer, 0);
-    assert_eq!(cpu.display, [false; DISPLAY_WIDTH * DISPLAY_HEIGHT]);
+    assert_eq!(cpu.display, [0; DISPLAY_BUFFER_SIZE]);
+    assert!(!cpu.hires);
     assert_eq!(cpu.keypad, [false; 16]);
 }
 
 #[test]
+#[allow(clippy::useless_vec)]
 fn test_rom_loading() -> Result<(), String> {
     let mut cpu = Cpu::new();
     cpu.load_rom_in_memory(&vec![1, 2, 3, 4])?;
@@ -46,33 +53,183 @@ fn test_rom_loading() -> Result<(), String> {
     Ok(())
 }
 
+#[test]
+fn test_rom_loading_memory_size() -> Result<(), String> {
+    // ROMs fill the 4KB of CHIP-8 from 0x200, or the 64KB of XO-CHIP
+    let mut cpu = Cpu::new();
+    assert!(cpu.load_rom_in_memory(&[0; 0xE00]).is_ok());
+    assert!(cpu.load_rom_in_memory(&[0; 0xE01]).is_err());
+    assert_eq!(cpu.get_memory().len(), 0x1000);
+
+    cpu.set_quirks(Quirks::xo_chip());
+    cpu.load_rom_in_memory(&[0; 0xFE00])?;
+    assert_eq!(cpu.get_memory().len(), 0x10000);
+    Ok(())
+}
+
 #[test]
 fn test_get_display() {
     let cpu = Cpu::new();
-    assert_eq!(cpu.get_display(), [false; DISPLAY_WIDTH * DISPLAY_HEIGHT]);
+    assert_eq!(cpu.get_display(), [0; DISPLAY_WIDTH * DISPLAY_HEIGHT]);
 }
 
 #[test]
+#[allow(clippy::useless_vec)]
 fn test_fetch() -> Result<(), String> {
     let mut cpu = Cpu::new();
     cpu.load_rom_in_memory(&vec![0x24, 0x7C, 0xFF, 0x1])?;
     
     cpu.pc = 0x200; // ROM is loaded starting on address 0x200
-    assert_eq!(cpu.fetch(), 0x247C);
+    assert_eq!(cpu.fetch(), Ok(0x247C));
 
     cpu.pc = 0x201;
-    assert_eq!(cpu.fetch(), 0x7CFF);
+    assert_eq!(cpu.fetch(), Ok(0x7CFF));
 
     cpu.pc = 0x202;
-    assert_eq!(cpu.fetch(), 0xFF01);
+    assert_eq!(cpu.fetch(), Ok(0xFF01));
+
+    cpu.pc = 0xFFF; // Only one byte of the instruction fits in memory
+    assert_eq!(cpu.fetch(), Err(CpuError::PcOutOfRange { pc: 0xFFF, opcode: 0 }));
     Ok(())
 }
 
 #[test]
-#[should_panic]
 fn test_decode_invalid_instruction() {
     let mut cpu = Cpu::new();
-    cpu.decode(0x00FF); // Instruction 00FF is invalid
+    assert_eq!(
+        cpu.execute_opcode(0x5001), // Instruction 5001 is invalid
+        Err(CpuError::UnknownOpcode { pc: 0x1FE, opcode: 0x5001 })
+    );
+}
+
+#[test]
+fn test_run() -> Result<(), String> {
+    let mut cpu = Cpu::new();
+    cpu.load_rom_in_memory(&[0x60, 0x2A, 0x12, 0x00])?;
+
+    assert_eq!(cpu.run(), Ok(()));
+    assert_eq!(cpu.v[0], 0x2A);
+    assert_eq!(cpu.run(), Ok(()));
+    assert_eq!(cpu.pc, 0x200);
+    Ok(())
+}
+
+#[test]
+fn test_run_errors() -> Result<(), String> {
+    let mut cpu = Cpu::new();
+    cpu.load_rom_in_memory(&[0x00, 0xEE, 0x5F, 0x01])?;
+
+    // Returning from a subroutine that was never called
+    assert_eq!(cpu.run(), Err(CpuError::StackUnderflow { pc: 0x200, opcode: 0x00EE }));
+
+    cpu.pc = 0x202;
+    assert_eq!(cpu.run(), Err(CpuError::UnknownOpcode { pc: 0x202, opcode: 0x5F01 }));
+    Ok(())
+}
+
+#[test]
+fn test_run_pc_out_of_range() -> Result<(), String> {
+    // The last instruction of the 4KB of CHIP-8 runs, then the PC is past the end of memory
+    let mut cpu = Cpu::new();
+    cpu.set_memory(0xFFE, &[0x60, 0x2A])?;
+    cpu.pc = 0xFFE;
+    assert_eq!(cpu.run(), Ok(()));
+    assert_eq!(cpu.run(), Err(CpuError::PcOutOfRange { pc: 0x1000, opcode: 0x602A }));
+
+    // A skip over the end of memory
+    cpu.set_memory(0xFFE, &[0x30, 0x2A])?;
+    cpu.pc = 0xFFE;
+    assert_eq!(cpu.run(), Ok(()));
+    assert_eq!(cpu.pc, 0x1002);
+    assert_eq!(cpu.run(), Err(CpuError::PcOutOfRange { pc: 0x1002, opcode: 0x302A }));
+
+    // With the 64KB of XO-CHIP, the PC does not wrap around to 0x0000
+    cpu.set_quirks(Quirks::xo_chip());
+    cpu.set_memory(0xFFFE, &[0x60, 0x2A])?;
+    cpu.pc = 0xFFFE;
+    assert_eq!(cpu.run(), Err(CpuError::PcOutOfRange { pc: 0x10000, opcode: 0x602A }));
+    assert_eq!(cpu.pc, 0xFFFE);
+    Ok(())
+}
+
+#[test]
+fn test_run_classic_memory_out_of_bounds() -> Result<(), String> {
+    // I past the 4KB of CHIP-8, even though XO-CHIP could access it
+    let mut cpu = Cpu::new();
+    cpu.set_quirks(Quirks::cosmac_vip());
+    cpu.load_rom_in_memory(&[0xAF, 0xF0, 0x6F, 0x10, 0xFF, 0x1E, 0xF0, 0x33])?;
+    for _ in 0..3 {
+        cpu.run().map_err(|e| e.to_string())?;
+    }
+    assert_eq!(cpu.i, 0x1000);
+    assert_eq!(cpu.run(), Err(CpuError::MemoryOutOfBounds { pc: 0x206, opcode: 0xF033, address: 0x1000 }));
+    Ok(())
+}
+
+#[test]
+fn test_run_self_modifying() -> Result<(), String> {
+    let mut cpu = Cpu::new();
+    cpu.load_rom_in_memory(&[0x71, 0x01, 0x12, 0x00])?; // V1 += 1, then jumps back forever
+
+    assert_eq!(cpu.run(), Ok(()));
+    assert_eq!(cpu.run(), Ok(()));
+    assert_eq!(cpu.decode_cache[0x200], Some(Instruction::AddByte { x: 1, nn: 0x01 }));
+
+    // Overwriting the first instruction with V1 = 0x2A, which must not run the decoded V1 += 1
+    cpu.v[0] = 0x61;
+    cpu.v[1] = 0x2A;
+    cpu.i = 0x200;
+    cpu.execute_opcode(0xF155).unwrap();
+    assert_eq!(cpu.decode_cache[0x200], None);
+
+    assert_eq!(cpu.run(), Ok(()));
+    assert_eq!(cpu.v[1], 0x2A);
+    Ok(())
+}
+
+#[test]
+fn test_watchpoints() -> Result<(), String> {
+    let mut cpu = Cpu::new();
+    cpu.load_rom_in_memory(&[
+        0xA3, 0x00, // I = 0x300
+        0x60, 0x7B, // V0 = 123
+        0xF0, 0x33, // Stores the BCD of V0 at 0x300 ~ 0x302
+        0xF0, 0x65, // Reads V0 from 0x300
+    ])?;
+    cpu.add_watchpoint(Watchpoint { start: 0x301, end: 0x3FF, access: Access::Write });
+    cpu.add_watchpoint(Watchpoint { start: 0x300, end: 0x300, access: Access::Read });
+    cpu.add_watchpoint(Watchpoint { start: 0x206, end: 0x206, access: Access::Execute });
+
+    cpu.run().unwrap();
+    cpu.run().unwrap();
+    assert!(cpu.get_watch_hits().is_empty());
+
+    cpu.run().unwrap();
+    assert_eq!(cpu.get_watch_hits(), [
+        WatchHit { access: Access::Write, address: 0x301, pc: 0x204, opcode: 0xF033, old: 0, new: 2 },
+        WatchHit { access: Access::Write, address: 0x302, pc: 0x204, opcode: 0xF033, old: 0, new: 3 },
+    ]);
+
+    // Fetching the instruction is an access too, reported with its own opcode
+    cpu.run().unwrap();
+    assert_eq!(cpu.get_watch_hits(), [
+        WatchHit { access: Access::Execute, address: 0x206, pc: 0x206, opcode: 0xF065, old: 0xF0, new: 0xF0 },
+        WatchHit { access: Access::Read, address: 0x300, pc: 0x206, opcode: 0xF065, old: 1, new: 1 },
+    ]);
+    assert_eq!(cpu.v[0], 1);
+
+    // Hits only last until the next instruction
+    assert!(cpu.remove_watchpoint(&Watchpoint { start: 0x206, end: 0x206, access: Access::Execute }));
+    assert!(!cpu.remove_watchpoint(&Watchpoint { start: 0x206, end: 0x206, access: Access::Execute }));
+    cpu.execute_opcode(0x6000).unwrap();
+    assert!(cpu.get_watch_hits().is_empty());
+    assert_eq!(cpu.get_watchpoints().len(), 2);
+
+    // The debugger editing memory is not an access of the program
+    cpu.set_memory(0x300, &[4, 5])?;
+    assert!(cpu.get_watch_hits().is_empty());
+    assert_eq!(cpu.memory[0x300..0x302], [4, 5]);
+    Ok(())
 }
 
 #[test]
@@ -91,16 +248,50 @@ fn test_update_timers() {
     assert_eq!(cpu.delay_timer, 0); assert_eq!(cpu.sound_timer, 0);
 }
 
+#[test]
+fn test_skip_over_long_instruction() {
+    let mut cpu = Cpu::new();
+    cpu.memory[0x202] = 0xF0; // F000 NNNN is four bytes long
+    cpu.memory[0x203] = 0x00;
+    cpu.pc = 0x202;
+
+    cpu.execute_opcode(0x3000).unwrap(); // V0 equals 0, so the whole F000 NNNN instruction is skipped
+    assert_eq!(cpu.pc, 0x206);
+}
+
+#[test]
+fn test_instruction_00dn() {
+    let mut cpu = Cpu::new();
+    cpu.display[3 + 2 * DISPLAY_WIDTH] = 1;
+    cpu.display[4] = 1;
+
+    cpu.execute_opcode(0x00D2).unwrap();
+    assert_eq!(cpu.display[3], 1);
+    assert_eq!(cpu.get_display().iter().filter(|pixel| **pixel != 0).count(), 1); // Top row scrolled off
+}
+
+#[test]
+fn test_instruction_00e0_planes() {
+    let mut cpu = Cpu::new();
+    cpu.display[0] = 3;
+    cpu.display[1] = 2;
+
+    cpu.execute_opcode(0xF201).unwrap(); // Only the second plane is cleared
+    cpu.execute_opcode(0x00E0).unwrap();
+    assert_eq!(cpu.display[0], 1);
+    assert_eq!(cpu.display[1], 0);
+}
+
 #[test]
 fn test_instruction_00e0() {
     let mut cpu = Cpu::new();
 
-    cpu.display = [true; DISPLAY_WIDTH * DISPLAY_HEIGHT];
-    assert_eq!(cpu.display, [true; DISPLAY_WIDTH * DISPLAY_HEIGHT]);
+    cpu.display = [1; DISPLAY_BUFFER_SIZE];
+    assert_eq!(cpu.display, [1; DISPLAY_BUFFER_SIZE]);
 
-    cpu.decode(0x00E0);
+    cpu.execute_opcode(0x00E0).unwrap();
 
-    assert_eq!(cpu.display, [false; DISPLAY_WIDTH * DISPLAY_HEIGHT]);
+    assert_eq!(cpu.display, [0; DISPLAY_BUFFER_SIZE]);
 
 }
 
@@ -110,17 +301,71 @@ fn test_instruction_00ee() {
     cpu.sp = 3;
     cpu.stack[3] = 0x1C;
 
-    cpu.decode(0x00EE);
+    cpu.execute_opcode(0x00EE).unwrap();
     assert_eq!(cpu.sp, 2);
     assert_eq!(cpu.pc, 0x1C);
 }
 
+#[test]
+fn test_instruction_00cn() {
+    let mut cpu = Cpu::new();
+    cpu.display[3] = 1;
+    cpu.display[(DISPLAY_HEIGHT - 1) * DISPLAY_WIDTH] = 1;
+
+    cpu.execute_opcode(0x00C2).unwrap();
+    assert_eq!(cpu.display[3], 0);
+    assert_eq!(cpu.display[3 + 2 * DISPLAY_WIDTH], 1);
+    assert_eq!(cpu.get_display().iter().filter(|pixel| **pixel != 0).count(), 1); // Bottom row scrolled off
+}
+
+#[test]
+fn test_instruction_00fb_00fc() {
+    let mut cpu = Cpu::new();
+    cpu.display[2] = 1;
+    cpu.display[DISPLAY_WIDTH - 1] = 1;
+
+    cpu.execute_opcode(0x00FB).unwrap(); // Scroll right
+    assert_eq!(cpu.display[6], 1);
+    assert_eq!(cpu.get_display().iter().filter(|pixel| **pixel != 0).count(), 1);
+
+    cpu.execute_opcode(0x00FC).unwrap(); // Scroll left
+    assert_eq!(cpu.display[2], 1);
+    assert_eq!(cpu.get_display().iter().filter(|pixel| **pixel != 0).count(), 1);
+}
+
+#[test]
+fn test_instruction_00fd() {
+    let mut cpu = Cpu::new();
+    cpu.pc = 3;
+
+    cpu.execute_opcode(0x00FD).unwrap();
+    assert!(cpu.has_exited());
+    assert_eq!(cpu.pc, 1);
+}
+
+#[test]
+fn test_instruction_00fe_00ff() {
+    let mut cpu = Cpu::new();
+    cpu.display[0] = 1;
+
+    cpu.execute_opcode(0x00FF).unwrap();
+    assert_eq!(cpu.get_display_size(), (HIRES_DISPLAY_WIDTH, HIRES_DISPLAY_HEIGHT));
+    assert_eq!(cpu.get_display().len(), HIRES_DISPLAY_WIDTH * HIRES_DISPLAY_HEIGHT);
+    assert_eq!(cpu.display[0], 0);
+
+    cpu.display[0] = 1;
+    cpu.execute_opcode(0x00FE).unwrap();
+    assert_eq!(cpu.get_display_size(), (DISPLAY_WIDTH, DISPLAY_HEIGHT));
+    assert_eq!(cpu.get_display().len(), DISPLAY_WIDTH * DISPLAY_HEIGHT);
+    assert_eq!(cpu.display[0], 0);
+}
+
 #[test]
 fn test_instruction_1nnn() {
     let mut cpu = Cpu::new();
 
     assert_eq!(cpu.pc, 0x200);
-    cpu.decode(0x1420);
+    cpu.execute_opcode(0x1420).unwrap();
     assert_eq!(cpu.pc, 0x0420);
 }
 
@@ -129,22 +374,34 @@ fn test_instruction_2nnn() {
     let mut cpu = Cpu::new();
     cpu.pc = 3;
 
-    cpu.decode(0x2369);
+    cpu.execute_opcode(0x2369).unwrap();
     assert_eq!(cpu.sp, 1);
     assert_eq!(cpu.stack[1], 3);
     assert_eq!(cpu.pc, 0x0369);
 }
 
+#[test]
+fn test_instruction_2nnn_stack_overflow() {
+    let mut cpu = Cpu::new();
+    cpu.pc = 0x202;
+
+    for _ in 0..15 {
+        cpu.execute_opcode(0x2200).unwrap();
+    }
+    assert_eq!(cpu.execute_opcode(0x2200), Err(CpuError::StackOverflow { pc: 0x1FE, opcode: 0x2200 }));
+    assert_eq!(cpu.sp, 15);
+}
+
 #[test]
 fn test_instruction_3xnn() {
     let mut cpu = Cpu::new();
     cpu.v[0] = 0x13;
     cpu.pc = 1;
 
-    cpu.decode(0x3026);
+    cpu.execute_opcode(0x3026).unwrap();
     assert_ne!(cpu.pc, 3);
 
-    cpu.decode(0x3013);
+    cpu.execute_opcode(0x3013).unwrap();
     assert_eq!(cpu.pc, 3);
 }
 
@@ -154,10 +411,10 @@ fn test_instruction_4xnn() {
     cpu.v[0] = 0x13;
     cpu.pc = 1;
 
-    cpu.decode(0x4026);
+    cpu.execute_opcode(0x4026).unwrap();
     assert_eq!(cpu.pc, 3);
 
-    cpu.decode(0x4013);
+    cpu.execute_opcode(0x4013).unwrap();
     assert_ne!(cpu.pc, 5);
 }
 
@@ -168,21 +425,51 @@ fn test_instruction_5xy0() {
     cpu.v[1] = 0x4;
     cpu.pc = 1;
     
-    cpu.decode(0x5010); // Vx and Vy are equal
+    cpu.execute_opcode(0x5010).unwrap(); // Vx and Vy are equal
     assert_eq!(cpu.pc, 3);
 
-    cpu.decode(0x5120); // Vx and Vy are not equal
+    cpu.execute_opcode(0x5120).unwrap(); // Vx and Vy are not equal
     assert_ne!(cpu.pc, 5);
 }
 
+#[test]
+fn test_instruction_5xy2() {
+    let mut cpu = Cpu::new();
+    cpu.i = 0x300;
+    for idx in 0..16 {
+        cpu.v[idx] = idx as u8 + 1;
+    }
+
+    cpu.execute_opcode(0x5242).unwrap(); // V2 through V4
+    assert_eq!(cpu.memory[0x300..0x304], [3, 4, 5, 0]);
+
+    cpu.execute_opcode(0x5312).unwrap(); // V3 through V1, in reverse order
+    assert_eq!(cpu.memory[0x300..0x303], [4, 3, 2]);
+    assert_eq!(cpu.i, 0x300);
+}
+
+#[test]
+fn test_instruction_5xy3() {
+    let mut cpu = Cpu::new();
+    cpu.i = 0x300;
+    cpu.memory[0x300..0x303].copy_from_slice(&[7, 8, 9]);
+
+    cpu.execute_opcode(0x5573).unwrap(); // V5 through V7
+    assert_eq!(cpu.v[5..8], [7, 8, 9]);
+
+    cpu.execute_opcode(0x5203).unwrap(); // V2 through V0, in reverse order
+    assert_eq!(cpu.v[..3], [9, 8, 7]);
+    assert_eq!(cpu.i, 0x300);
+}
+
 #[test]
 fn test_instruction_6xnn() {
     let mut cpu = Cpu::new();
     
-    cpu.decode(0x6CD4);
+    cpu.execute_opcode(0x6CD4).unwrap();
     assert_eq!(cpu.v[0xC], 0x0D4);
 
-    cpu.decode(0x643F);
+    cpu.execute_opcode(0x643F).unwrap();
     assert_eq!(cpu.v[0x4], 0x03F);
 }
 
@@ -193,7 +480,7 @@ fn test_instruction_7xnn() {
     cpu.v[0xD] = 0x78;
     let initial_v = cpu.v[0xD];
 
-    cpu.decode(0x7D21);
+    cpu.execute_opcode(0x7D21).unwrap();
     assert_eq!(cpu.v[0xD], (0x0021 + initial_v))
 }
 
@@ -202,7 +489,7 @@ fn test_instruction_8xy0() {
     let mut cpu = Cpu::new();
     cpu.v[2] = 0x7F;
 
-    cpu.decode(0x8120);
+    cpu.execute_opcode(0x8120).unwrap();
     assert_eq!(cpu.v[1], 0x7F);
 }
 
@@ -212,7 +499,7 @@ fn test_instruction_8xy1() {
     cpu.v[0] = 0xA;
     cpu.v[1] = 0xFF;
 
-    cpu.decode(0x8011);
+    cpu.execute_opcode(0x8011).unwrap();
     assert_eq!(cpu.v[0], 0xFF);
 }
 
@@ -222,7 +509,7 @@ fn test_instruction_8xy2() {
     cpu.v[0] = 0xA;
     cpu.v[1] = 0xFF;
 
-    cpu.decode(0x8012);
+    cpu.execute_opcode(0x8012).unwrap();
     assert_eq!(cpu.v[0], 0xA);
 }
 
@@ -232,7 +519,7 @@ fn test_instruction_8xy3() {
     cpu.v[0] = 0xA;
     cpu.v[1] = 0xFF;
 
-    cpu.decode(0x8013);
+    cpu.execute_opcode(0x8013).unwrap();
     assert_eq!(cpu.v[0], 0xF5);
 }
 
@@ -242,14 +529,14 @@ fn test_instruction_8xy4() {
     cpu.v[0] = 0xF;
     cpu.v[1] = 0xA;
 
-    cpu.decode(0x8014); // Addition without carry
+    cpu.execute_opcode(0x8014).unwrap(); // Addition without carry
     assert_eq!(cpu.v[0], 0x19);
     assert_eq!(cpu.v[0xF], 0);
 
     cpu.v[0] = 0xFF;
     cpu.v[1] = 0xF;
 
-    cpu.decode(0x8014); // Addition with carry
+    cpu.execute_opcode(0x8014).unwrap(); // Addition with carry
     assert_eq!(cpu.v[0], 0xE);
     assert_eq!(cpu.v[0xF], 1);
 }
@@ -260,14 +547,14 @@ fn test_instruction_8xy5() {
     cpu.v[0] = 0xA;
     cpu.v[1] = 0xF;
 
-    cpu.decode(0x8015); // Subtraction with borrow (VF should be 0)
+    cpu.execute_opcode(0x8015).unwrap(); // Subtraction with borrow (VF should be 0)
     assert_eq!(cpu.v[0], 0xFB);
     assert_eq!(cpu.v[0xF], 0);
 
     cpu.v[0] = 0xF;
     cpu.v[1] = 0xA;
 
-    cpu.decode(0x8015); // Subtraction without borrow (VF should be 1)
+    cpu.execute_opcode(0x8015).unwrap(); // Subtraction without borrow (VF should be 1)
     assert_eq!(cpu.v[0], 0x5);
     assert_eq!(cpu.v[0xF], 1);
 }
@@ -277,13 +564,13 @@ fn test_instruction_8xy6() {
     let mut cpu = Cpu::new();
     cpu.v[0] = 0xC; // Decimal = 12; Binary = 1100
     
-    cpu.decode(0x8006); // LSB is 0
+    cpu.execute_opcode(0x8006).unwrap(); // LSB is 0
     assert_eq!(cpu.v[0], 0x6);
     assert_eq!(cpu.v[0xF], 0);
 
     cpu.v[0] = 0x11; // Decimal = 17; Binary = 10001
     
-    cpu.decode(0x8006); // LSB is 1
+    cpu.execute_opcode(0x8006).unwrap(); // LSB is 1
     assert_eq!(cpu.v[0], 0x8);
     assert_eq!(cpu.v[0xF], 1);
 }
@@ -294,14 +581,14 @@ fn test_instruction_8xy7() {
     cpu.v[0] = 0xF;
     cpu.v[1] = 0xA;
 
-    cpu.decode(0x8017); // Subtraction with borrow (VF should be 0)
+    cpu.execute_opcode(0x8017).unwrap(); // Subtraction with borrow (VF should be 0)
     assert_eq!(cpu.v[0], 0xFB);
     assert_eq!(cpu.v[0xF], 0);
 
     cpu.v[0] = 0xA;
     cpu.v[1] = 0xF;
 
-    cpu.decode(0x8017); // Subtraction without borrow (VF should be 1)
+    cpu.execute_opcode(0x8017).unwrap(); // Subtraction without borrow (VF should be 1)
     assert_eq!(cpu.v[0], 0x5);
     assert_eq!(cpu.v[0xF], 1);
 }
@@ -311,13 +598,13 @@ fn test_instruction_8xye() {
     let mut cpu = Cpu::new();
     cpu.v[0] = 0xA; // Decimal = 10; Binary = 1010
     
-    cpu.decode(0x800E); // MSB is 0
+    cpu.execute_opcode(0x800E).unwrap(); // MSB is 0
     assert_eq!(cpu.v[0], 0x14);
     assert_eq!(cpu.v[0xF], 0);
 
     cpu.v[0] = 0xF0; // Decimal = 240; Binary = 11110000
     
-    cpu.decode(0x800E); // MSB is 1
+    cpu.execute_opcode(0x800E).unwrap(); // MSB is 1
     assert_eq!(cpu.v[0], 0xE0);
     assert_eq!(cpu.v[0xF], 1);
 }
@@ -329,10 +616,10 @@ fn test_instruction_9xy0() {
     cpu.v[1] = 0x4;
     cpu.pc = 1;
     
-    cpu.decode(0x9010); // Vx and Vy are equal
+    cpu.execute_opcode(0x9010).unwrap(); // Vx and Vy are equal
     assert_ne!(cpu.pc, 3);
 
-    cpu.decode(0x9120); // Vx and Vy are not equal
+    cpu.execute_opcode(0x9120).unwrap(); // Vx and Vy are not equal
     assert_eq!(cpu.pc, 3);
 }
 
@@ -341,7 +628,7 @@ fn test_instruction_annn() {
     let mut cpu = Cpu::new();
 
     assert_eq!(cpu.i, 0);
-    cpu.decode(0xA123);
+    cpu.execute_opcode(0xA123).unwrap();
     assert_eq!(cpu.i, 0x0123);
 }
 
@@ -349,7 +636,7 @@ fn test_instruction_annn() {
 fn test_instruction_bnnn() {
     let mut cpu = Cpu::new();
     cpu.v[0] = 0x5;
-    cpu.decode(0xB666);
+    cpu.execute_opcode(0xB666).unwrap();
 
     assert_eq!(cpu.pc, 0x066B);
 }
@@ -358,14 +645,26 @@ fn test_instruction_bnnn() {
 fn test_instruction_cxnn() {
     let mut cpu = Cpu::new();
 
-    cpu.decode(0xC000);
+    cpu.execute_opcode(0xC000).unwrap();
     assert_eq!(cpu.v[0], 0x0);
 
     /* Binary of F:  00001111
         * Binary of F0: 11110000
         * Therefore, (F & [random u8]) & F0 should always be 0 */
-    cpu.decode(0xC00F);
-    assert_eq!(cpu.v[0] & 0xF0, 0)
+    cpu.execute_opcode(0xC00F).unwrap();
+    assert_eq!(cpu.v[0] & 0xF0, 0);
+
+    // With a chosen seed, the random numbers are always the same
+    cpu.set_seed(42);
+    cpu.execute_opcode(0xC0FF).unwrap();
+    cpu.execute_opcode(0xC1FF).unwrap();
+    cpu.execute_opcode(0xC20F).unwrap();
+    assert_eq!(cpu.v[..3], [0xA1, 0xB5, 0x08]);
+
+    let mut other = Cpu::new();
+    other.set_seed(42);
+    other.execute_opcode(0xC0FF).unwrap();
+    assert_eq!(other.v[0], cpu.v[0]);
 }
 
 #[test]
@@ -396,6 +695,7 @@ fn test_instruction_cxnn() {
     * 
     * Also, consider that initially I = 0, X = 0 and Y = 0.
     */
+#[allow(clippy::identity_op, clippy::erasing_op)]
 fn test_instruction_dxyn() {
     let mut cpu = Cpu::new();
     
@@ -405,33 +705,84 @@ fn test_instruction_dxyn() {
     cpu.memory[2] = 0xFF; // memory[I + 2]
 
     // Setting up display initial state
-    cpu.display[0] = true; // First line
-    cpu.display[1] = false;
-    cpu.display[2] = true;
+    cpu.display[0] = 1; // First line
+    cpu.display[1] = 0;
+    cpu.display[2] = 1;
 
-    cpu.display[0 + 1 * DISPLAY_WIDTH] = true; // Second line
-    cpu.display[1 + 1 * DISPLAY_WIDTH] = true;
-    cpu.display[2 + 1 * DISPLAY_WIDTH] = true;
+    cpu.display[0 + 1 * DISPLAY_WIDTH] = 1; // Second line
+    cpu.display[1 + 1 * DISPLAY_WIDTH] = 1;
+    cpu.display[2 + 1 * DISPLAY_WIDTH] = 1;
 
-    cpu.display[0 + 2 * DISPLAY_WIDTH] = false; // Third line
-    cpu.display[1 + 2 * DISPLAY_WIDTH] = true;
-    cpu.display[2 + 2 * DISPLAY_WIDTH] = false;
+    cpu.display[0 + 2 * DISPLAY_WIDTH] = 0; // Third line
+    cpu.display[1 + 2 * DISPLAY_WIDTH] = 1;
+    cpu.display[2 + 2 * DISPLAY_WIDTH] = 0;
 
-    cpu.decode(0xD003);
+    cpu.execute_opcode(0xD003).unwrap();
     
-    assert_eq!(cpu.display[0], true); // Checking first line result
-    assert_eq!(cpu.display[1], false);
-    assert_eq!(cpu.display[2], false);
+    assert_eq!(cpu.display[0], 1); // Checking first line result
+    assert_eq!(cpu.display[1], 0);
+    assert_eq!(cpu.display[2], 0);
+
+    assert_eq!(cpu.display[0 + 1 * DISPLAY_WIDTH], 0); // Checking second line result
+    assert_eq!(cpu.display[1 + 1 * DISPLAY_WIDTH], 0);
+    assert_eq!(cpu.display[2 + 1 * DISPLAY_WIDTH], 1);
+
+    assert_eq!(cpu.display[0 + 2 * DISPLAY_WIDTH], 1); // Checking third line result
+    assert_eq!(cpu.display[1 + 2 * DISPLAY_WIDTH], 0);
+    assert_eq!(cpu.display[2 + 2 * DISPLAY_WIDTH], 1);
+
+    assert_eq!(cpu.v[0xF], 1);
+}
+
+#[test]
+fn test_instruction_dxy0() {
+    let mut cpu = Cpu::new();
+    cpu.execute_opcode(0x00FF).unwrap();
+    cpu.i = 0x300;
+    cpu.v[0] = 100;
+    cpu.v[1] = 10;
+
+    // 16x16 sprite whose rows only have their leftmost and rightmost pixels on
+    for row in 0..16 {
+        cpu.memory[0x300 + row * 2] = 0x80;
+        cpu.memory[0x300 + row * 2 + 1] = 0x01;
+    }
 
-    assert_eq!(cpu.display[0 + 1 * DISPLAY_WIDTH], false); // Checking second line result
-    assert_eq!(cpu.display[1 + 1 * DISPLAY_WIDTH], false);
-    assert_eq!(cpu.display[2 + 1 * DISPLAY_WIDTH], true);
+    cpu.execute_opcode(0xD010).unwrap();
+    for row in 10..26 {
+        assert_eq!(cpu.display[row * HIRES_DISPLAY_WIDTH + 100], 1);
+        assert_eq!(cpu.display[row * HIRES_DISPLAY_WIDTH + 115], 1);
+    }
+    assert_eq!(cpu.get_display().iter().filter(|pixel| **pixel != 0).count(), 32);
+    assert_eq!(cpu.v[0xF], 0);
+
+    cpu.execute_opcode(0xD010).unwrap(); // Drawing it again erases it
+    assert_eq!(cpu.get_display().iter().filter(|pixel| **pixel != 0).count(), 0);
+    assert_eq!(cpu.v[0xF], 1);
+}
 
-    assert_eq!(cpu.display[0 + 2 * DISPLAY_WIDTH], true); // Checking third line result
-    assert_eq!(cpu.display[1 + 2 * DISPLAY_WIDTH], false);
-    assert_eq!(cpu.display[2 + 2 * DISPLAY_WIDTH], true);
+#[test]
+fn test_instruction_dxyn_planes() {
+    let mut cpu = Cpu::new();
+    cpu.i = 0x300;
+    cpu.memory[0x300] = 0x80; // First plane sprite
+    cpu.memory[0x301] = 0xC0; // Second plane sprite
+
+    cpu.execute_opcode(0xF301).unwrap(); // Both planes selected
+    cpu.execute_opcode(0xD001).unwrap();
+    assert_eq!(cpu.display[0], 3);
+    assert_eq!(cpu.display[1], 2);
+    assert_eq!(cpu.v[0xF], 0);
 
+    cpu.execute_opcode(0xF101).unwrap(); // First plane selected
+    cpu.execute_opcode(0xD001).unwrap();
+    assert_eq!(cpu.display[0], 2);
     assert_eq!(cpu.v[0xF], 1);
+
+    cpu.execute_opcode(0xF001).unwrap(); // No planes selected, nothing is drawn
+    cpu.execute_opcode(0xD001).unwrap();
+    assert_eq!(cpu.display[0], 2);
+    assert_eq!(cpu.v[0xF], 0);
 }
 
 #[test]
@@ -441,12 +792,12 @@ fn test_instruction_ex9e() {
     cpu.v[0] = 0xF;
     cpu.keypad[0xF] = true;
     cpu.pc = 1;
-    cpu.decode(0xE09E);
+    cpu.execute_opcode(0xE09E).unwrap();
     
     assert_eq!(cpu.pc, 3);
 
     cpu.v[0] = 0x3;
-    cpu.decode(0xE09E);
+    cpu.execute_opcode(0xE09E).unwrap();
     
     assert_ne!(cpu.pc, 5);
 }
@@ -458,23 +809,48 @@ fn test_instruction_exa1() {
     cpu.v[0] = 0xF;
     cpu.keypad[0xF] = true;
     cpu.pc = 1;
-    cpu.decode(0xE0A1);
+    cpu.execute_opcode(0xE0A1).unwrap();
     
     assert_ne!(cpu.pc, 3);
 
     cpu.v[0] = 0x3;
-    cpu.decode(0xE0A1);
+    cpu.execute_opcode(0xE0A1).unwrap();
     
     assert_eq!(cpu.pc, 3);
 }
 
+#[test]
+fn test_instruction_f000() {
+    let mut cpu = Cpu::new();
+    cpu.memory[0x202] = 0xAB;
+    cpu.memory[0x203] = 0xCD;
+    cpu.pc = 0x202;
+
+    cpu.execute_opcode(0xF000).unwrap();
+    assert_eq!(cpu.i, 0xABCD);
+    assert_eq!(cpu.pc, 0x204);
+}
+
+#[test]
+fn test_instruction_f002() {
+    let mut cpu = Cpu::new();
+    cpu.set_quirks(Quirks::xo_chip());
+    cpu.i = 0xF000; // XO-CHIP programs can use 64KB of memory
+    for idx in 0..16 {
+        cpu.memory[0xF000 + idx] = idx as u8;
+    }
+
+    cpu.execute_opcode(0xF002).unwrap();
+    assert_eq!(cpu.get_audio_pattern(), [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15]);
+}
+
 #[test]
 fn test_instruction_fx07() {
     let mut cpu = Cpu::new();
     cpu.delay_timer = 0xA3;
 
     assert_eq!(cpu.v[0], 0);
-    cpu.decode(0xF007);
+    cpu.execute_opcode(0xF007).unwrap();
     assert_eq!(cpu.v[0], 0xA3);
 }
 
@@ -483,14 +859,42 @@ fn test_instruction_fx0a() {
     let mut cpu = Cpu::new();
     cpu.pc = 3;
 
-    cpu.decode(0xF00A); // No keypresses
+    cpu.execute_opcode(0xF00A).unwrap(); // No keypresses
     assert_eq!(cpu.pc, 1);
     assert_eq!(cpu.v[0], 0);
 
+    cpu.pc = 3;
     cpu.keypad[7] = true; // Keypress on index 7
-    cpu.decode(0xF00A);
-    assert_eq!(cpu.pc, 3);
+    cpu.execute_opcode(0xF00A).unwrap();
+    assert_eq!(cpu.pc, 3); // PC stays at the next instruction
     assert_eq!(cpu.v[0], 7);
+
+    // A keypress at the end of memory does not overflow the PC
+    cpu.pc = 0xFFFE;
+    cpu.execute_opcode(0xF10A).unwrap();
+    assert_eq!(cpu.pc, 0xFFFE);
+    assert_eq!(cpu.v[1], 7);
+}
+
+#[test]
+fn test_run_fx0a_next_instruction() -> Result<(), String> {
+    let mut cpu = Cpu::new();
+    cpu.load_rom_in_memory(&[
+        0xF0, 0x0A, // Waits for a key press, storing the key in V0
+        0x61, 0x05, // V1 = 5
+    ])?;
+
+    cpu.run().unwrap();
+    assert_eq!(cpu.pc, 0x200);
+
+    // The instruction after FX0A runs once a key is pressed
+    cpu.keypad[7] = true;
+    cpu.run().unwrap();
+    cpu.run().unwrap();
+    assert_eq!(cpu.v[0], 7);
+    assert_eq!(cpu.v[1], 5);
+    assert_eq!(cpu.pc, 0x204);
+    Ok(())
 }
 
 #[test]
@@ -499,7 +903,7 @@ fn test_instruction_fx15() {
     cpu.v[0] = 0xA3;
 
     assert_eq!(cpu.delay_timer, 0);
-    cpu.decode(0xF015);
+    cpu.execute_opcode(0xF015).unwrap();
     assert_eq!(cpu.delay_timer, 0xA3);
 }
 
@@ -509,7 +913,7 @@ fn test_instruction_fx18() {
     cpu.v[0] = 0xA3;
 
     assert_eq!(cpu.sound_timer, 0);
-    cpu.decode(0xF018);
+    cpu.execute_opcode(0xF018).unwrap();
     assert_eq!(cpu.sound_timer, 0xA3);
 }
 
@@ -519,9 +923,9 @@ fn test_instruction_fx1e() {
     cpu.v[0] = 0x5;
 
     assert_eq!(cpu.i, 0);
-    cpu.decode(0xF01E);
+    cpu.execute_opcode(0xF01E).unwrap();
     assert_eq!(cpu.i, 0x5);
-    cpu.decode(0xF01E);
+    cpu.execute_opcode(0xF01E).unwrap();
     assert_eq!(cpu.i, 0xA);
 }
 
@@ -531,21 +935,65 @@ fn test_instruction_fx29() {
     cpu.v[0] = 0xD;
 
     let expected_value = 0x50 + (cpu.v[0] * 5) as u16;
-    cpu.decode(0xF029);
+    cpu.execute_opcode(0xF029).unwrap();
     assert_eq!(cpu.i, expected_value);
 }
 
+#[test]
+fn test_instruction_fx30() {
+    let mut cpu = Cpu::new();
+    cpu.v[0] = 0x9;
+
+    cpu.execute_opcode(0xF030).unwrap();
+    assert_eq!(cpu.i, 0xA0 + 90);
+    assert_eq!(cpu.memory[cpu.i as usize..(cpu.i as usize + 2)], [0x3C, 0x7E]);
+}
+
 #[test]
 fn test_instruction_fx33() {
     let mut cpu = Cpu::new();  
     cpu.v[0] = 214;
 
-    cpu.decode(0xF033);
+    cpu.execute_opcode(0xF033).unwrap();
     assert_eq!(cpu.memory[cpu.i as usize], 2);
     assert_eq!(cpu.memory[cpu.i as usize + 1], 1);
     assert_eq!(cpu.memory[cpu.i as usize + 2], 4);
 }
 
+#[test]
+fn test_memory_out_of_bounds() {
+    let mut cpu = Cpu::new();
+    cpu.i = 0x1000; // Past the 4KB of CHIP-8
+    cpu.pc = 0x202;
+    for opcode in [0xF033, 0xF055, 0xF065, 0xD001] {
+        assert_eq!(cpu.execute_opcode(opcode), Err(CpuError::MemoryOutOfBounds { pc: 0x200, opcode, address: 0x1000 }));
+    }
+
+    cpu.set_quirks(Quirks::xo_chip());
+    cpu.i = 0xFFFE;
+
+    assert_eq!(
+        cpu.execute_opcode(0xF033), // The third BCD digit does not fit in memory
+        Err(CpuError::MemoryOutOfBounds { pc: 0x200, opcode: 0xF033, address: 0x10000 })
+    );
+    assert_eq!(cpu.memory[0xFFFE], 0); // Nothing is written
+
+    assert!(cpu.execute_opcode(0xF255).is_err());
+    assert!(cpu.execute_opcode(0xF265).is_err());
+    assert!(cpu.execute_opcode(0xD005).is_err());
+    assert!(cpu.execute_opcode(0xF002).is_err());
+}
+
+#[test]
+fn test_instruction_fx3a() {
+    let mut cpu = Cpu::new();
+    cpu.v[0] = 112;
+
+    assert_eq!(cpu.get_pitch(), 64);
+    cpu.execute_opcode(0xF03A).unwrap();
+    assert_eq!(cpu.get_pitch(), 112);
+}
+
 #[test]
 fn test_instruction_fx55() {
     let mut cpu = Cpu::new(); 
@@ -555,7 +1003,7 @@ fn test_instruction_fx55() {
         cpu.v[idx] = idx as u8;
     }
 
-    cpu.decode(0xFF55);
+    cpu.execute_opcode(0xFF55).unwrap();
     for idx in 0..16 {
         assert_eq!(cpu.memory[2000 + idx], cpu.v[idx])
     }
@@ -570,8 +1018,135 @@ fn test_instruction_fx65() {
         cpu.memory[2000 + idx] = idx as u8;
     }
 
-    cpu.decode(0xFF65);
+    cpu.execute_opcode(0xFF65).unwrap();
     for idx in 0..16 {
         assert_eq!(cpu.v[idx], cpu.memory[2000 + idx]);
     }
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_instruction_fx75_fx85() {
+    let mut cpu = Cpu::new();
+    for idx in 0..8 {
+        cpu.v[idx] = idx as u8 + 1;
+    }
+
+    cpu.execute_opcode(0xF775).unwrap();
+    cpu.v = [0; 16];
+
+    cpu.execute_opcode(0xF385).unwrap();
+    assert_eq!(cpu.v[..5], [1, 2, 3, 4, 0]);
+}
+
+#[test]
+fn test_quirk_shift_uses_vy() {
+    let mut cpu = Cpu::new();
+    cpu.set_quirks(Quirks { shift_uses_vy: true, ..Quirks::modern() });
+    cpu.v[0] = 0xFF;
+    cpu.v[1] = 0x11; // Decimal = 17; Binary = 10001
+
+    cpu.execute_opcode(0x8016).unwrap(); // Vy is shifted to the right and stored in Vx
+    assert_eq!(cpu.v[0], 0x8);
+    assert_eq!(cpu.v[1], 0x11);
+    assert_eq!(cpu.v[0xF], 1);
+
+    cpu.v[1] = 0x70; // Binary = 01110000
+
+    cpu.execute_opcode(0x801E).unwrap(); // Vy is shifted to the left and stored in Vx
+    assert_eq!(cpu.v[0], 0xE0);
+    assert_eq!(cpu.v[0xF], 0);
+}
+
+#[test]
+fn test_quirk_load_store_increment() {
+    let mut cpu = Cpu::new();
+    cpu.set_quirks(Quirks { load_store_increment: LoadStoreIncrement::XPlusOne, ..Quirks::modern() });
+    cpu.i = 2000;
+
+    cpu.execute_opcode(0xF355).unwrap();
+    assert_eq!(cpu.i, 2004);
+
+    cpu.execute_opcode(0xF065).unwrap();
+    assert_eq!(cpu.i, 2005);
+
+    // CHIP-48 leaves I pointing to the last register
+    cpu.set_quirks(Quirks { load_store_increment: LoadStoreIncrement::X, ..Quirks::modern() });
+    cpu.execute_opcode(0xF355).unwrap();
+    assert_eq!(cpu.i, 2008);
+
+    cpu.execute_opcode(0xF065).unwrap();
+    assert_eq!(cpu.i, 2008);
+}
+
+#[test]
+fn test_quirk_jump_uses_vx() {
+    let mut cpu = Cpu::new();
+    cpu.set_quirks(Quirks { jump_uses_vx: true, ..Quirks::modern() });
+    cpu.v[0] = 0x5;
+    cpu.v[6] = 0x10;
+
+    cpu.execute_opcode(0xB666).unwrap();
+    assert_eq!(cpu.pc, 0x0676);
+}
+
+#[test]
+fn test_quirk_vf_reset() {
+    let mut cpu = Cpu::new();
+    cpu.set_quirks(Quirks { vf_reset: true, ..Quirks::modern() });
+
+    for opcode in [0x8011, 0x8012, 0x8013] {
+        cpu.v[0xF] = 1;
+        cpu.execute_opcode(opcode).unwrap();
+        assert_eq!(cpu.v[0xF], 0);
+    }
+}
+
+#[test]
+fn test_quirk_clip_sprites() {
+    let mut cpu = Cpu::new();
+    cpu.memory[0] = 0xFF;
+    cpu.memory[1] = 0xFF;
+    cpu.v[0] = (DISPLAY_WIDTH - 4) as u8;
+    cpu.v[1] = (DISPLAY_HEIGHT - 1) as u8;
+
+    cpu.execute_opcode(0xD012).unwrap(); // Sprite wraps around to the opposite side of the display
+    assert_eq!(cpu.display[DISPLAY_WIDTH * DISPLAY_HEIGHT - 1], 1);
+    assert_eq!(cpu.display[(DISPLAY_HEIGHT - 1) * DISPLAY_WIDTH], 1);
+    assert_eq!(cpu.display[0], 1);
+
+    cpu.display = [0; DISPLAY_BUFFER_SIZE];
+    cpu.set_quirks(Quirks { clip_sprites: true, ..Quirks::modern() });
+
+    cpu.execute_opcode(0xD012).unwrap(); // Sprite is clipped at the edges of the display
+    assert_eq!(cpu.display[DISPLAY_WIDTH * DISPLAY_HEIGHT - 1], 1);
+    assert_eq!(cpu.display[(DISPLAY_HEIGHT - 1) * DISPLAY_WIDTH], 0);
+    assert_eq!(cpu.display[0], 0);
+}
+
+#[test]
+fn test_quirk_display_wait() {
+    let mut cpu = Cpu::new();
+    cpu.set_quirks(Quirks { display_wait: true, ..Quirks::modern() });
+    cpu.pc = 3;
+
+    cpu.execute_opcode(0xD001).unwrap(); // First sprite of the frame is drawn
+    assert_eq!(cpu.pc, 3);
+
+    cpu.execute_opcode(0xD001).unwrap(); // Next sprite waits for the display to refresh
+    assert_eq!(cpu.pc, 1);
+
+    cpu.update_timers();
+    cpu.pc = 3;
+    cpu.execute_opcode(0xD001).unwrap();
+    assert_eq!(cpu.pc, 3);
+}
+
+#[test]
+fn test_quirk_profiles() {
+    assert_eq!("modern".parse::<Quirks>(), Ok(Quirks::default()));
+    assert_eq!("vip".parse::<Quirks>(), Ok(Quirks::cosmac_vip()));
+    assert_eq!("CHIP48".parse::<Quirks>(), Ok(Quirks::chip48()));
+    assert_eq!("schip".parse::<Quirks>(), Ok(Quirks::super_chip()));
+    assert_eq!("xochip".parse::<Quirks>(), Ok(Quirks::xo_chip()));
+    assert!("chip-9".parse::<Quirks>().is_err());
+}
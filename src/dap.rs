@@ -0,0 +1,752 @@
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::net::TcpListener;
+use std::rc::Rc;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use serde_json::{json, Value};
+
+use crate::chip8::Chip8;
+use crate::cpu::Register;
+use crate::debugger::{format_instruction_at, read_instruction, read_word, DebugAction, DebugCommand, DebugConsole, DebugHook, Debugger, StopReason};
+use crate::error::RunError;
+use crate::expression::{Expression, LogMessage};
+use crate::octo::SourceMap;
+
+// CHIP-8 is shown to the client as a single thread
+const THREAD_ID: u64 = 1;
+
+// Variables of the registers and of the stack
+const REGISTERS_REFERENCE: u64 = 1;
+const STACK_REFERENCE: u64 = 2;
+
+// Longest message accepted from the client, so a wrong Content-Length cannot exhaust memory
+const MAX_MESSAGE_LENGTH: usize = 16 * 1024 * 1024;
+
+
+// Reads a message framed by a Content-Length header, or returns None when the input has ended
+pub fn read_message<R: BufRead>(reader: &mut R) -> Result<Option<Value>, String> {
+    let mut length = None;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).map_err(|e| e.to_string())? == 0 {
+            return Ok(None);
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("Content-Length") {
+                length = Some(value.trim().parse::<usize>().map_err(|_| format!("invalid Content-Length '{}'", value.trim()))?);
+            }
+        }
+    }
+
+    let length = length.ok_or("message without a Content-Length header")?;
+    if length > MAX_MESSAGE_LENGTH {
+        return Err(format!("Content-Length {} is over the limit of {} bytes", length, MAX_MESSAGE_LENGTH));
+    }
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).map_err(|e| e.to_string())?;
+    serde_json::from_slice(&body).map(Some).map_err(|e| format!("invalid message: {}", e))
+}
+
+// Writes a message framed by a Content-Length header
+pub fn write_message<W: Write>(writer: &mut W, message: &Value) -> io::Result<()> {
+    let body = message.to_string();
+    write!(writer, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    writer.flush()
+}
+
+
+// Writer of the messages sent to the client, which numbers them
+struct Output {
+    writer: Box<dyn Write>,
+    seq: u64,
+}
+
+// Sends responses and events to the client. Clones share the same numbering
+#[derive(Clone)]
+pub struct DapWriter {
+    output: Rc<RefCell<Output>>,
+}
+
+impl DapWriter {
+
+    // Creates new writer of messages to the chosen output
+    pub fn new(writer: Box<dyn Write>) -> Self {
+        Self { output: Rc::new(RefCell::new(Output { writer, seq: 0 })) }
+    }
+
+    /* Numbers and sends a message. A client that went away cannot be told anything,
+     * and the requests it sent stop coming, so errors are left out */
+    fn send(&self, mut message: Value) {
+        let mut output = self.output.borrow_mut();
+        output.seq += 1;
+        message["seq"] = json!(output.seq);
+        let _ = write_message(&mut output.writer, &message);
+    }
+
+    // Sends an event with the chosen body
+    pub fn event(&self, event: &str, body: Value) {
+        self.send(json!({ "type": "event", "event": event, "body": body }));
+    }
+
+    // Answers a request with the chosen body, or with an error message
+    pub fn respond(&self, request: &Value, result: Result<Value, String>) {
+        let mut response = json!({
+            "type": "response",
+            "request_seq": request["seq"],
+            "command": request["command"],
+            "success": result.is_ok(),
+        });
+        match result {
+            Ok(Value::Null) => (),
+            Ok(body) => response["body"] = body,
+            Err(message) => response["message"] = json!(message),
+        }
+        self.send(response);
+    }
+}
+
+// Console of the debugger that sends everything printed to the debug console of the client
+pub struct DapConsole {
+    writer: DapWriter,
+}
+
+impl DebugConsole for DapConsole {
+
+    // Commands are not read from the console, but received as requests
+    fn read_line(&mut self, _prompt: &str) -> Option<String> {
+        None
+    }
+
+    fn print(&mut self, text: &str) {
+        self.writer.event("output", json!({ "category": "console", "output": format!("{}\n", text) }));
+    }
+}
+
+
+/* Connection to a client: the requests it sends, which are read on another thread, and the writer
+ * of the messages sent to it. An invalid message ends the requests with the error reading it */
+pub struct DapConnection {
+    requests: Receiver<Result<Value, String>>,
+    writer: DapWriter,
+}
+
+impl DapConnection {
+
+    // Waits for a client to connect to the chosen port of localhost
+    pub fn listen(port: u16) -> Result<Self, String> {
+        let listener = TcpListener::bind(("127.0.0.1", port)).map_err(|e| format!("Port {} could not be listened on: {}", port, e))?;
+        let (stream, _) = listener.accept().map_err(|e| format!("Client could not connect: {}", e))?;
+        let reader = BufReader::new(stream.try_clone().map_err(|e| e.to_string())?);
+        Ok(Self::new(reader, Box::new(BufWriter::new(stream))))
+    }
+
+    // Creates new connection that reads requests from the chosen input until it ends
+    pub fn new<R: BufRead + Send + 'static>(mut reader: R, writer: Box<dyn Write>) -> Self {
+        let (sender, requests) = mpsc::channel();
+        thread::spawn(move || loop {
+            match read_message(&mut reader) {
+                Ok(Some(request)) => {
+                    if sender.send(Ok(request)).is_err() {
+                        break;
+                    }
+                },
+                Ok(None) => break,
+                Err(e) => {
+                    let _ = sender.send(Err(format!("Debug adapter client sent an invalid message: {}", e)));
+                    break;
+                },
+            }
+        });
+        Self { requests, writer: DapWriter::new(writer) }
+    }
+
+    // Returns a console printing to the client
+    pub fn console(&self) -> DapConsole {
+        DapConsole { writer: self.writer.clone() }
+    }
+}
+
+
+// Parses a memory reference or instruction reference sent by the client, a hexadecimal address such as "0x0200"
+fn parse_reference(reference: &Value) -> Result<u16, String> {
+    let text = reference.as_str().unwrap_or_default();
+    let digits = text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")).unwrap_or(text);
+    u16::from_str_radix(digits, 16).map_err(|_| format!("invalid address '{}'", text))
+}
+
+// Formats an address as a memory or instruction reference
+fn format_reference(address: u16) -> String {
+    format!("{:#06X}", address)
+}
+
+// Turns the hit condition of a breakpoint, such as "5", ">= 5" or "% 5", into a condition on the hit count
+fn hit_condition(text: &str) -> String {
+    let text = text.trim();
+    if let Some(count) = text.strip_prefix('%') {
+        format!("hits % ({}) == 0", count)
+    } else if text.starts_with(['<', '>', '=', '!']) {
+        format!("hits {}", text)
+    } else {
+        format!("hits >= ({})", text)
+    }
+}
+
+// Returns the features of the adapter, answered to the initialize request
+fn capabilities() -> Value {
+    json!({
+        "supportsConfigurationDoneRequest": true,
+        "supportsConditionalBreakpoints": true,
+        "supportsHitConditionalBreakpoints": true,
+        "supportsLogPoints": true,
+        "supportsInstructionBreakpoints": true,
+        "supportsEvaluateForHovers": true,
+        "supportsSetVariable": true,
+        "supportsReadMemoryRequest": true,
+        "supportsWriteMemoryRequest": true,
+        "supportsDisassembleRequest": true,
+        "supportsTerminateRequest": true,
+    })
+}
+
+// Returns a variable of a register, which refers to memory if it holds an address
+fn register_variable(chip8: &Chip8, register: Register) -> Value {
+    let value = chip8.get_register(register);
+    match register {
+        Register::I | Register::Pc | Register::Stack(_) => json!({
+            "name": register.to_string(),
+            "value": format_reference(value),
+            "variablesReference": 0,
+            "memoryReference": format_reference(value),
+        }),
+        _ => json!({ "name": register.to_string(), "value": format!("{:#04X}", value), "variablesReference": 0 }),
+    }
+}
+
+// Changes a register to the value of an expression
+fn set_variable(chip8: &mut Chip8, arguments: &Value) -> Result<Value, String> {
+    let register: Register = arguments["name"].as_str().unwrap_or_default().parse()?;
+    let value = arguments["value"].as_str().unwrap_or_default().parse::<Expression>()?.evaluate(chip8, 0)?;
+    let value = u16::try_from(value).map_err(|_| format!("{} does not fit in {}", value, register))?;
+    chip8.set_register(register, value)?;
+    Ok(json!({ "value": register_variable(chip8, register)["value"] }))
+}
+
+// Returns the address of a memory request: its memory reference moved by its offset, which cannot go below 0
+fn memory_address(arguments: &Value) -> Result<usize, String> {
+    let reference = parse_reference(&arguments["memoryReference"])?;
+    let offset = arguments["offset"].as_i64().unwrap_or(0);
+    (reference as i64).checked_add(offset)
+        .and_then(|address| usize::try_from(address).ok())
+        .ok_or_else(|| format!("invalid offset {} from address {}", offset, format_reference(reference)))
+}
+
+// Reads memory, returning the bytes past the end of it as unreadable
+fn read_memory(chip8: &Chip8, arguments: &Value) -> Result<Value, String> {
+    let memory = chip8.get_memory();
+    let count = usize::try_from(arguments["count"].as_u64().unwrap_or(0)).unwrap_or(usize::MAX);
+    let start = memory_address(arguments)?;
+    let end = start.saturating_add(count).min(memory.len());
+    let data = memory.get(start..end).unwrap_or_default();
+
+    Ok(json!({
+        "address": format!("{:#06X}", start),
+        "data": BASE64.encode(data),
+        "unreadableBytes": count - data.len(),
+    }))
+}
+
+// Writes bytes to memory
+fn write_memory(chip8: &mut Chip8, arguments: &Value) -> Result<Value, String> {
+    let address = memory_address(arguments)?;
+    let bytes = BASE64.decode(arguments["data"].as_str().unwrap_or_default()).map_err(|e| format!("invalid data: {}", e))?;
+    chip8.set_memory(address, &bytes)?;
+    Ok(json!({ "bytesWritten": bytes.len() }))
+}
+
+/* Stopped CHIP-8 as a debuggee of a Debug Adapter Protocol client, such as VS Code.
+ *
+ * The requests of the client are answered from the state of CHIP-8, and the stepping, pausing and
+ * breakpoints are done by a Debugger. Breakpoints can be set on addresses, or on lines of the
+ * Octo source of the ROM. While CHIP-8 runs, the requests are handled before each frame */
+pub struct DapServer<'a> {
+    requests: Receiver<Result<Value, String>>,
+    writer: DapWriter,
+    debugger: Debugger<'a>,
+
+    // Octo sources breakpoints were set on, by path
+    sources: BTreeMap<String, SourceMap>,
+
+    // Numbers of the breakpoints set on each source, and of those set on instructions
+    source_breakpoints: BTreeMap<String, Vec<u32>>,
+    instruction_breakpoints: Vec<u32>,
+
+    // Octo source of the ROM, which stack frames are shown in
+    program_source: Option<String>,
+
+    // Whether the client is done setting breakpoints, so CHIP-8 can start running
+    configured: bool,
+
+    // Whether CHIP-8 is paused waiting for requests
+    paused: bool,
+
+    // Whether the next pause is the one before the first instruction, asked for by the launch request
+    entry: bool,
+
+    // Error that stopped the CPU, reported as an exception
+    exception: Option<String>,
+
+    // Whether the client asked to disconnect
+    disconnected: bool,
+
+    // Error reading the messages of the client, which ended the connection
+    connection_error: Option<String>,
+}
+
+impl<'a> DapServer<'a> {
+
+    // Creates new server answering the requests of the connection, which prints to the chosen console of the connection
+    pub fn new(connection: DapConnection, console: &'a mut DapConsole) -> Self {
+        Self {
+            requests: connection.requests,
+            writer: connection.writer,
+            debugger: Debugger::new(console),
+            sources: BTreeMap::new(),
+            source_breakpoints: BTreeMap::new(),
+            instruction_breakpoints: Vec::new(),
+            program_source: None,
+            configured: false,
+            paused: false,
+            entry: false,
+            exception: None,
+            disconnected: false,
+            connection_error: None,
+        }
+    }
+
+    /* Answers the requests of the client until it asks to launch a ROM, then launches it by
+     * calling load with the path of the ROM chosen by the request, if any */
+    pub fn launch<T, F>(&mut self, load: F) -> Result<T, String>
+    where
+        F: FnOnce(Option<String>) -> Result<T, String>,
+    {
+        let request = loop {
+            let request = self.receive().ok_or_else(|| {
+                self.connection_error.take().unwrap_or_else(|| String::from("The client disconnected before launching a ROM"))
+            })?;
+            match request["command"].as_str().unwrap_or_default() {
+                "initialize" => self.writer.respond(&request, Ok(capabilities())),
+                "launch" => break request,
+                "disconnect" => {
+                    self.writer.respond(&request, Ok(Value::Null));
+                    return Err(String::from("The client disconnected before launching a ROM"));
+                },
+                command => self.writer.respond(&request, Err(format!("'{}' cannot be requested before launching a ROM", command))),
+            }
+        };
+
+        let arguments = &request["arguments"];
+        let source = arguments["source"].as_str().map(String::from);
+        let result = match &source {
+            Some(path) => self.load_source(path).map(|_| ()),
+            None => Ok(()),
+        };
+        let result = result.and_then(|()| load(arguments["program"].as_str().map(String::from)));
+        self.writer.respond(&request, result.as_ref().map(|_| Value::Null).map_err(Clone::clone));
+
+        if result.is_ok() {
+            self.program_source = source;
+            if arguments["stopOnEntry"].as_bool().unwrap_or(false) {
+                self.debugger.pause();
+                self.entry = true;
+            }
+            self.writer.event("initialized", json!({}));
+        }
+        result
+    }
+
+    /* Tells the client that CHIP-8 stopped, after the main loop ended.
+     *
+     * If the CPU stopped with an error, it is reported as an exception first, so the client can
+     * still look at the state of CHIP-8 until it resumes */
+    pub fn exit(&mut self, chip8: &mut Chip8, result: &Result<(), RunError>) {
+        if let (Err(RunError::Cpu(e)), false) = (result, self.disconnected) {
+            self.exception = Some(e.to_string());
+            self.writer.event("output", json!({ "category": "stderr", "output": format!("CHIP-8 stopped: {}\n", e) }));
+            self.prompt(chip8);
+        }
+
+        self.writer.event("exited", json!({ "exitCode": if result.is_ok() { 0 } else { 1 } }));
+        self.writer.event("terminated", json!({}));
+
+        // The client still asks for the debuggee to disconnect
+        while !self.disconnected {
+            match self.receive() {
+                Some(request) => {
+                    self.handle(chip8, &request);
+                },
+                None => break,
+            }
+        }
+    }
+
+    // Returns the error reading the messages of the client, if that is how the connection ended
+    pub fn get_connection_error(&self) -> Option<&str> {
+        self.connection_error.as_deref()
+    }
+
+    // Waits for the next request, or returns None once the connection ended
+    fn receive(&mut self) -> Option<Value> {
+        match self.requests.recv() {
+            Ok(Ok(request)) => Some(request),
+            Ok(Err(e)) => {
+                self.connection_error = Some(e);
+                None
+            },
+            Err(_) => None,
+        }
+    }
+
+    // Reads the Octo source of the chosen path, unless it was already read
+    fn load_source(&mut self, path: &str) -> Result<SourceMap, String> {
+        if let Some(map) = self.sources.get(path) {
+            return Ok(map.clone());
+        }
+        let source = std::fs::read_to_string(path).map_err(|e| format!("Octo source could not be read on path '{}': {}", path, e))?;
+        let map = SourceMap::from_octo(&source).map_err(|e| format!("Octo source '{}' could not be read: {}", path, e))?;
+        self.sources.insert(path.to_string(), map.clone());
+        Ok(map)
+    }
+
+    // Returns the source stack frames are shown in, and its map
+    fn get_program_source(&self) -> Option<(&String, &SourceMap)> {
+        match &self.program_source {
+            Some(path) => self.sources.get_key_value(path),
+            None => self.sources.iter().next(),
+        }
+    }
+
+    // Adds a breakpoint with the condition, hit condition and log message chosen by the client
+    fn add_breakpoint(&mut self, address: u16, requested: &Value) -> Result<u32, String> {
+        let text = |name: &str| requested[name].as_str().filter(|text| !text.trim().is_empty()).map(String::from);
+
+        let condition = match (text("condition"), text("hitCondition").map(|text| hit_condition(&text))) {
+            (Some(condition), Some(hits)) => Some(format!("({}) && {}", condition, hits)),
+            (condition, hits) => condition.or(hits),
+        };
+        let condition = condition.map(|condition| condition.parse::<Expression>()).transpose()?;
+        let message = text("logMessage").map(|message| message.parse::<LogMessage>()).transpose()?;
+        Ok(self.debugger.add_breakpoint(Some(address), condition, message))
+    }
+
+    // Replaces the breakpoints of an Octo source by those on the chosen lines
+    fn set_breakpoints(&mut self, arguments: &Value) -> Result<Value, String> {
+        let path = arguments["source"]["path"].as_str().ok_or("The source has no path")?.to_string();
+        for id in self.source_breakpoints.remove(&path).unwrap_or_default() {
+            self.debugger.remove_breakpoint(id);
+        }
+
+        let map = self.load_source(&path);
+        let mut ids = Vec::new();
+        let mut breakpoints = Vec::new();
+        for requested in arguments["breakpoints"].as_array().into_iter().flatten() {
+            let line = requested["line"].as_u64().unwrap_or(0) as usize;
+
+            // Breakpoints on lines without any code move to the next line with some
+            let result = map.clone()
+                .and_then(|map| map.get_address(line).ok_or_else(|| format!("No code at or after line {}", line)))
+                .and_then(|(line, address)| Ok((self.add_breakpoint(address, requested)?, line, address)));
+            breakpoints.push(match result {
+                Ok((id, line, address)) => {
+                    ids.push(id);
+                    json!({ "id": id, "verified": true, "line": line, "instructionReference": format_reference(address) })
+                },
+                Err(e) => json!({ "verified": false, "line": line, "message": e }),
+            });
+        }
+        self.source_breakpoints.insert(path, ids);
+        Ok(json!({ "breakpoints": breakpoints }))
+    }
+
+    // Replaces the breakpoints on instructions, such as those set in the disassembly
+    fn set_instruction_breakpoints(&mut self, arguments: &Value) -> Result<Value, String> {
+        for id in std::mem::take(&mut self.instruction_breakpoints) {
+            self.debugger.remove_breakpoint(id);
+        }
+
+        let mut breakpoints = Vec::new();
+        for requested in arguments["breakpoints"].as_array().into_iter().flatten() {
+            let result = parse_reference(&requested["instructionReference"])
+                .map(|address| address.wrapping_add(requested["offset"].as_i64().unwrap_or(0) as u16))
+                .and_then(|address| Ok((self.add_breakpoint(address, requested)?, address)));
+            breakpoints.push(match result {
+                Ok((id, address)) => {
+                    self.instruction_breakpoints.push(id);
+                    json!({ "id": id, "verified": true, "instructionReference": format_reference(address) })
+                },
+                Err(e) => json!({ "verified": false, "message": e }),
+            });
+        }
+        Ok(json!({ "breakpoints": breakpoints }))
+    }
+
+    // Returns the source and line of the chosen address, as the fields of a stack frame or instruction
+    fn locate(&self, address: u16) -> Value {
+        let location = self.get_program_source()
+            .and_then(|(path, map)| map.get_line(address).map(|line| (path, line)));
+        match location {
+            Some((path, line)) => json!({ "source": { "path": path }, "line": line, "column": 1 }),
+            None => json!({ "line": 0, "column": 0 }),
+        }
+    }
+
+    /* Returns the stack frames: the instruction at the PC, then the calls of the subroutines on
+     * the stack, innermost first */
+    fn stack_trace(&self, chip8: &Chip8) -> Value {
+        let pc = chip8.get_register(Register::Pc);
+        let calls = chip8.get_stack().iter().rev().map(|address| address.wrapping_sub(2));
+
+        let frames: Vec<Value> = std::iter::once(pc).chain(calls).enumerate()
+            .map(|(id, address)| {
+                let mut frame = self.locate(address);
+                frame["id"] = json!(id);
+                frame["name"] = json!(format!("{:04X}  {}", address, format_instruction_at(chip8, address)));
+                frame["instructionPointerReference"] = json!(format_reference(address));
+                frame
+            })
+            .collect();
+        json!({ "stackFrames": frames, "totalFrames": frames.len() })
+    }
+
+    // Returns the variables of the registers or of the stack
+    fn variables(&self, chip8: &Chip8, arguments: &Value) -> Result<Value, String> {
+        let registers: Vec<Register> = match arguments["variablesReference"].as_u64() {
+            Some(REGISTERS_REFERENCE) => (0..16).map(Register::V)
+                .chain([Register::I, Register::Pc, Register::Sp, Register::Dt, Register::St])
+                .collect(),
+            Some(STACK_REFERENCE) => (1..=chip8.get_stack().len() as u8).map(Register::Stack).collect(),
+            _ => return Err(String::from("Unknown variables")),
+        };
+        let variables: Vec<Value> = registers.into_iter().map(|register| register_variable(chip8, register)).collect();
+        Ok(json!({ "variables": variables }))
+    }
+
+    // Returns the instructions around an address, each 2 bytes long except F000 (whose address is part of it)
+    fn disassemble(&self, chip8: &Chip8, arguments: &Value) -> Result<Value, String> {
+        // There cannot be more instructions than bytes of memory
+        let count = arguments["instructionCount"].as_u64().unwrap_or(0).min(chip8.get_memory().len() as u64);
+        let reference = parse_reference(&arguments["memoryReference"])?;
+        let mut address = arguments["instructionOffset"].as_i64().unwrap_or(0).checked_mul(2)
+            .and_then(|offset| offset.checked_add(arguments["offset"].as_i64().unwrap_or(0)))
+            .and_then(|offset| offset.checked_add(reference as i64))
+            .ok_or_else(|| format!("invalid offset from address {}", format_reference(reference)))?;
+
+        let mut instructions = Vec::new();
+        for _ in 0..count {
+            let instruction = match u16::try_from(address) {
+                Ok(valid) => {
+                    let mut instruction = self.locate(valid);
+                    instruction["address"] = json!(format_reference(valid));
+                    instruction["instructionBytes"] = json!(format!("{:04X}", read_word(chip8, valid)));
+                    instruction["instruction"] = json!(format_instruction_at(chip8, valid));
+                    address += read_instruction(chip8, valid).map_or(2, |instruction| instruction.get_length()) as i64;
+                    instruction
+                },
+                Err(_) => {
+                    let instruction = json!({ "address": format_reference(address as u16), "instruction": "", "presentationHint": "invalid" });
+                    address += 2;
+                    instruction
+                },
+            };
+            instructions.push(instruction);
+        }
+        Ok(json!({ "instructions": instructions }))
+    }
+
+    /* Evaluates an expression of the breakpoint conditions. In the debug console, the commands
+     * of the debugger prompt that do not resume running can be used too, such as "watch" */
+    fn evaluate(&mut self, chip8: &mut Chip8, arguments: &Value) -> Result<Value, String> {
+        let text = arguments["expression"].as_str().unwrap_or_default();
+        let error = match text.parse::<Expression>().and_then(|expression| expression.evaluate(chip8, 0)) {
+            Ok(value) if value >= 0 => return Ok(json!({ "result": format!("{:#X} ({})", value, value), "variablesReference": 0 })),
+            Ok(value) => return Ok(json!({ "result": value.to_string(), "variablesReference": 0 })),
+            Err(e) => e,
+        };
+
+        let command = match (arguments["context"].as_str(), text.parse::<DebugCommand>()) {
+            (Some("repl"), Ok(command)) => command,
+            _ => return Err(error),
+        };
+        match command {
+            DebugCommand::Step(_) | DebugCommand::Next | DebugCommand::Finish | DebugCommand::Continue | DebugCommand::Until(_) | DebugCommand::Quit => {
+                Err(String::from("Use the buttons of the editor to resume running or stop"))
+            },
+            command => {
+                self.debugger.execute(chip8, command);
+                Ok(json!({ "result": "", "variablesReference": 0 }))
+            },
+        }
+    }
+
+    // Resumes running with a command of the debugger, if CHIP-8 is paused
+    fn resume(&mut self, chip8: &mut Chip8, command: DebugCommand) -> Option<DebugAction> {
+        if self.paused {
+            self.debugger.execute(chip8, command)
+        } else {
+            None
+        }
+    }
+
+    // Answers a request, returning what the frontend should do if it resumes running or quits
+    fn handle(&mut self, chip8: &mut Chip8, request: &Value) -> Option<DebugAction> {
+        let arguments = &request["arguments"];
+        let mut action = None;
+
+        let result = match request["command"].as_str().unwrap_or_default() {
+            "initialize" => Ok(capabilities()),
+            "configurationDone" => {
+                self.configured = true;
+                Ok(Value::Null)
+            },
+            "setBreakpoints" => self.set_breakpoints(arguments),
+            "setInstructionBreakpoints" => self.set_instruction_breakpoints(arguments),
+            "setExceptionBreakpoints" => Ok(json!({ "breakpoints": [] })),
+            "threads" => Ok(json!({ "threads": [{ "id": THREAD_ID, "name": "CHIP-8" }] })),
+            "stackTrace" => Ok(self.stack_trace(chip8)),
+            "scopes" => Ok(json!({ "scopes": [
+                { "name": "Registers", "presentationHint": "registers", "variablesReference": REGISTERS_REFERENCE, "expensive": false },
+                { "name": "Stack", "variablesReference": STACK_REFERENCE, "expensive": false },
+            ] })),
+            "variables" => self.variables(chip8, arguments),
+            "setVariable" => set_variable(chip8, arguments),
+            "evaluate" => self.evaluate(chip8, arguments),
+            "readMemory" => read_memory(chip8, arguments),
+            "writeMemory" => write_memory(chip8, arguments),
+            "disassemble" => self.disassemble(chip8, arguments),
+            "continue" => {
+                action = self.resume(chip8, DebugCommand::Continue);
+                Ok(json!({ "allThreadsContinued": true }))
+            },
+            "next" => {
+                action = self.resume(chip8, DebugCommand::Next);
+                Ok(Value::Null)
+            },
+            "stepIn" => {
+                action = self.resume(chip8, DebugCommand::Step(1));
+                Ok(Value::Null)
+            },
+            "stepOut" if chip8.get_stack().is_empty() => Err(String::from("Not in a subroutine, so there is nothing to step out of")),
+            "stepOut" => {
+                action = self.resume(chip8, DebugCommand::Finish);
+                Ok(Value::Null)
+            },
+            "pause" => {
+                self.debugger.pause();
+                Ok(Value::Null)
+            },
+            "disconnect" | "terminate" => {
+                self.disconnected = true;
+                action = Some(DebugAction::Quit);
+                Ok(Value::Null)
+            },
+            command => Err(format!("Unsupported request '{}'", command)),
+        };
+        self.writer.respond(request, result);
+        action
+    }
+
+    // Tells the client why CHIP-8 stopped
+    fn send_stopped(&mut self) {
+        let reason = self.debugger.get_stop_reason();
+        let mut body = json!({ "threadId": THREAD_ID, "allThreadsStopped": true });
+
+        let description = if let Some(e) = &self.exception {
+            body["text"] = json!(e);
+            "exception"
+        } else if std::mem::take(&mut self.entry) {
+            "entry"
+        } else {
+            match reason {
+                Some(StopReason::Breakpoint(id)) => {
+                    body["hitBreakpointIds"] = json!([id]);
+                    if self.instruction_breakpoints.contains(&id) { "instruction breakpoint" } else { "breakpoint" }
+                },
+                Some(StopReason::Step | StopReason::Until) => "step",
+                Some(StopReason::Watchpoint) => "data breakpoint",
+                Some(StopReason::Pause) | None => "pause",
+            }
+        };
+        body["reason"] = json!(description);
+        if let Some(reason) = reason {
+            body["description"] = json!(reason.to_string());
+        }
+        self.writer.event("stopped", body);
+    }
+}
+
+impl DebugHook for DapServer<'_> {
+    fn should_break(&mut self, chip8: &Chip8) -> bool {
+        self.debugger.should_break(chip8)
+    }
+
+    fn pause(&mut self) {
+        self.debugger.pause();
+    }
+
+    // Answers requests until the client resumes running, or until it disconnects
+    fn prompt(&mut self, chip8: &mut Chip8) -> DebugAction {
+        self.send_stopped();
+        self.paused = true;
+
+        let action = loop {
+            match self.receive() {
+                Some(request) => {
+                    if let Some(action) = self.handle(chip8, &request) {
+                        break action;
+                    }
+                },
+                None => break DebugAction::Quit,
+            }
+        };
+        self.paused = false;
+        action
+    }
+
+    // Answers the requests received while running. CHIP-8 only starts once the client is done setting breakpoints
+    fn poll(&mut self, chip8: &mut Chip8) -> DebugAction {
+        loop {
+            let request = if self.configured {
+                match self.requests.try_recv() {
+                    Ok(Ok(request)) => request,
+                    Ok(Err(e)) => {
+                        self.connection_error = Some(e);
+                        return DebugAction::Quit;
+                    },
+                    Err(TryRecvError::Empty) => return DebugAction::Resume,
+                    Err(TryRecvError::Disconnected) => return DebugAction::Quit,
+                }
+            } else {
+                match self.receive() {
+                    Some(request) => request,
+                    None => return DebugAction::Quit,
+                }
+            };
+
+            if self.handle(chip8, &request) == Some(DebugAction::Quit) {
+                return DebugAction::Quit;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+#[path ="./dap_test.rs"]
+mod dap_test;
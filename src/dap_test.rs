@@ -0,0 +1,228 @@
+use super::*;
+use crate::quirks::Quirks;
+use std::io::Cursor;
+
+// Sets V0, calls a subroutine that increments V1, then loops forever
+const ROM: [u8; 10] = [0x60, 0x05, 0x22, 0x06, 0x12, 0x04, 0x71, 0x01, 0x00, 0xEE];
+
+// Octo source of the ROM
+const SOURCE: &str = "\
+: main
+  v0 := 5
+  sub
+  loop again
+: sub
+  v1 += 1
+  return
+";
+
+// Output shared with the test, which keeps every message sent
+#[derive(Clone, Default)]
+struct SharedOutput(Rc<RefCell<Vec<u8>>>);
+
+impl Write for SharedOutput {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl SharedOutput {
+
+    // Returns the messages sent so far
+    fn messages(&self) -> Vec<Value> {
+        let data = self.0.borrow().clone();
+        let mut reader = Cursor::new(data);
+        let mut messages = Vec::new();
+        while let Some(message) = read_message(&mut reader).unwrap() {
+            messages.push(message);
+        }
+        messages
+    }
+
+    // Returns the body of the last response to the chosen command
+    fn response(&self, command: &str) -> Value {
+        self.messages().into_iter().rev()
+            .find(|m| m["type"] == "response" && m["command"] == command)
+            .map(|m| m["body"].clone())
+            .unwrap_or_else(|| panic!("no response to {}", command))
+    }
+
+    // Returns the bodies of the events with the chosen name
+    fn events(&self, event: &str) -> Vec<Value> {
+        self.messages().into_iter().filter(|m| m["event"] == event).map(|m| m["body"].clone()).collect()
+    }
+}
+
+// Sends requests to the server, numbered in order
+fn send(sender: &mpsc::Sender<Result<Value, String>>, requests: &[(&str, Value)]) {
+    for (seq, (command, arguments)) in requests.iter().enumerate() {
+        sender.send(Ok(json!({ "seq": seq, "type": "request", "command": command, "arguments": arguments }))).unwrap();
+    }
+}
+
+#[test]
+fn test_message_framing() -> Result<(), String> {
+    let mut data = Vec::new();
+    write_message(&mut data, &json!({ "seq": 1 })).unwrap();
+    assert_eq!(String::from_utf8(data.clone()).unwrap(), "Content-Length: 9\r\n\r\n{\"seq\":1}");
+
+    let mut reader = Cursor::new(data);
+    assert_eq!(read_message(&mut reader)?, Some(json!({ "seq": 1 })));
+    assert_eq!(read_message(&mut reader)?, None);
+
+    assert!(read_message(&mut Cursor::new("Content-Type: json\r\n\r\n{}")).is_err());
+    assert!(read_message(&mut Cursor::new("Content-Length: 99999999999999\r\n\r\n{}")).unwrap_err().contains("over the limit"));
+
+    // An invalid message ends the connection, and its error is returned instead of being printed
+    let connection = DapConnection::new(Cursor::new("Content-Type: json\r\n\r\n{}"), Box::new(SharedOutput::default()));
+    let mut console = connection.console();
+    let mut server = DapServer::new(connection, &mut console);
+    let error = server.launch(Ok).unwrap_err();
+    assert!(error.starts_with("Debug adapter client sent an invalid message"));
+    Ok(())
+}
+
+#[test]
+fn test_hit_condition() {
+    assert_eq!(hit_condition("5"), "hits >= (5)");
+    assert_eq!(hit_condition(" == 5"), "hits == 5");
+    assert_eq!(hit_condition("%3"), "hits % (3) == 0");
+}
+
+#[test]
+fn test_session() -> Result<(), String> {
+    let path = std::env::temp_dir().join(format!("chip8_dap_test_{}.8o", std::process::id()));
+    std::fs::write(&path, SOURCE).unwrap();
+    let path = path.to_string_lossy().to_string();
+
+    let (sender, requests) = mpsc::channel();
+    let output = SharedOutput::default();
+    let connection = DapConnection { requests, writer: DapWriter::new(Box::new(output.clone())) };
+    let mut console = connection.console();
+    let mut server = DapServer::new(connection, &mut console);
+    let mut chip8 = Chip8::new(Quirks::default());
+    chip8.load_rom(&ROM)?;
+
+    send(&sender, &[
+        ("initialize", json!({ "adapterID": "chip-8" })),
+        ("launch", json!({ "program": "game.ch8", "source": path, "stopOnEntry": true })),
+    ]);
+    assert_eq!(server.launch(Ok)?, Some(String::from("game.ch8")));
+    assert_eq!(output.response("initialize")["supportsInstructionBreakpoints"], true);
+    assert_eq!(output.events("initialized").len(), 1);
+
+    // Breakpoints on lines without code move to the next line with some
+    send(&sender, &[
+        ("setBreakpoints", json!({ "source": { "path": path }, "breakpoints": [{ "line": 5 }, { "line": 9 }] })),
+        ("configurationDone", json!({})),
+    ]);
+    assert_eq!(server.poll(&mut chip8), DebugAction::Resume);
+    let breakpoints = output.response("setBreakpoints");
+    assert_eq!(breakpoints["breakpoints"][0], json!({ "id": 1, "verified": true, "line": 6, "instructionReference": "0x0206" }));
+    assert_eq!(breakpoints["breakpoints"][1]["verified"], false);
+
+    // Stops before the first instruction, then at the breakpoint
+    assert_eq!(chip8.run_frame_until(100, |c| server.should_break(c)), Ok(false));
+    send(&sender, &[("threads", json!({})), ("continue", json!({ "threadId": 1 }))]);
+    assert_eq!(server.prompt(&mut chip8), DebugAction::Resume);
+
+    assert_eq!(chip8.run_frame_until(100, |c| server.should_break(c)), Ok(false));
+    send(&sender, &[
+        ("stackTrace", json!({ "threadId": 1 })),
+        ("variables", json!({ "variablesReference": REGISTERS_REFERENCE })),
+        ("variables", json!({ "variablesReference": STACK_REFERENCE })),
+        ("readMemory", json!({ "memoryReference": "0x0206", "count": 4 })),
+        ("setVariable", json!({ "variablesReference": REGISTERS_REFERENCE, "name": "V1", "value": "v0 + 1" })),
+        ("evaluate", json!({ "expression": "v1", "context": "watch" })),
+        ("stepOut", json!({ "threadId": 1 })),
+    ]);
+    assert_eq!(server.prompt(&mut chip8), DebugAction::Resume);
+
+    let frames = output.response("stackTrace")["stackFrames"].clone();
+    assert_eq!(frames.as_array().map(Vec::len), Some(2));
+    assert_eq!(frames[0]["name"], "0206  ADD V1, 0x01");
+    assert_eq!(frames[0]["line"], 6);
+    assert_eq!(frames[0]["source"]["path"], path.as_str());
+    assert_eq!(frames[1]["instructionPointerReference"], "0x0202");
+    assert_eq!(frames[1]["line"], 3);
+
+    assert_eq!(output.messages().iter().filter(|m| m["command"] == "variables").count(), 2);
+    assert_eq!(output.response("variables")["variables"][0], json!({
+        "name": "STACK1", "value": "0x0204", "variablesReference": 0, "memoryReference": "0x0204",
+    }));
+    assert_eq!(output.response("readMemory"), json!({ "address": "0x0206", "data": "cQEA7g==", "unreadableBytes": 0 }));
+    assert_eq!(output.response("setVariable")["value"], "0x06");
+    assert_eq!(output.response("evaluate")["result"], "0x6 (6)");
+
+    // Stepping out stops after the return
+    assert_eq!(chip8.run_frame_until(100, |c| server.should_break(c)), Ok(false));
+    assert_eq!(chip8.get_register(Register::Pc), 0x204);
+    send(&sender, &[("disconnect", json!({}))]);
+    assert_eq!(server.prompt(&mut chip8), DebugAction::Quit);
+
+    let reasons: Vec<Value> = output.events("stopped").iter().map(|e| e["reason"].clone()).collect();
+    assert_eq!(reasons, vec![json!("entry"), json!("breakpoint"), json!("step")]);
+    assert_eq!(output.events("stopped")[1]["hitBreakpointIds"], json!([1]));
+
+    std::fs::remove_file(&path).unwrap();
+    Ok(())
+}
+
+#[test]
+fn test_memory_and_console() -> Result<(), String> {
+    let (sender, requests) = mpsc::channel();
+    let output = SharedOutput::default();
+    let connection = DapConnection { requests, writer: DapWriter::new(Box::new(output.clone())) };
+    let mut console = connection.console();
+    let mut server = DapServer::new(connection, &mut console);
+    let mut chip8 = Chip8::new(Quirks::default());
+    chip8.load_rom(&ROM)?;
+    server.paused = true;
+
+    let requests = [
+        ("writeMemory", json!({ "memoryReference": "0x0300", "data": "AQI=" })),
+        ("readMemory", json!({ "memoryReference": "0xFFFE", "offset": -0xFCFE, "count": 3 })),
+        ("evaluate", json!({ "expression": "watch 300", "context": "repl" })),
+        ("evaluate", json!({ "expression": "c", "context": "repl" })),
+        ("disassemble", json!({ "memoryReference": "0x0200", "instructionOffset": -1, "instructionCount": 2 })),
+    ];
+    for (idx, (command, arguments)) in requests.iter().enumerate() {
+        let request = json!({ "seq": idx, "type": "request", "command": command, "arguments": arguments });
+        assert_eq!(server.handle(&mut chip8, &request), None);
+    }
+    drop(sender);
+
+    assert_eq!(output.response("writeMemory")["bytesWritten"], 2);
+    assert_eq!(output.response("readMemory"), json!({ "address": "0x0300", "data": "AQIA", "unreadableBytes": 0 }));
+
+    // Counts past the end of memory are unreadable, and offsets out of memory are refused
+    let read = |reference: &str, offset: i64, count: u64| read_memory(&chip8, &json!({ "memoryReference": reference, "offset": offset, "count": count }));
+    assert_eq!(read("0x0FFF", 0, u64::MAX)?["unreadableBytes"], u64::MAX - 1);
+    assert_eq!(read("0x0FFF", 2, 3)?, json!({ "address": "0x1001", "data": "", "unreadableBytes": 3 }));
+    assert!(read("0x0200", i64::MAX, 1).is_err());
+    assert!(read("0x0200", -0x201, 1).is_err());
+
+    // Commands of the prompt can be used in the debug console, but not those that resume running
+    assert_eq!(chip8.get_watchpoints().len(), 1);
+    assert!(output.events("output").iter().any(|e| e["output"] == "Watchpoint on write 0x0300\n"));
+    assert!(output.messages().iter().any(|m| m["command"] == "evaluate" && m["success"] == false));
+
+    let instructions = output.response("disassemble")["instructions"].clone();
+    assert_eq!(instructions[0]["address"], "0x01FE");
+    assert_eq!(instructions[1], json!({ "address": "0x0200", "instructionBytes": "6005", "instruction": "LD V0, 0x05", "line": 0, "column": 0 }));
+
+    // Offsets that overflow are refused, and counts stop at the size of memory
+    let disassemble = |offset: i64, count: u64| {
+        server.disassemble(&chip8, &json!({ "memoryReference": "0x0200", "instructionOffset": offset, "instructionCount": count }))
+    };
+    assert!(disassemble(i64::MAX, 1).is_err());
+    assert!(disassemble(i64::MIN / 2 - 1, 1).is_err());
+    let instructions = disassemble(0, u64::MAX)?["instructions"].as_array().map(Vec::len);
+    assert_eq!(instructions, Some(chip8.get_memory().len()));
+    Ok(())
+}
@@ -0,0 +1,742 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt;
+use std::io::{self, BufRead, Write};
+use std::str::FromStr;
+
+use crate::chip8::Chip8;
+use crate::cpu::Register;
+use crate::disasm::{format_instruction, Syntax};
+use crate::expression::{Expression, LogMessage};
+use crate::instruction::Instruction;
+use crate::watchpoint::{Access, Watchpoint};
+
+const PROMPT: &str = "(chip-8) ";
+
+// Bytes shown by "x" and instructions shown by "list" when no count is chosen
+const DEFAULT_EXAMINE_LENGTH: usize = 16;
+const DEFAULT_LIST_COUNT: usize = 8;
+
+const HELP: &str = "\
+Commands (addresses and values are hexadecimal, counts and breakpoint numbers are decimal):
+  s, step [N]          run N instructions (1 by default)
+  n, next              run one instruction, running a whole subroutine for 2NNN calls
+  f, finish            run until the current subroutine returns
+  c, continue          run until a breakpoint
+  u, until ADDR        run until the PC reaches ADDR or a breakpoint
+  b, break [ADDR]      add a breakpoint at ADDR, or list the breakpoints
+  b [ADDR] if COND     add a breakpoint that only pauses when COND is true, such as
+                       'b 2A4 if v3 > 5 && [i] == 0xFF' or 'b if dt == 0' (at any address)
+  log ADDR|* MESSAGE   add a logpoint, printing MESSAGE with the values of the expressions between
+                       braces instead of pausing, such as 'log 2A4 V3 is {v3}, [I] is {[i]:x}'
+  cond N [COND]        change the condition of breakpoint N, or remove it
+  d, delete [N]        delete breakpoint N, or every breakpoint
+  watch [RANGE] [rwx]  pause after instructions that read, write (by default) or execute
+                       an address of RANGE (ADDR or ADDR-END), or list the watchpoints
+  unwatch [RANGE]      delete the watchpoints of RANGE, or every watchpoint
+  r, regs              print the registers, timers and stack
+  p, print REG         print a register: V0-VF, I, PC, SP, DT, ST or STACK1-STACKF
+  set REG VALUE        change a register
+  stack                print the return addresses on the stack
+  x ADDR [LEN]         print LEN bytes of memory starting at ADDR (16 by default)
+  w, write ADDR BYTE.. write bytes to memory starting at ADDR
+  l, list [ADDR] [N]   print N instructions starting at ADDR (8 from the PC by default)
+  h, help              print this help
+  q, quit              quit the emulator
+An empty line repeats the last command.";
+
+
+// Terminal the debugger prompt reads commands from and prints to
+pub trait DebugConsole {
+
+    // Shows the prompt and reads a line, or returns None when the input has ended
+    fn read_line(&mut self, prompt: &str) -> Option<String>;
+
+    // Shows text to the user, followed by a new line
+    fn print(&mut self, text: &str);
+}
+
+// Console of the standard input and output
+pub struct StdConsole;
+
+impl DebugConsole for StdConsole {
+    fn read_line(&mut self, prompt: &str) -> Option<String> {
+        print!("{}", prompt);
+        io::stdout().flush().ok()?;
+
+        let mut line = String::new();
+        match io::stdin().lock().read_line(&mut line) {
+            Ok(0) | Err(_) => None,
+            Ok(_) => Some(line),
+        }
+    }
+
+    fn print(&mut self, text: &str) {
+        println!("{}", text);
+    }
+}
+
+// Console that reads lines from a script and keeps everything printed, for tests
+#[derive(Default)]
+pub struct ScriptedConsole {
+    lines: VecDeque<String>,
+    pub output: String,
+}
+
+impl ScriptedConsole {
+
+    // Creates new console that reads the chosen lines in order
+    pub fn new(lines: &[&str]) -> Self {
+        Self {
+            lines: lines.iter().map(|line| line.to_string()).collect(),
+            output: String::new(),
+        }
+    }
+}
+
+impl DebugConsole for ScriptedConsole {
+    fn read_line(&mut self, _prompt: &str) -> Option<String> {
+        self.lines.pop_front()
+    }
+
+    fn print(&mut self, text: &str) {
+        self.output.push_str(text);
+        self.output.push('\n');
+    }
+}
+
+
+// Commands of the debugger prompt
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DebugCommand {
+    Step(u32),
+    Next,
+    Finish,
+    Continue,
+    Until(u16),
+    Break { address: Option<u16>, condition: Option<Expression> },
+    Log { address: Option<u16>, message: LogMessage },
+    Condition { id: u32, condition: Option<Expression> },
+    Breakpoints,
+    Delete(Option<u32>),
+    Watch { start: u16, end: u16, accesses: Vec<Access> },
+    Watchpoints,
+    Unwatch(Option<(u16, u16)>),
+    Registers,
+    Print(Register),
+    Set(Register, u16),
+    Stack,
+    Examine { address: u16, length: usize },
+    Write { address: u16, bytes: Vec<u8> },
+    List { address: Option<u16>, count: usize },
+    Help,
+    Quit,
+}
+
+// Parses a hexadecimal number, with or without the 0x prefix
+fn parse_hex(s: &str) -> Result<u16, String> {
+    let digits = s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")).unwrap_or(s);
+    u16::from_str_radix(digits, 16).map_err(|_| format!("invalid hexadecimal number '{}'", s))
+}
+
+// Parses a range of addresses written as ADDR or ADDR-END, both ends included
+pub fn parse_range(s: &str) -> Result<(u16, u16), String> {
+    let (start, end) = match s.split_once('-') {
+        Some((start, end)) => (parse_hex(start)?, parse_hex(end)?),
+        None => (parse_hex(s)?, parse_hex(s)?),
+    };
+    if start > end {
+        return Err(format!("invalid range '{}' (the end is lower than the start)", s));
+    }
+    Ok((start, end))
+}
+
+// Parses the kinds of access of a watchpoint, one letter each, such as "rw"
+fn parse_accesses(s: &str) -> Result<Vec<Access>, String> {
+    let mut accesses: Vec<Access> = s.chars().map(|c| c.to_string().parse()).collect::<Result<_, _>>()?;
+    accesses.sort();
+    accesses.dedup();
+    Ok(accesses)
+}
+
+// Splits the first word from the rest of the line
+fn split_word(s: &str) -> (&str, &str) {
+    let s = s.trim();
+    match s.split_once(char::is_whitespace) {
+        Some((word, rest)) => (word, rest.trim_start()),
+        None => (s, ""),
+    }
+}
+
+// Parses the rest of a break command: [ADDR] [if COND]
+fn parse_break(s: &str) -> Result<DebugCommand, String> {
+    let (first, rest) = split_word(s);
+    let (address, rest) = match first {
+        "if" => (None, s),
+        _ => (Some(parse_hex(first)?), rest),
+    };
+
+    let condition = match split_word(rest) {
+        ("", _) => None,
+        ("if", condition) => Some(condition.parse()?),
+        _ => return Err(format!("invalid breakpoint '{}' (expected ADDR, ADDR if COND or if COND)", s)),
+    };
+    Ok(DebugCommand::Break { address, condition })
+}
+
+// Parses a breakpoint number
+fn parse_id(s: &str) -> Result<u32, String> {
+    s.parse().map_err(|_| format!("invalid breakpoint number '{}'", s))
+}
+
+// Parses a decimal count, which must not be 0
+fn parse_count(s: &str) -> Result<usize, String> {
+    s.parse().ok().filter(|count| *count > 0).ok_or_else(|| format!("invalid count '{}'", s))
+}
+
+impl FromStr for DebugCommand {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let words: Vec<&str> = s.split_whitespace().collect();
+        let usage = || format!("invalid command '{}' (type \"help\" for the list of commands)", s.trim());
+
+        // Conditions and messages take the rest of the line, spaces included
+        let (name, rest) = split_word(s);
+        match (name, rest) {
+            ("b" | "break", rest) if !rest.is_empty() => return parse_break(rest),
+            ("log", rest) => {
+                let (address, message) = split_word(rest);
+                if message.is_empty() {
+                    return Err(usage());
+                }
+                let address = if address == "*" { None } else { Some(parse_hex(address)?) };
+                return Ok(DebugCommand::Log { address, message: message.parse()? });
+            },
+            ("cond" | "condition", rest) if !rest.is_empty() => {
+                let (id, condition) = split_word(rest);
+                let condition = if condition.is_empty() { None } else { Some(condition.parse()?) };
+                return Ok(DebugCommand::Condition { id: parse_id(id)?, condition });
+            },
+            _ => (),
+        }
+
+        let command = match words[..] {
+            ["s" | "step"] => DebugCommand::Step(1),
+            ["s" | "step", count] => DebugCommand::Step(parse_count(count)? as u32),
+            ["n" | "next"] => DebugCommand::Next,
+            ["f" | "finish"] => DebugCommand::Finish,
+            ["c" | "continue"] => DebugCommand::Continue,
+            ["u" | "until", address] => DebugCommand::Until(parse_hex(address)?),
+            ["b" | "break"] => DebugCommand::Breakpoints,
+            ["d" | "delete"] => DebugCommand::Delete(None),
+            ["d" | "delete", id] => DebugCommand::Delete(Some(parse_id(id)?)),
+            ["watch"] => DebugCommand::Watchpoints,
+            ["watch", range] => {
+                let (start, end) = parse_range(range)?;
+                DebugCommand::Watch { start, end, accesses: vec![Access::Write] }
+            },
+            ["watch", range, accesses] => {
+                let (start, end) = parse_range(range)?;
+                DebugCommand::Watch { start, end, accesses: parse_accesses(accesses)? }
+            },
+            ["unwatch"] => DebugCommand::Unwatch(None),
+            ["unwatch", range] => DebugCommand::Unwatch(Some(parse_range(range)?)),
+            ["r" | "regs"] => DebugCommand::Registers,
+            ["p" | "print", register] => DebugCommand::Print(register.parse()?),
+            ["set", register, value] => DebugCommand::Set(register.parse()?, parse_hex(value)?),
+            ["stack"] => DebugCommand::Stack,
+            ["x", address] => DebugCommand::Examine { address: parse_hex(address)?, length: DEFAULT_EXAMINE_LENGTH },
+            ["x", address, length] => DebugCommand::Examine { address: parse_hex(address)?, length: parse_count(length)? },
+            ["w" | "write", address, ref bytes @ ..] if !bytes.is_empty() => DebugCommand::Write {
+                address: parse_hex(address)?,
+                bytes: bytes.iter()
+                    .map(|byte| parse_hex(byte).and_then(|b| u8::try_from(b).map_err(|_| format!("{} is not a byte", byte))))
+                    .collect::<Result<Vec<u8>, String>>()?,
+            },
+            ["l" | "list"] => DebugCommand::List { address: None, count: DEFAULT_LIST_COUNT },
+            ["l" | "list", address] => DebugCommand::List { address: Some(parse_hex(address)?), count: DEFAULT_LIST_COUNT },
+            ["l" | "list", address, count] => DebugCommand::List { address: Some(parse_hex(address)?), count: parse_count(count)? },
+            ["h" | "help"] => DebugCommand::Help,
+            ["q" | "quit"] => DebugCommand::Quit,
+            _ => return Err(usage()),
+        };
+        Ok(command)
+    }
+}
+
+
+// What the frontend should do after the prompt
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DebugAction {
+
+    // Keep running the emulator until the debugger pauses it again
+    Resume,
+
+    // Quit the emulator
+    Quit,
+}
+
+/* Debugger driven by the main loop of the frontend.
+ *
+ * It is asked before each instruction whether to pause, and is given control of CHIP-8
+ * while it is paused */
+pub trait DebugHook {
+
+    // Returns whether CHIP-8 should pause before running the instruction at the PC
+    fn should_break(&mut self, chip8: &Chip8) -> bool;
+
+    // Pauses before the next instruction
+    fn pause(&mut self);
+
+    // Takes control of paused CHIP-8, until it should resume running or quit
+    fn prompt(&mut self, chip8: &mut Chip8) -> DebugAction;
+
+    // Called before each frame while CHIP-8 is running, such as to handle requests from a client
+    fn poll(&mut self, _chip8: &mut Chip8) -> DebugAction {
+        DebugAction::Resume
+    }
+}
+
+// How the debugger lets CHIP-8 run
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Mode {
+
+    // The prompt is shown before the next instruction
+    Paused,
+
+    // Runs until a breakpoint
+    Running,
+
+    // Runs the chosen number of instructions
+    Stepping(u32),
+
+    // Runs until the PC reaches the address
+    Until(u16),
+
+    // Runs until the subroutine called from the chosen stack level returns to the address
+    StepOver { address: u16, sp: u16 },
+
+    // Runs until the stack pointer goes below the chosen level, when the subroutine returns
+    StepOut { sp: u16 },
+}
+
+// Why the debugger paused CHIP-8
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StopReason {
+
+    // It was asked to pause, such as by the user or when the input of the prompt ended
+    Pause,
+
+    // A step, next or finish command ended
+    Step,
+
+    // The PC reached the address of an until command
+    Until,
+
+    // The breakpoint with the chosen number was reached
+    Breakpoint(u32),
+
+    // The last instruction made watched memory accesses
+    Watchpoint,
+}
+
+impl fmt::Display for StopReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StopReason::Pause | StopReason::Watchpoint => write!(f, "Paused"),
+            StopReason::Step => write!(f, "Stepped"),
+            StopReason::Until => write!(f, "Reached"),
+            StopReason::Breakpoint(id) => write!(f, "Breakpoint {}", id),
+        }
+    }
+}
+
+/* Breakpoint at an address, or at every instruction if it has none.
+ *
+ * It only pauses when its condition, if any, is true. A logpoint (a breakpoint with a message)
+ * prints the message instead of pausing */
+#[derive(Clone, Debug)]
+struct Breakpoint {
+    id: u32,
+    address: Option<u16>,
+    condition: Option<Expression>,
+    message: Option<LogMessage>,
+
+    // How many times the PC reached the breakpoint, whether its condition was true or not
+    hits: u64,
+}
+
+impl fmt::Display for Breakpoint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let kind = if self.message.is_some() { "Logpoint" } else { "Breakpoint" };
+        match self.address {
+            Some(address) => write!(f, "{} {} at {:#06X}", kind, self.id, address)?,
+            None => write!(f, "{} {} at every instruction", kind, self.id)?,
+        }
+        if let Some(condition) = &self.condition {
+            write!(f, " if {}", condition)?;
+        }
+        if let Some(message) = &self.message {
+            write!(f, ": \"{}\"", message)?;
+        }
+        Ok(())
+    }
+}
+
+/* Step debugger with PC breakpoints and memory watchpoints.
+ *
+ * The frontend asks it before each instruction whether to pause (should_break), and shows
+ * the prompt while it is paused, which reads commands until one of them resumes running.
+ * Watchpoints are kept by the CPU, which records the watched accesses of each instruction,
+ * so the debugger pauses right after the instruction that made them */
+pub struct Debugger<'a> {
+    console: &'a mut dyn DebugConsole,
+    breakpoints: Vec<Breakpoint>,
+
+    // Number of the next breakpoint added
+    next_id: u32,
+
+    mode: Mode,
+
+    // Why CHIP-8 was last paused
+    stop_reason: Option<StopReason>,
+
+    /* Whether CHIP-8 was just resumed, so the instruction the debugger paused on is run
+     * even if it has a breakpoint, and the accesses that paused it are not reported again */
+    resumed: bool,
+
+    // Command repeated when an empty line is entered
+    last_command: Option<DebugCommand>,
+}
+
+impl<'a> Debugger<'a> {
+
+    // Creates new debugger that lets CHIP-8 run until it is paused
+    pub fn new(console: &'a mut dyn DebugConsole) -> Self {
+        Self {
+            console,
+            breakpoints: Vec::new(),
+            next_id: 1,
+            mode: Mode::Running,
+            stop_reason: None,
+            resumed: false,
+            last_command: None,
+        }
+    }
+
+    // Pauses before the next instruction
+    pub fn pause(&mut self) {
+        self.mode = Mode::Paused;
+    }
+
+    // Returns whether the prompt should be shown before running anything else
+    pub fn is_paused(&self) -> bool {
+        self.mode == Mode::Paused
+    }
+
+    // Returns why CHIP-8 was last paused, if it was
+    pub fn get_stop_reason(&self) -> Option<StopReason> {
+        self.stop_reason
+    }
+
+    /* Adds a breakpoint at the chosen address, or at every instruction, returning its number.
+     * It becomes a logpoint if it has a message */
+    pub fn add_breakpoint(&mut self, address: Option<u16>, condition: Option<Expression>, message: Option<LogMessage>) -> u32 {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.breakpoints.push(Breakpoint { id, address, condition, message, hits: 0 });
+        id
+    }
+
+    // Deletes the breakpoint with the chosen number, returning whether it existed
+    pub fn remove_breakpoint(&mut self, id: u32) -> bool {
+        let count = self.breakpoints.len();
+        self.breakpoints.retain(|b| b.id != id);
+        self.breakpoints.len() != count
+    }
+
+    /* Evaluates the breakpoints of the PC, printing the messages of the logpoints.
+     * Returns the number of the first breakpoint that should pause */
+    fn check_breakpoints(&mut self, chip8: &Chip8) -> Option<u32> {
+        let pc = chip8.get_register(Register::Pc);
+        let mut paused_by = None;
+
+        for breakpoint in self.breakpoints.iter_mut().filter(|b| b.address.is_none_or(|address| address == pc)) {
+            breakpoint.hits += 1;
+            let reached = match &breakpoint.condition {
+                Some(condition) => condition.is_true(chip8, breakpoint.hits),
+                None => Ok(true),
+            };
+
+            match (reached, &breakpoint.message) {
+                (Ok(false), _) => (),
+                (Ok(true), Some(message)) => self.console.print(&message.format(chip8, breakpoint.hits)),
+                (Ok(true), None) => paused_by = paused_by.or(Some(breakpoint.id)),
+
+                // A condition that cannot be evaluated pauses, so it can be fixed
+                (Err(e), _) => {
+                    self.console.print(&format!("Condition of breakpoint {} failed: {}", breakpoint.id, e));
+                    paused_by = paused_by.or(Some(breakpoint.id));
+                },
+            }
+        }
+        paused_by
+    }
+
+    // Returns whether CHIP-8 should pause before running the instruction at the PC, pausing if so
+    pub fn should_break(&mut self, chip8: &Chip8) -> bool {
+        let resumed = std::mem::take(&mut self.resumed);
+        let pc = chip8.get_register(Register::Pc);
+
+        let paused_by = if resumed { None } else { self.check_breakpoints(chip8) };
+
+        let sp = chip8.get_register(Register::Sp);
+        let reason = match self.mode {
+            Mode::Paused => Some(StopReason::Pause),
+            Mode::Stepping(0) => Some(StopReason::Step),
+            Mode::Stepping(left) => {
+                self.mode = Mode::Stepping(left - 1);
+                None
+            },
+            Mode::Until(address) if !resumed && pc == address => Some(StopReason::Until),
+            Mode::StepOver { address, sp: level } if pc == address && sp <= level => Some(StopReason::Step),
+            Mode::StepOut { sp: level } if sp < level => Some(StopReason::Step),
+            _ => None,
+        };
+        let reason = reason.or_else(|| paused_by.map(StopReason::Breakpoint));
+
+        let hits = if resumed { &[] } else { chip8.get_watch_hits() };
+        for hit in hits {
+            self.console.print(&hit.to_string());
+        }
+        let reason = reason.or_else(|| (!hits.is_empty()).then_some(StopReason::Watchpoint));
+
+        match reason {
+            Some(reason) => {
+                self.mode = Mode::Paused;
+                self.stop_reason = Some(reason);
+                let location = format_location(chip8, pc);
+                self.console.print(&format!("{} at {}", reason, location));
+                true
+            },
+            None => false,
+        }
+    }
+
+    /* Reads and runs commands until one of them resumes running or quits.
+     * When the input ends, CHIP-8 keeps running until the next breakpoint */
+    pub fn prompt(&mut self, chip8: &mut Chip8) -> DebugAction {
+        loop {
+            let line = match self.console.read_line(PROMPT) {
+                Some(line) => line,
+                None => return self.resume(Mode::Running),
+            };
+
+            let command = if line.trim().is_empty() {
+                match self.last_command.clone() {
+                    Some(command) => command,
+                    None => continue,
+                }
+            } else {
+                match line.parse::<DebugCommand>() {
+                    Ok(command) => command,
+                    Err(e) => {
+                        self.console.print(&e);
+                        continue;
+                    },
+                }
+            };
+            self.last_command = Some(command.clone());
+
+            if let Some(action) = self.execute(chip8, command) {
+                return action;
+            }
+        }
+    }
+
+    // Runs a command, returning what the frontend should do if it resumes running or quits
+    pub fn execute(&mut self, chip8: &mut Chip8, command: DebugCommand) -> Option<DebugAction> {
+        let pc = chip8.get_register(Register::Pc);
+
+        match command {
+            DebugCommand::Step(count) => return Some(self.resume(Mode::Stepping(count))),
+            DebugCommand::Next => {
+                let mode = match read_instruction(chip8, pc) {
+                    Ok(Instruction::Call { .. }) => Mode::StepOver { address: pc.wrapping_add(2), sp: chip8.get_register(Register::Sp) },
+                    _ => Mode::Stepping(1),
+                };
+                return Some(self.resume(mode));
+            },
+            DebugCommand::Finish => match chip8.get_register(Register::Sp) {
+                0 => self.console.print("Not in a subroutine, so there is nothing to finish"),
+                sp => return Some(self.resume(Mode::StepOut { sp })),
+            },
+            DebugCommand::Continue => return Some(self.resume(Mode::Running)),
+            DebugCommand::Until(address) => return Some(self.resume(Mode::Until(address))),
+            DebugCommand::Quit => return Some(DebugAction::Quit),
+            DebugCommand::Break { address, condition } => {
+                let id = self.add_breakpoint(address, condition, None);
+                self.print_breakpoint(id);
+            },
+            DebugCommand::Log { address, message } => {
+                let id = self.add_breakpoint(address, None, Some(message));
+                self.print_breakpoint(id);
+            },
+            DebugCommand::Condition { id, condition } => match self.breakpoints.iter_mut().find(|b| b.id == id) {
+                Some(breakpoint) => {
+                    breakpoint.condition = condition;
+                    self.print_breakpoint(id);
+                },
+                None => self.console.print(&format!("No breakpoint {}", id)),
+            },
+            DebugCommand::Breakpoints if self.breakpoints.is_empty() => self.console.print("No breakpoints"),
+            DebugCommand::Breakpoints => {
+                let breakpoints: Vec<String> = self.breakpoints.iter()
+                    .map(|breakpoint| format!("  {} (reached {} times)", breakpoint, breakpoint.hits))
+                    .collect();
+                self.console.print(&format!("Breakpoints:\n{}", breakpoints.join("\n")));
+            },
+            DebugCommand::Delete(None) => {
+                self.breakpoints.clear();
+                self.console.print("Deleted every breakpoint");
+            },
+            DebugCommand::Delete(Some(id)) => {
+                let message = if self.remove_breakpoint(id) { "Deleted breakpoint" } else { "No breakpoint" };
+                self.console.print(&format!("{} {}", message, id));
+            },
+            DebugCommand::Watch { start, end, accesses } => {
+                for access in accesses {
+                    let watchpoint = Watchpoint { start, end, access };
+                    chip8.add_watchpoint(watchpoint);
+                    self.console.print(&format!("Watchpoint on {}", watchpoint));
+                }
+            },
+            DebugCommand::Watchpoints if chip8.get_watchpoints().is_empty() => self.console.print("No watchpoints"),
+            DebugCommand::Watchpoints => {
+                let watchpoints: Vec<String> = chip8.get_watchpoints().iter().map(|w| format!("  {}", w)).collect();
+                self.console.print(&format!("Watchpoints:\n{}", watchpoints.join("\n")));
+            },
+            DebugCommand::Unwatch(range) => {
+                let watchpoints: Vec<Watchpoint> = chip8.get_watchpoints().iter()
+                    .filter(|w| range.is_none_or(|(start, end)| w.start == start && w.end == end))
+                    .copied()
+                    .collect();
+                for watchpoint in &watchpoints {
+                    chip8.remove_watchpoint(watchpoint);
+                }
+                self.console.print(&format!("Deleted {} watchpoints", watchpoints.len()));
+            },
+            DebugCommand::Registers => {
+                let state = chip8.get_machine_state();
+                self.console.print(&format!("Frame: {} (instruction {} of the frame)\n{}", chip8.get_frame_count(), chip8.get_cycles_run(), state));
+            },
+            DebugCommand::Print(register) => {
+                self.console.print(&format!("{} = {:#X}", register, chip8.get_register(register)));
+            },
+            DebugCommand::Set(register, value) => match chip8.set_register(register, value) {
+                Ok(()) => self.console.print(&format!("{} = {:#X}", register, value)),
+                Err(e) => self.console.print(&e),
+            },
+            DebugCommand::Stack => {
+                let addresses: Vec<String> = chip8.get_stack().iter().map(|address| format!("{:#06X}", address)).collect();
+                self.console.print(&format!("Stack: {}", if addresses.is_empty() { String::from("empty") } else { addresses.join(" ") }));
+            },
+            DebugCommand::Examine { address, length } => {
+                let memory = chip8.get_memory();
+                let start = address as usize;
+                let end = start.saturating_add(length).min(memory.len());
+                let bytes = match memory.get(start..end) {
+                    Some(bytes) if !bytes.is_empty() => bytes,
+                    _ => {
+                        self.console.print(&format!("{:#06X} is past the end of memory", address));
+                        return None;
+                    },
+                };
+                let lines: Vec<String> = bytes.chunks(16).enumerate()
+                    .map(|(idx, bytes)| {
+                        let hex: Vec<String> = bytes.iter().map(|b| format!("{:02X}", b)).collect();
+                        format!("{:04X}  {}", start + idx * 16, hex.join(" "))
+                    })
+                    .collect();
+                self.console.print(&lines.join("\n"));
+            },
+            DebugCommand::Write { address, bytes } => match chip8.set_memory(address as usize, &bytes) {
+                Ok(()) => self.console.print(&format!("Wrote {} bytes at {:#06X}", bytes.len(), address)),
+                Err(e) => self.console.print(&e),
+            },
+            DebugCommand::List { address, count } => {
+                let mut address = address.unwrap_or(pc);
+                let mut lines = Vec::new();
+                for _ in 0..count {
+                    lines.push(format_location(chip8, address));
+                    let length = read_instruction(chip8, address).map_or(2, |instruction| instruction.get_length());
+                    address = address.wrapping_add(length);
+                }
+                self.console.print(&lines.join("\n"));
+            },
+            DebugCommand::Help => self.console.print(HELP),
+        }
+        None
+    }
+
+    // Prints the breakpoint with the chosen number
+    fn print_breakpoint(&mut self, id: u32) {
+        if let Some(breakpoint) = self.breakpoints.iter().find(|b| b.id == id) {
+            self.console.print(&breakpoint.to_string());
+        }
+    }
+
+    // Lets CHIP-8 run in the chosen mode
+    fn resume(&mut self, mode: Mode) -> DebugAction {
+        self.mode = mode;
+        self.resumed = true;
+        DebugAction::Resume
+    }
+}
+
+impl DebugHook for Debugger<'_> {
+    fn should_break(&mut self, chip8: &Chip8) -> bool {
+        Debugger::should_break(self, chip8)
+    }
+
+    fn pause(&mut self) {
+        Debugger::pause(self)
+    }
+
+    fn prompt(&mut self, chip8: &mut Chip8) -> DebugAction {
+        Debugger::prompt(self, chip8)
+    }
+}
+
+// Reads the word at the chosen address, which is 0 past the end of memory
+pub(crate) fn read_word(chip8: &Chip8, address: u16) -> u16 {
+    let memory = chip8.get_memory();
+    let byte = |address: u16| memory.get(address as usize).copied().unwrap_or(0) as u16;
+    byte(address) << 8 | byte(address.wrapping_add(1))
+}
+
+// Decodes the instruction at the chosen address
+pub(crate) fn read_instruction(chip8: &Chip8, address: u16) -> Result<Instruction, String> {
+    Instruction::decode(read_word(chip8, address)).map_err(|e| e.to_string())
+}
+
+// Returns the mnemonic of the instruction at the chosen address
+pub(crate) fn format_instruction_at(chip8: &Chip8, address: u16) -> String {
+    let opcode = read_word(chip8, address);
+    match Instruction::decode(opcode) {
+        Ok(instruction) => {
+            let long_address = read_word(chip8, address.wrapping_add(2));
+            format_instruction(instruction, Syntax::Classic, long_address, &BTreeMap::new())
+        },
+        Err(_) => format!("DW {:#06X}", opcode),
+    }
+}
+
+// Returns the address, opcode and mnemonic of the instruction at the chosen address
+fn format_location(chip8: &Chip8, address: u16) -> String {
+    format!("{:04X}  {:04X}  {}", address, read_word(chip8, address), format_instruction_at(chip8, address))
+}
+
+#[cfg(test)]
+#[path ="./debugger_test.rs"]
+mod debugger_test;
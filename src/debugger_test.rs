@@ -0,0 +1,294 @@
+use super::*;
+use crate::quirks::Quirks;
+
+// Calls a subroutine that sets V1, then increases V0 and loops forever
+const CALL_ROM: [u8; 12] = [
+    0x60, 0x01, // 0x200: V0 = 1
+    0x22, 0x08, // 0x202: Calls 0x208
+    0x70, 0x01, // 0x204: V0 += 1
+    0x12, 0x06, // 0x206: Jumps to itself
+    0x61, 0x05, // 0x208: V1 = 5
+    0x00, 0xEE, // 0x20A: Returns
+];
+
+fn new_chip8() -> Chip8 {
+    let mut chip8 = Chip8::new(Quirks::default());
+    chip8.load_rom(&CALL_ROM).unwrap();
+    chip8
+}
+
+// Runs frames until the debugger pauses, giving up after 10 frames
+fn run_until_paused(chip8: &mut Chip8, debugger: &mut Debugger) -> bool {
+    for _ in 0..10 {
+        if !chip8.run_frame_until(10, |chip8| debugger.should_break(chip8)).unwrap() {
+            return true;
+        }
+    }
+    false
+}
+
+#[test]
+fn test_command_parse() {
+    assert_eq!("s".parse(), Ok(DebugCommand::Step(1)));
+    assert_eq!("step 12".parse(), Ok(DebugCommand::Step(12)));
+    assert_eq!("  n  ".parse(), Ok(DebugCommand::Next));
+    assert_eq!("finish".parse(), Ok(DebugCommand::Finish));
+    assert_eq!("u 0x20A".parse(), Ok(DebugCommand::Until(0x20A)));
+    assert_eq!("b 20a".parse(), Ok(DebugCommand::Break { address: Some(0x20A), condition: None }));
+    assert_eq!(
+        "b 2a4 if pc == 0x2A4 && v3 > 5".parse(),
+        Ok(DebugCommand::Break { address: Some(0x2A4), condition: "pc == 0x2A4 && v3 > 5".parse().ok() })
+    );
+    assert_eq!("break if dt == 0".parse(), Ok(DebugCommand::Break { address: None, condition: "dt == 0".parse().ok() }));
+    assert_eq!("log * V3 = {v3}".parse(), Ok(DebugCommand::Log { address: None, message: "V3 = {v3}".parse().unwrap() }));
+    assert_eq!("cond 2 [i] == 0xFF".parse(), Ok(DebugCommand::Condition { id: 2, condition: "[i] == 0xFF".parse().ok() }));
+    assert_eq!("cond 2".parse(), Ok(DebugCommand::Condition { id: 2, condition: None }));
+    assert_eq!("d 2".parse(), Ok(DebugCommand::Delete(Some(2))));
+    assert_eq!("b".parse(), Ok(DebugCommand::Breakpoints));
+    assert_eq!("d".parse(), Ok(DebugCommand::Delete(None)));
+    assert_eq!("p VF".parse(), Ok(DebugCommand::Print(Register::V(0xF))));
+    assert_eq!("set i 0x300".parse(), Ok(DebugCommand::Set(Register::I, 0x300)));
+    assert_eq!("x 300 4".parse(), Ok(DebugCommand::Examine { address: 0x300, length: 4 }));
+    assert_eq!("w 300 12 ab".parse(), Ok(DebugCommand::Write { address: 0x300, bytes: vec![0x12, 0xAB] }));
+    assert_eq!("list".parse(), Ok(DebugCommand::List { address: None, count: DEFAULT_LIST_COUNT }));
+    assert_eq!("watch 300".parse(), Ok(DebugCommand::Watch { start: 0x300, end: 0x300, accesses: vec![Access::Write] }));
+    assert_eq!(
+        "watch 300-30f wrw".parse(),
+        Ok(DebugCommand::Watch { start: 0x300, end: 0x30F, accesses: vec![Access::Read, Access::Write] })
+    );
+    assert_eq!("unwatch 300-30f".parse(), Ok(DebugCommand::Unwatch(Some((0x300, 0x30F)))));
+
+    assert!("jump".parse::<DebugCommand>().is_err());
+    assert!("s 0".parse::<DebugCommand>().is_err());
+    assert!("b xyz".parse::<DebugCommand>().is_err());
+    assert!("b 200 v3 > 5".parse::<DebugCommand>().is_err());
+    assert!("b if".parse::<DebugCommand>().is_err());
+    assert!("b if v3 >".parse::<DebugCommand>().is_err());
+    assert!("log 200".parse::<DebugCommand>().is_err());
+    assert!("log 200 {v3".parse::<DebugCommand>().is_err());
+    assert!("cond x".parse::<DebugCommand>().is_err());
+    assert!("p v16".parse::<DebugCommand>().is_err());
+    assert!("w 300 100".parse::<DebugCommand>().is_err());
+    assert!("w 300".parse::<DebugCommand>().is_err());
+    assert!("watch 30f-300".parse::<DebugCommand>().is_err());
+    assert!("watch 300 q".parse::<DebugCommand>().is_err());
+}
+
+#[test]
+fn test_breakpoint() {
+    let mut chip8 = new_chip8();
+    let mut console = ScriptedConsole::new(&["c"]);
+    let mut debugger = Debugger::new(&mut console);
+    debugger.add_breakpoint(Some(0x204), None, None);
+
+    assert!(run_until_paused(&mut chip8, &mut debugger));
+    assert!(debugger.is_paused());
+    assert_eq!(chip8.get_register(Register::Pc), 0x204);
+    assert_eq!(chip8.get_register(Register::V(1)), 5);
+
+    // Continuing runs the instruction with the breakpoint, and the loop never reaches it again
+    assert_eq!(debugger.prompt(&mut chip8), DebugAction::Resume);
+    assert!(!run_until_paused(&mut chip8, &mut debugger));
+    assert_eq!(chip8.get_register(Register::V(0)), 2);
+    assert!(console.output.contains("Breakpoint 1 at 0204  7001  ADD V0, 0x01"));
+}
+
+#[test]
+fn test_step() {
+    let mut chip8 = new_chip8();
+    let mut console = ScriptedConsole::new(&["s", "s 2"]);
+    let mut debugger = Debugger::new(&mut console);
+    debugger.pause();
+
+    assert!(run_until_paused(&mut chip8, &mut debugger));
+    assert_eq!(chip8.get_register(Register::Pc), 0x200);
+
+    debugger.prompt(&mut chip8);
+    assert!(run_until_paused(&mut chip8, &mut debugger));
+    assert_eq!(chip8.get_register(Register::Pc), 0x202);
+
+    // Stepping enters subroutines
+    debugger.prompt(&mut chip8);
+    assert!(run_until_paused(&mut chip8, &mut debugger));
+    assert_eq!(chip8.get_register(Register::Pc), 0x20A);
+    assert_eq!(chip8.get_stack(), &[0x204]);
+}
+
+#[test]
+fn test_next() {
+    let mut chip8 = new_chip8();
+    let mut console = ScriptedConsole::new(&["n", "n", "n"]);
+    let mut debugger = Debugger::new(&mut console);
+    debugger.pause();
+    run_until_paused(&mut chip8, &mut debugger);
+
+    // Runs the whole subroutine called at 0x202
+    for pc in [0x202, 0x204, 0x206] {
+        debugger.prompt(&mut chip8);
+        assert!(run_until_paused(&mut chip8, &mut debugger));
+        assert_eq!(chip8.get_register(Register::Pc), pc);
+    }
+    assert_eq!(chip8.get_register(Register::V(1)), 5);
+    assert!(chip8.get_stack().is_empty());
+}
+
+#[test]
+fn test_finish() {
+    let mut chip8 = new_chip8();
+    let mut console = ScriptedConsole::new(&["f", "f", "c"]);
+    let mut debugger = Debugger::new(&mut console);
+    debugger.add_breakpoint(Some(0x208), None, None);
+
+    assert!(run_until_paused(&mut chip8, &mut debugger));
+    assert_eq!(debugger.get_stop_reason(), Some(StopReason::Breakpoint(1)));
+
+    // Runs the rest of the subroutine, pausing after it returns
+    debugger.prompt(&mut chip8);
+    assert!(run_until_paused(&mut chip8, &mut debugger));
+    assert_eq!(chip8.get_register(Register::Pc), 0x204);
+    assert_eq!(debugger.get_stop_reason(), Some(StopReason::Step));
+
+    // Outside of any subroutine, finish does not resume, so the next command is read
+    assert_eq!(debugger.prompt(&mut chip8), DebugAction::Resume);
+    assert!(console.output.contains("Not in a subroutine, so there is nothing to finish"));
+}
+
+#[test]
+fn test_next_breakpoint() {
+    let mut chip8 = new_chip8();
+    let mut console = ScriptedConsole::new(&["u 202", "n"]);
+    let mut debugger = Debugger::new(&mut console);
+    debugger.pause();
+    run_until_paused(&mut chip8, &mut debugger);
+    debugger.add_breakpoint(Some(0x20A), None, None);
+
+    debugger.prompt(&mut chip8);
+    assert!(run_until_paused(&mut chip8, &mut debugger));
+    assert_eq!(chip8.get_register(Register::Pc), 0x202);
+
+    // A breakpoint inside the subroutine still pauses it
+    debugger.prompt(&mut chip8);
+    assert!(run_until_paused(&mut chip8, &mut debugger));
+    assert_eq!(chip8.get_register(Register::Pc), 0x20A);
+    assert!(console.output.contains("Reached at 0202"));
+}
+
+#[test]
+fn test_conditional_breakpoint() {
+    let mut chip8 = Chip8::new(Quirks::default());
+    chip8.load_rom(&[0x70, 0x01, 0x12, 0x00]).unwrap(); // V0 += 1, then jumps back forever
+    let mut console = ScriptedConsole::new(&[
+        "b 200 if v0 == 5", "log 202 V0 is {v0:x}", "c",
+        "b", "cond 1 hits == 10", "c",
+        "d 1", "d 2", "b if v0 == 0x10", "c",
+    ]);
+    let mut debugger = Debugger::new(&mut console);
+    debugger.pause();
+    run_until_paused(&mut chip8, &mut debugger);
+
+    debugger.prompt(&mut chip8);
+    assert!(run_until_paused(&mut chip8, &mut debugger));
+    assert_eq!(chip8.get_register(Register::V(0)), 5);
+
+    // Hit counts keep counting when the condition changes
+    debugger.prompt(&mut chip8);
+    assert!(run_until_paused(&mut chip8, &mut debugger));
+    assert_eq!(chip8.get_register(Register::V(0)), 10);
+
+    // A breakpoint without an address is checked before every instruction
+    debugger.prompt(&mut chip8);
+    assert!(run_until_paused(&mut chip8, &mut debugger));
+    assert_eq!(chip8.get_register(Register::V(0)), 0x10);
+    assert_eq!(chip8.get_register(Register::Pc), 0x202);
+
+    let output = &console.output;
+    assert!(output.contains("Breakpoint 1 at 0x0200 if v0 == 5\nLogpoint 2 at 0x0202: \"V0 is {v0:x}\"\n"));
+    assert!(output.contains("V0 is 0x1\nV0 is 0x2\nV0 is 0x3\nV0 is 0x4\nV0 is 0x5\nBreakpoint 1 at 0200"));
+    assert!(output.contains("  Breakpoint 1 at 0x0200 if v0 == 5 (reached 5 times)\n  Logpoint 2 at 0x0202: \"V0 is {v0:x}\" (reached 5 times)\n"));
+    assert!(output.contains("Breakpoint 3 at every instruction if v0 == 0x10\n"));
+    assert!(output.contains("Breakpoint 3 at 0202  1200  JP 0x200\n"));
+}
+
+#[test]
+fn test_watchpoint() {
+    let mut chip8 = new_chip8();
+    let mut console = ScriptedConsole::new(&["watch 208-20b x", "watch", "c", "c", "unwatch 208-20b", "c"]);
+    let mut debugger = Debugger::new(&mut console);
+    debugger.pause();
+    run_until_paused(&mut chip8, &mut debugger);
+
+    // Pauses right after each instruction fetched from the subroutine
+    debugger.prompt(&mut chip8);
+    assert!(run_until_paused(&mut chip8, &mut debugger));
+    assert_eq!(chip8.get_register(Register::Pc), 0x20A);
+    assert_eq!(chip8.get_register(Register::V(1)), 5);
+
+    debugger.prompt(&mut chip8);
+    assert!(run_until_paused(&mut chip8, &mut debugger));
+    assert_eq!(chip8.get_register(Register::Pc), 0x204);
+
+    debugger.prompt(&mut chip8);
+    assert!(!run_until_paused(&mut chip8, &mut debugger));
+    assert!(chip8.get_watchpoints().is_empty());
+
+    let output = &console.output;
+    assert!(output.contains("Watchpoints:\n  execute 0x0208-0x020B\n"));
+    assert!(output.contains("Watchpoint: instruction 0x6105 at address 0x0208 was fetched from address 0x0209 (0x05)\n"));
+    assert!(output.contains("Paused at 020A  00EE  RET\n"));
+    assert!(output.contains("Deleted 1 watchpoints\n"));
+}
+
+#[test]
+fn test_inspect_and_modify() {
+    let mut chip8 = new_chip8();
+    let mut console = ScriptedConsole::new(&[
+        "set v3 2a", "p v3", "set pc 204", "set dt 100",
+        "w 300 12 34", "x 300 2", "stack", "b 206", "b", "d 1", "b", "list 200 2",
+        "x ffe 18446744073709551615", "x f000 4", "q",
+    ]);
+    let mut debugger = Debugger::new(&mut console);
+    debugger.pause();
+    run_until_paused(&mut chip8, &mut debugger);
+
+    assert_eq!(debugger.prompt(&mut chip8), DebugAction::Quit);
+    assert_eq!(chip8.get_register(Register::V(3)), 0x2A);
+    assert_eq!(chip8.get_register(Register::Pc), 0x204);
+    assert_eq!(chip8.get_register(Register::Dt), 0);
+    assert_eq!(&chip8.get_memory()[0x300..0x302], &[0x12, 0x34]);
+
+    let output = &console.output;
+    assert!(output.contains("V3 = 0x2A\n"));
+    assert!(output.contains("0x100 does not fit in DT, which is a byte\n"));
+    assert!(output.contains("Wrote 2 bytes at 0x0300\n"));
+    assert!(output.contains("0300  12 34\n"));
+    assert!(output.contains("Stack: empty\n"));
+    assert!(output.contains("Breakpoints:\n  Breakpoint 1 at 0x0206 (reached 0 times)\n"));
+    assert!(output.contains("No breakpoints\n"));
+    assert!(output.contains("0200  6001  LD V0, 0x01\n0202  2208  CALL 0x208\n"));
+
+    // Lengths past the end of memory stop at its end
+    assert!(output.contains("0FFE  00 00\n"));
+    assert!(output.contains("0xF000 is past the end of memory\n"));
+}
+
+#[test]
+fn test_prompt_input() {
+    let mut chip8 = new_chip8();
+    let mut console = ScriptedConsole::new(&["", "jump", "s", ""]);
+    let mut debugger = Debugger::new(&mut console);
+    debugger.pause();
+    run_until_paused(&mut chip8, &mut debugger);
+
+    // Empty lines repeat the last command, and are ignored before any command
+    debugger.prompt(&mut chip8);
+    run_until_paused(&mut chip8, &mut debugger);
+    debugger.prompt(&mut chip8);
+    run_until_paused(&mut chip8, &mut debugger);
+    assert_eq!(chip8.get_register(Register::Pc), 0x208);
+
+    // Once the input ends, CHIP-8 keeps running
+    assert_eq!(debugger.prompt(&mut chip8), DebugAction::Resume);
+    assert!(!debugger.is_paused());
+    assert!(!run_until_paused(&mut chip8, &mut debugger));
+    assert!(console.output.contains("invalid command 'jump'"));
+}
@@ -0,0 +1,313 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+use std::str::FromStr;
+
+use crate::cpu::START_ADDRESS;
+use crate::instruction::Instruction;
+
+// Bytes shown on each line of data
+const DATA_BYTES_PER_LINE: usize = 4;
+
+
+// Assembly syntax the instructions are written in
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Syntax {
+
+    // Mnemonics of Cowgod's technical reference, such as "LD V0, 0x12"
+    Classic,
+
+    // Statements of the Octo assembler, such as "v0 := 0x12"
+    Octo,
+}
+
+impl FromStr for Syntax {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "classic" => Ok(Syntax::Classic),
+            "octo" => Ok(Syntax::Octo),
+            _ => Err(format!("unknown syntax '{}' (expected classic or octo)", s)),
+        }
+    }
+}
+
+
+/* Writes an instruction in the chosen syntax. Addresses that have a label are written as the label.
+ * The long address of F000 NNNN is the word stored after its opcode */
+pub fn format_instruction(instruction: Instruction, syntax: Syntax, long_address: u16, labels: &BTreeMap<u16, String>) -> String {
+    let address = |nnn: u16| labels.get(&nnn).cloned().unwrap_or_else(|| format!("{:#05X}", nnn));
+
+    match syntax {
+        Syntax::Classic => match instruction {
+            Instruction::ScrollDown { n } => format!("SCD {}", n),
+            Instruction::ScrollUp { n } => format!("SCU {}", n),
+            Instruction::ClearScreen => String::from("CLS"),
+            Instruction::Return => String::from("RET"),
+            Instruction::ScrollRight => String::from("SCR"),
+            Instruction::ScrollLeft => String::from("SCL"),
+            Instruction::Exit => String::from("EXIT"),
+            Instruction::LowRes => String::from("LOW"),
+            Instruction::HighRes => String::from("HIGH"),
+            Instruction::Jump { nnn } => format!("JP {}", address(nnn)),
+            Instruction::Call { nnn } => format!("CALL {}", address(nnn)),
+            Instruction::SkipIfEqualByte { x, nn } => format!("SE V{:X}, {:#04X}", x, nn),
+            Instruction::SkipIfNotEqualByte { x, nn } => format!("SNE V{:X}, {:#04X}", x, nn),
+            Instruction::SkipIfEqual { x, y } => format!("SE V{:X}, V{:X}", x, y),
+            Instruction::StoreRange { x, y } => format!("SAVE V{:X} - V{:X}", x, y),
+            Instruction::LoadRange { x, y } => format!("LOAD V{:X} - V{:X}", x, y),
+            Instruction::SetByte { x, nn } => format!("LD V{:X}, {:#04X}", x, nn),
+            Instruction::AddByte { x, nn } => format!("ADD V{:X}, {:#04X}", x, nn),
+            Instruction::Set { x, y } => format!("LD V{:X}, V{:X}", x, y),
+            Instruction::Or { x, y } => format!("OR V{:X}, V{:X}", x, y),
+            Instruction::And { x, y } => format!("AND V{:X}, V{:X}", x, y),
+            Instruction::Xor { x, y } => format!("XOR V{:X}, V{:X}", x, y),
+            Instruction::Add { x, y } => format!("ADD V{:X}, V{:X}", x, y),
+            Instruction::Sub { x, y } => format!("SUB V{:X}, V{:X}", x, y),
+            Instruction::ShiftRight { x, y } => format!("SHR V{:X}, V{:X}", x, y),
+            Instruction::SubReverse { x, y } => format!("SUBN V{:X}, V{:X}", x, y),
+            Instruction::ShiftLeft { x, y } => format!("SHL V{:X}, V{:X}", x, y),
+            Instruction::SkipIfNotEqual { x, y } => format!("SNE V{:X}, V{:X}", x, y),
+            Instruction::SetIndex { nnn } => format!("LD I, {}", address(nnn)),
+            Instruction::JumpOffset { nnn, .. } => format!("JP V0, {}", address(nnn)),
+            Instruction::Random { x, nn } => format!("RND V{:X}, {:#04X}", x, nn),
+            Instruction::Draw { x, y, n } => format!("DRW V{:X}, V{:X}, {}", x, y, n),
+            Instruction::SkipIfKey { x } => format!("SKP V{:X}", x),
+            Instruction::SkipIfNotKey { x } => format!("SKNP V{:X}", x),
+            Instruction::SetIndexLong => format!("LD I, LONG {}", address(long_address)),
+            Instruction::SelectPlanes { n } => format!("PLANE {}", n),
+            Instruction::LoadAudioPattern => String::from("AUDIO"),
+            Instruction::GetDelayTimer { x } => format!("LD V{:X}, DT", x),
+            Instruction::WaitKey { x } => format!("LD V{:X}, K", x),
+            Instruction::SetDelayTimer { x } => format!("LD DT, V{:X}", x),
+            Instruction::SetSoundTimer { x } => format!("LD ST, V{:X}", x),
+            Instruction::AddIndex { x } => format!("ADD I, V{:X}", x),
+            Instruction::SetIndexFont { x } => format!("LD F, V{:X}", x),
+            Instruction::SetIndexBigFont { x } => format!("LD HF, V{:X}", x),
+            Instruction::StoreBcd { x } => format!("LD B, V{:X}", x),
+            Instruction::SetPitch { x } => format!("PITCH V{:X}", x),
+            Instruction::Store { x } => format!("LD [I], V{:X}", x),
+            Instruction::Load { x } => format!("LD V{:X}, [I]", x),
+            Instruction::SaveFlags { x } => format!("LD R, V{:X}", x),
+            Instruction::LoadFlags { x } => format!("LD V{:X}, R", x),
+        },
+
+        /* Octo skips are written as conditions for running the next statement, which are
+         * the opposite of the skip condition: 3XNN (skip if equal) is "if vX != NN then" */
+        Syntax::Octo => match instruction {
+            Instruction::ScrollDown { n } => format!("scroll-down {}", n),
+            Instruction::ScrollUp { n } => format!("scroll-up {}", n),
+            Instruction::ClearScreen => String::from("clear"),
+            Instruction::Return => String::from("return"),
+            Instruction::ScrollRight => String::from("scroll-right"),
+            Instruction::ScrollLeft => String::from("scroll-left"),
+            Instruction::Exit => String::from("exit"),
+            Instruction::LowRes => String::from("lores"),
+            Instruction::HighRes => String::from("hires"),
+            Instruction::Jump { nnn } => format!("jump {}", address(nnn)),
+            Instruction::Call { nnn } => match labels.get(&nnn) {
+                Some(label) => label.clone(),
+                None => format!(":call {:#05X}", nnn),
+            },
+            Instruction::SkipIfEqualByte { x, nn } => format!("if v{:x} != {:#04X} then", x, nn),
+            Instruction::SkipIfNotEqualByte { x, nn } => format!("if v{:x} == {:#04X} then", x, nn),
+            Instruction::SkipIfEqual { x, y } => format!("if v{:x} != v{:x} then", x, y),
+            Instruction::StoreRange { x, y } => format!("save v{:x} - v{:x}", x, y),
+            Instruction::LoadRange { x, y } => format!("load v{:x} - v{:x}", x, y),
+            Instruction::SetByte { x, nn } => format!("v{:x} := {:#04X}", x, nn),
+            Instruction::AddByte { x, nn } => format!("v{:x} += {:#04X}", x, nn),
+            Instruction::Set { x, y } => format!("v{:x} := v{:x}", x, y),
+            Instruction::Or { x, y } => format!("v{:x} |= v{:x}", x, y),
+            Instruction::And { x, y } => format!("v{:x} &= v{:x}", x, y),
+            Instruction::Xor { x, y } => format!("v{:x} ^= v{:x}", x, y),
+            Instruction::Add { x, y } => format!("v{:x} += v{:x}", x, y),
+            Instruction::Sub { x, y } => format!("v{:x} -= v{:x}", x, y),
+            Instruction::ShiftRight { x, y } => format!("v{:x} >>= v{:x}", x, y),
+            Instruction::SubReverse { x, y } => format!("v{:x} =- v{:x}", x, y),
+            Instruction::ShiftLeft { x, y } => format!("v{:x} <<= v{:x}", x, y),
+            Instruction::SkipIfNotEqual { x, y } => format!("if v{:x} == v{:x} then", x, y),
+            Instruction::SetIndex { nnn } => format!("i := {}", address(nnn)),
+            Instruction::JumpOffset { nnn, .. } => format!("jump0 {}", address(nnn)),
+            Instruction::Random { x, nn } => format!("v{:x} := random {:#04X}", x, nn),
+            Instruction::Draw { x, y, n } => format!("sprite v{:x} v{:x} {}", x, y, n),
+            Instruction::SkipIfKey { x } => format!("if v{:x} -key then", x),
+            Instruction::SkipIfNotKey { x } => format!("if v{:x} key then", x),
+            Instruction::SetIndexLong => format!("i := long {}", address(long_address)),
+            Instruction::SelectPlanes { n } => format!("plane {}", n),
+            Instruction::LoadAudioPattern => String::from("audio"),
+            Instruction::GetDelayTimer { x } => format!("v{:x} := delay", x),
+            Instruction::WaitKey { x } => format!("v{:x} := key", x),
+            Instruction::SetDelayTimer { x } => format!("delay := v{:x}", x),
+            Instruction::SetSoundTimer { x } => format!("buzzer := v{:x}", x),
+            Instruction::AddIndex { x } => format!("i += v{:x}", x),
+            Instruction::SetIndexFont { x } => format!("i := hex v{:x}", x),
+            Instruction::SetIndexBigFont { x } => format!("i := bighex v{:x}", x),
+            Instruction::StoreBcd { x } => format!("bcd v{:x}", x),
+            Instruction::SetPitch { x } => format!("pitch := v{:x}", x),
+            Instruction::Store { x } => format!("save v{:x}", x),
+            Instruction::Load { x } => format!("load v{:x}", x),
+            Instruction::SaveFlags { x } => format!("saveflags v{:x}", x),
+            Instruction::LoadFlags { x } => format!("loadflags v{:x}", x),
+        },
+    }
+}
+
+
+// ROM loaded at the start address, split into the instructions reachable from it and data
+pub struct Disassembly<'a> {
+    rom: &'a [u8],
+
+    // Instructions that can be reached by following jumps, calls and skips from the start address, by address
+    instructions: BTreeMap<u16, Instruction>,
+
+    // Labels of the jump and call targets
+    labels: BTreeMap<u16, String>,
+}
+
+impl<'a> Disassembly<'a> {
+
+    /* Finds the code of a ROM by following every path the program can take from the start address.
+     * Indirect jumps (BNNN) cannot be followed, so code only reached through them is shown as data */
+    pub fn new(rom: &'a [u8]) -> Self {
+        let mut instructions = BTreeMap::new();
+        let mut jump_targets = BTreeSet::new();
+        let mut call_targets = BTreeSet::new();
+        let mut pending = vec![START_ADDRESS];
+
+        while let Some(address) = pending.pop() {
+            if instructions.contains_key(&address) {
+                continue;
+            }
+            let instruction = match read_word(rom, address).map(Instruction::decode) {
+                Some(Ok(instruction)) => instruction,
+                _ => continue,
+            };
+            let length = instruction.get_length();
+            if instruction == Instruction::SetIndexLong && read_word(rom, address.wrapping_add(2)).is_none() {
+                continue;
+            }
+            instructions.insert(address, instruction);
+
+            let next = address.wrapping_add(length);
+            match instruction {
+                Instruction::Jump { nnn } => {
+                    jump_targets.insert(nnn);
+                    pending.push(nnn);
+                },
+                Instruction::Call { nnn } => {
+                    call_targets.insert(nnn);
+                    pending.extend([nnn, next]);
+                },
+                Instruction::JumpOffset { nnn, .. } => {
+                    jump_targets.insert(nnn);
+                },
+                Instruction::Return | Instruction::Exit => (),
+                Instruction::SkipIfEqualByte { .. } | Instruction::SkipIfNotEqualByte { .. }
+                | Instruction::SkipIfEqual { .. } | Instruction::SkipIfNotEqual { .. }
+                | Instruction::SkipIfKey { .. } | Instruction::SkipIfNotKey { .. } => {
+                    let skipped_length = match read_word(rom, next) {
+                        Some(0xF000) => 4,
+                        _ => 2,
+                    };
+                    pending.extend([next, next.wrapping_add(skipped_length)]);
+                },
+                _ => pending.push(next),
+            }
+        }
+
+        /* Only targets inside the ROM get a label, with calls named as subroutines. Targets in the middle
+         * of another instruction are not shown in the listing, so they do not get one either */
+        let inside_instruction: BTreeSet<u16> = instructions.iter()
+            .flat_map(|(address, instruction)| (1..instruction.get_length()).map(move |offset| address.wrapping_add(offset)))
+            .collect();
+        let in_rom = |address: &u16| {
+            *address >= START_ADDRESS && ((*address - START_ADDRESS) as usize) < rom.len() && !inside_instruction.contains(address)
+        };
+        let mut labels: BTreeMap<u16, String> = jump_targets.iter()
+            .filter(|address| in_rom(address))
+            .map(|address| (*address, format!("label_{:04X}", address)))
+            .collect();
+        labels.extend(call_targets.iter()
+            .filter(|address| in_rom(address))
+            .map(|address| (*address, format!("sub_{:04X}", address))));
+
+        Self { rom, instructions, labels }
+    }
+
+    // Returns the instruction found at the chosen address, if it is reachable code
+    pub fn get_instruction(&self, address: u16) -> Option<Instruction> {
+        self.instructions.get(&address).copied()
+    }
+
+    // Returns the labels of the jump and call targets inside the ROM, by address
+    pub fn get_labels(&self) -> &BTreeMap<u16, String> {
+        &self.labels
+    }
+
+    /* Writes the listing in the chosen syntax: one line per instruction with its address, raw opcode
+     * and mnemonic, and lines of bytes for data. Labels are written before the line they point to */
+    pub fn to_listing(&self, syntax: Syntax) -> String {
+        let mut listing = String::new();
+        let end = START_ADDRESS as usize + self.rom.len();
+        let mut address = START_ADDRESS as usize;
+
+        while address < end {
+            if let Some(label) = self.labels.get(&(address as u16)) {
+                match syntax {
+                    Syntax::Classic => writeln!(listing, "{}:", label).unwrap(),
+                    Syntax::Octo => writeln!(listing, ": {}", label).unwrap(),
+                }
+            }
+
+            let (length, raw, text) = match self.instructions.get(&(address as u16)) {
+                Some(instruction) => {
+                    let length = instruction.get_length() as usize;
+                    let long_address = read_word(self.rom, (address + 2) as u16).unwrap_or(0);
+                    (length, self.raw_bytes(address, length), format_instruction(*instruction, syntax, long_address, &self.labels))
+                },
+                None => {
+                    let length = self.data_length(address, end);
+                    let bytes = &self.rom[(address - START_ADDRESS as usize)..(address - START_ADDRESS as usize + length)];
+                    let text = match syntax {
+                        Syntax::Classic => format!("DB {}", bytes.iter().map(|b| format!("{:#04X}", b)).collect::<Vec<_>>().join(", ")),
+                        Syntax::Octo => bytes.iter().map(|b| format!("{:#04X}", b)).collect::<Vec<_>>().join(" "),
+                    };
+                    (length, self.raw_bytes(address, length), text)
+                },
+            };
+            writeln!(listing, "{:04X}  {:<8}  {}", address, raw, text).unwrap();
+            address += length;
+        }
+        listing
+    }
+
+    // Returns the bytes of the chosen range of the ROM as hexadecimal digits
+    fn raw_bytes(&self, address: usize, length: usize) -> String {
+        let offset = address - START_ADDRESS as usize;
+        self.rom[offset..(offset + length).min(self.rom.len())].iter().map(|b| format!("{:02X}", b)).collect()
+    }
+
+    // Returns how many data bytes are shown on the line at the chosen address, stopping before code and labels
+    fn data_length(&self, address: usize, end: usize) -> usize {
+        let mut length = 1;
+        while length < DATA_BYTES_PER_LINE && address + length < end {
+            let next = (address + length) as u16;
+            if self.instructions.contains_key(&next) || self.labels.contains_key(&next) {
+                break;
+            }
+            length += 1;
+        }
+        length
+    }
+}
+
+// Reads the two bytes at the chosen address of a ROM loaded at the start address, if both are in the ROM
+fn read_word(rom: &[u8], address: u16) -> Option<u16> {
+    let offset = (address as usize).checked_sub(START_ADDRESS as usize)?;
+    let bytes = rom.get(offset..offset + 2)?;
+    Some((bytes[0] as u16) << 8 | bytes[1] as u16)
+}
+
+#[cfg(test)]
+#[path ="./disasm_test.rs"]
+mod disasm_test;
@@ -0,0 +1,78 @@
+use super::*;
+
+// Calls a subroutine that draws a sprite, then loops forever. The sprite and an unused byte are data
+const ROM: [u8; 14] = [
+    0x22, 0x04, // 0x200: Calls 0x204
+    0x12, 0x02, // 0x202: Jumps to itself forever
+    0xA2, 0x0A, // 0x204: I = 0x20A
+    0xD0, 0x13, // 0x206: Draws the 3 rows tall sprite at (V0, V1)
+    0x00, 0xEE, // 0x208: Returns
+    0xFF, 0x81, // 0x20A: Sprite
+    0xFF, 0x00, // 0x20C: Sprite and an unused byte
+];
+
+#[test]
+fn test_format_instruction() {
+    let labels = BTreeMap::from([(0x300, String::from("sub_0300"))]);
+    let cases = [
+        (Instruction::SetByte { x: 0, nn: 0x12 }, "LD V0, 0x12", "v0 := 0x12"),
+        (Instruction::Call { nnn: 0x300 }, "CALL sub_0300", "sub_0300"),
+        (Instruction::Call { nnn: 0x400 }, "CALL 0x400", ":call 0x400"),
+        (Instruction::SkipIfEqualByte { x: 0xA, nn: 0x05 }, "SE VA, 0x05", "if va != 0x05 then"),
+        (Instruction::Draw { x: 1, y: 2, n: 5 }, "DRW V1, V2, 5", "sprite v1 v2 5"),
+        (Instruction::SetIndexLong, "LD I, LONG 0x1234", "i := long 0x1234"),
+        (Instruction::Load { x: 3 }, "LD V3, [I]", "load v3"),
+    ];
+    for (instruction, classic, octo) in cases {
+        assert_eq!(format_instruction(instruction, Syntax::Classic, 0x1234, &labels), classic);
+        assert_eq!(format_instruction(instruction, Syntax::Octo, 0x1234, &labels), octo);
+    }
+}
+
+#[test]
+fn test_disassembly() {
+    let disassembly = Disassembly::new(&ROM);
+    assert_eq!(disassembly.get_instruction(0x206), Some(Instruction::Draw { x: 0, y: 1, n: 3 }));
+    assert_eq!(disassembly.get_instruction(0x20A), None);
+    assert_eq!(disassembly.get_labels().values().collect::<Vec<_>>(), ["label_0202", "sub_0204"]);
+
+    assert_eq!(disassembly.to_listing(Syntax::Classic), "\
+0200  2204      CALL sub_0204
+label_0202:
+0202  1202      JP label_0202
+sub_0204:
+0204  A20A      LD I, 0x20A
+0206  D013      DRW V0, V1, 3
+0208  00EE      RET
+020A  FF81FF00  DB 0xFF, 0x81, 0xFF, 0x00
+");
+
+    assert_eq!(disassembly.to_listing(Syntax::Octo), "\
+0200  2204      sub_0204
+: label_0202
+0202  1202      jump label_0202
+: sub_0204
+0204  A20A      i := 0x20A
+0206  D013      sprite v0 v1 3
+0208  00EE      return
+020A  FF81FF00  0xFF 0x81 0xFF 0x00
+");
+}
+
+#[test]
+fn test_disassembly_skips() {
+    // Both paths of a skip are code, including the instruction after a skipped F000 NNNN
+    let rom = [0x30, 0x00, 0xF0, 0x00, 0x12, 0x34, 0x00, 0xFD, 0x12, 0x34];
+    let disassembly = Disassembly::new(&rom);
+    assert_eq!(disassembly.get_instruction(0x202), Some(Instruction::SetIndexLong));
+    assert_eq!(disassembly.get_instruction(0x206), Some(Instruction::Exit));
+    assert_eq!(disassembly.get_instruction(0x208), None); // After exiting
+    assert!(disassembly.to_listing(Syntax::Classic).contains("0202  F0001234  LD I, LONG 0x1234\n"));
+}
+
+#[test]
+fn test_syntax_parse() {
+    assert_eq!("classic".parse(), Ok(Syntax::Classic));
+    assert_eq!("octo".parse(), Ok(Syntax::Octo));
+    assert!("intel".parse::<Syntax>().is_err());
+}
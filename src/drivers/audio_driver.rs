@@ -1,17 +1,22 @@
+#[allow(clippy::single_component_path_imports)]
 use sdl2;
 use sdl2::audio::{AudioDevice, AudioCallback, AudioSpecDesired};
 
+use chip_8::audio::{PatternPlayer, AUDIO_PATTERN_SIZE, SAMPLE_RATE};
+use chip_8::frontend::AudioSink;
+
 pub struct AudioDriver {
-    device: AudioDevice<SquareWave>,
+    device: AudioDevice<PatternWave>,
 }
 
 impl AudioDriver {
 
+    #[allow(clippy::redundant_field_names)]
     pub fn new(sdl_context: &sdl2::Sdl) -> Result<Self, String> {
         let audio_subsystem = sdl_context.audio()?;
 
         let desired_spec = AudioSpecDesired {
-            freq: Some(44100),
+            freq: Some(SAMPLE_RATE as i32),
             channels: Some(1),
             samples: None,
         };
@@ -20,11 +25,7 @@ impl AudioDriver {
             .open_playback(None, &desired_spec, |spec| {
 
                 // initialize the audio callback
-                SquareWave {
-                    phase_inc: 250.0 / spec.freq as f32,
-                    phase: 0.0,
-                    volume: 0.1,
-                }
+                PatternWave(PatternPlayer::new(spec.freq as u32, 0.1))
             })?;
 
         Ok(
@@ -32,34 +33,33 @@ impl AudioDriver {
         )
     }
 
-    pub fn start_beep(&self) {
+}
+
+impl AudioSink for AudioDriver {
+
+    // Sets the audio pattern and pitch played while beeping
+    fn set_pattern(&mut self, pattern: [u8; AUDIO_PATTERN_SIZE], pitch: u8) {
+        self.device.lock().0.set_pattern(pattern, pitch);
+    }
+
+    fn start_beep(&mut self) {
         self.device.resume();
     }
-    pub fn stop_beep(&self) {
+    fn stop_beep(&mut self) {
         self.device.pause();
     }
 }
 
 
-struct SquareWave {
-    phase_inc: f32,
-    phase: f32,
-    volume: f32,
-}
+// Plays the audio pattern through the SDL2 audio callback
+struct PatternWave(PatternPlayer);
 
-impl AudioCallback for SquareWave {
+impl AudioCallback for PatternWave {
     type Channel = f32;
 
     fn callback(&mut self, out: &mut [f32]) {
         
-        // Generates a square wave
-        for x in out.iter_mut() {
-            *x = if self.phase <= 0.5 {
-                self.volume
-            } else {
-                -self.volume
-            };
-            self.phase = (self.phase + self.phase_inc) % 1.0;
-        }
+        // Generates the audio pattern wave
+        self.0.fill(out);
     }
 }
\ No newline at end of file
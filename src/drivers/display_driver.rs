@@ -4,7 +4,9 @@ use sdl2::video::{Window, WindowBuildError};
 use sdl2::pixels::Color;
 use sdl2::render::Canvas;
 
-use crate::cpu::{DISPLAY_HEIGHT, DISPLAY_WIDTH};
+use chip_8::{DISPLAY_HEIGHT, DISPLAY_WIDTH};
+use chip_8::frontend::VideoSink;
+use chip_8::screen::Palette;
 
 const WINDOW_SCALE: u32 = 15;
 const WINDOW_WIDTH: u32 = (DISPLAY_WIDTH as u32) * WINDOW_SCALE;
@@ -15,6 +17,10 @@ pub struct DisplayDriver {
     canvas: Canvas<Window>,
     bg_color: Color,
     main_color: Color,
+
+    // XO-CHIP colors of pixels set only on the second plane, and on both planes
+    second_plane_color: Color,
+    overlap_color: Color,
 }
 
 impl DisplayDriver {
@@ -44,6 +50,8 @@ impl DisplayDriver {
             canvas,
             bg_color: bg_color.unwrap_or(Color::RGB(0, 0, 0)),
             main_color: main_color.unwrap_or(Color::RGB(255, 255, 255)),
+            second_plane_color: Color::RGB(170, 170, 170),
+            overlap_color: Color::RGB(85, 85, 85),
         })
     }
 
@@ -55,28 +63,30 @@ impl DisplayDriver {
             .build()
     }
 
-    // Draw on screen with chosen colors based on current state of the display
-    pub fn draw_display(&mut self, chip8_display: &[bool]) -> Result<(), String>{
+    /* Draw on screen with chosen colors based on current state of the display.
+     * The canvas is scaled so the display fills the window in either resolution */
+    pub fn draw_display(&mut self, chip8_display: &[u8], width: usize, height: usize) -> Result<(), String>{
         
+        self.canvas.set_logical_size(width as u32, height as u32).map_err(|e| e.to_string())?;
+
         // Clear canvas using black color
         self.canvas.set_draw_color(self.bg_color);
         self.canvas.clear();
 
-        // Draw color is set to white
-        self.canvas.set_draw_color(self.main_color);
-
-        // Iterating through each display pixel. If pixel is true, it should be drawn
+        // Iterating through each display pixel. If pixel is set on any plane, it should be drawn
         for (idx, pixel) in chip8_display.iter().enumerate() {
-            if *pixel {
-                let x_coord = (idx % DISPLAY_WIDTH) as u32;
-                let y_coord = (idx / DISPLAY_WIDTH) as u32;
+            if *pixel != 0 {
+                let color = match *pixel {
+                    1 => self.main_color,
+                    2 => self.second_plane_color,
+                    _ => self.overlap_color,
+                };
+                self.canvas.set_draw_color(color);
 
-                let rect = Rect::new(
-                    (x_coord * WINDOW_SCALE) as i32,
-                    (y_coord * WINDOW_SCALE) as i32,
-                    WINDOW_SCALE,
-                    WINDOW_SCALE
-                );
+                let x_coord = (idx % width) as i32;
+                let y_coord = (idx / width) as i32;
+
+                let rect = Rect::new(x_coord, y_coord, 1, 1);
 
                 self.canvas.fill_rect(rect)?
             }
@@ -85,4 +95,24 @@ impl DisplayDriver {
         Ok(())
     }
     
+}
+
+impl VideoSink for DisplayDriver {
+    fn present(&mut self, display: &[u8], width: usize, height: usize) -> Result<(), String> {
+        self.draw_display(display, width, height)
+    }
+
+    fn set_title(&mut self, title: &str) -> Result<(), String> {
+        self.canvas.window_mut().set_title(title).map_err(|e| e.to_string())
+    }
+
+    fn get_palette(&self) -> Palette {
+        let rgb = |color: Color| [color.r, color.g, color.b];
+        Palette {
+            background: rgb(self.bg_color),
+            main: rgb(self.main_color),
+            second_plane: rgb(self.second_plane_color),
+            overlap: rgb(self.overlap_color),
+        }
+    }
 }
\ No newline at end of file
@@ -1,10 +1,11 @@
 use sdl2::event::Event;
-use sdl2::keyboard::Keycode;
+use sdl2::keyboard::{Keycode, Mod};
+
+use chip_8::frontend::{InputEvent, InputSource};
 
 
 pub struct KeypadDriver {
     event_pump: sdl2::EventPump,
-    pub key_pressed: bool,
 }
 
 impl KeypadDriver {
@@ -15,34 +16,23 @@ impl KeypadDriver {
 
         Ok(Self {
             event_pump,
-            key_pressed: false,
         })
     }
 
-    // Polling events checking for Quit, KeyDown and KeyUp events
-    pub fn poll_event(&mut self) -> Result<Option<usize>, ()> {
-        for event in self.event_pump.poll_iter() {
-
-            match event {
-                Event::Quit {..} | Event::KeyDown {keycode: Some(Keycode::Escape), ..} => {
-                    return Err(());
-                },
-                Event::KeyDown {keycode: Some(key), ..} => {
-                    if let Some(k) = KeypadDriver::keycode_to_keypad(key) {
-                        self.key_pressed = true;
-                        return Ok(Some(k));
-                    }
-                },
-                Event::KeyUp {keycode: Some(key), ..} => {
-                    if let Some(k) = KeypadDriver::keycode_to_keypad(key) {
-                        self.key_pressed = false;
-                        return Ok(Some(k));
-                    }
-                },
-                _ => (),
-            }
+    // Converts the function keys F1 to F9 to the save state slot of the same number
+    fn keycode_to_slot(key: Keycode) -> Option<u8> {
+        match key {
+            Keycode::F1 => Some(1),
+            Keycode::F2 => Some(2),
+            Keycode::F3 => Some(3),
+            Keycode::F4 => Some(4),
+            Keycode::F5 => Some(5),
+            Keycode::F6 => Some(6),
+            Keycode::F7 => Some(7),
+            Keycode::F8 => Some(8),
+            Keycode::F9 => Some(9),
+            _ =>           None,
         }
-        Ok(None)
     }
 
     // Converts detected keycodes to CHIP-8 keypad keys
@@ -68,4 +58,64 @@ impl KeypadDriver {
         }
     }
 
+}
+
+impl InputSource for KeypadDriver {
+
+    // Polling events checking for Quit, KeyDown and KeyUp events
+    fn poll_event(&mut self) -> Option<InputEvent> {
+        for event in self.event_pump.poll_iter() {
+
+            match event {
+                Event::Quit {..} | Event::KeyDown {keycode: Some(Keycode::Escape), ..} => {
+                    return Some(InputEvent::Quit);
+                },
+                Event::KeyDown {keycode: Some(Keycode::Equals | Keycode::Plus | Keycode::KpPlus), ..} => {
+                    return Some(InputEvent::SpeedUp);
+                },
+                Event::KeyDown {keycode: Some(Keycode::Minus | Keycode::KpMinus), ..} => {
+                    return Some(InputEvent::SpeedDown);
+                },
+                // Holding Backspace rewinds the emulator
+                Event::KeyDown {keycode: Some(Keycode::Backspace), repeat: false, ..} => {
+                    return Some(InputEvent::RewindStart);
+                },
+                Event::KeyUp {keycode: Some(Keycode::Backspace), ..} => {
+                    return Some(InputEvent::RewindStop);
+                },
+                Event::KeyDown {keycode: Some(Keycode::F12), repeat: false, ..} => {
+                    return Some(InputEvent::Screenshot);
+                },
+                Event::KeyDown {keycode: Some(Keycode::F11), repeat: false, ..} => {
+                    return Some(InputEvent::ToggleGif);
+                },
+                Event::KeyDown {keycode: Some(Keycode::F10), repeat: false, ..} => {
+                    return Some(InputEvent::Debug);
+                },
+                Event::KeyDown {keycode: Some(key), keymod, repeat, ..} => {
+
+                    // Shift + F1 to F9 saves the state to a slot, and F1 to F9 loads it
+                    if let Some(slot) = KeypadDriver::keycode_to_slot(key) {
+                        if repeat {
+                            continue;
+                        }
+                        if keymod.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD) {
+                            return Some(InputEvent::SaveState(slot));
+                        }
+                        return Some(InputEvent::LoadState(slot));
+                    }
+                    if let Some(k) = KeypadDriver::keycode_to_keypad(key) {
+                        return Some(InputEvent::KeyDown(k));
+                    }
+                },
+                Event::KeyUp {keycode: Some(key), ..} => {
+                    if let Some(k) = KeypadDriver::keycode_to_keypad(key) {
+                        return Some(InputEvent::KeyUp(k));
+                    }
+                },
+                _ => (),
+            }
+        }
+        None
+    }
 }
\ No newline at end of file
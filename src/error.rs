@@ -0,0 +1,133 @@
+use std::error::Error;
+use std::fmt;
+
+
+/* Errors that stop the CPU.
+ *
+ * Each error carries the address (PC) and opcode of the instruction that caused it */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CpuError {
+
+    // The opcode does not match any known instruction
+    UnknownOpcode { pc: u16, opcode: u16 },
+
+    // A subroutine was called with all stack levels already in use
+    StackOverflow { pc: u16, opcode: u16 },
+
+    // A return from a subroutine happened without any subroutine being called
+    StackUnderflow { pc: u16, opcode: u16 },
+
+    // The instruction accessed an address past the end of memory
+    MemoryOutOfBounds { pc: u16, opcode: u16, address: usize },
+
+    /* The PC moved to an address where a whole instruction does not fit in memory.
+     * It carries the out of range PC and the opcode of the last instruction executed */
+    PcOutOfRange { pc: usize, opcode: u16 },
+}
+
+impl fmt::Display for CpuError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CpuError::UnknownOpcode { pc, opcode } => {
+                write!(f, "Unknown instruction {:#06X} at address {:#06X}", opcode, pc)
+            },
+            CpuError::StackOverflow { pc, opcode } => {
+                write!(f, "Stack overflow: instruction {:#06X} at address {:#06X} called a subroutine with a full stack", opcode, pc)
+            },
+            CpuError::StackUnderflow { pc, opcode } => {
+                write!(f, "Stack underflow: instruction {:#06X} at address {:#06X} returned with an empty stack", opcode, pc)
+            },
+            CpuError::MemoryOutOfBounds { pc, opcode, address } => {
+                write!(f, "Memory out of bounds: instruction {:#06X} at address {:#06X} accessed address {:#X}", opcode, pc, address)
+            },
+            CpuError::PcOutOfRange { pc, opcode } => {
+                write!(f, "PC out of range: instruction {:#06X} moved the PC to address {:#06X}", opcode, pc)
+            },
+        }
+    }
+}
+
+impl Error for CpuError {}
+
+
+// Error of decoding an opcode that does not match any instruction
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DecodeError {
+    pub opcode: u16,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Unknown instruction {:#06X}", self.opcode)
+    }
+}
+
+impl Error for DecodeError {}
+
+
+// Errors that stop the main loop of a frontend
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RunError {
+
+    // The CPU cannot continue running the program
+    Cpu(CpuError),
+
+    // The frontend failed to present the display, play audio or read input
+    Frontend(String),
+}
+
+impl From<CpuError> for RunError {
+    fn from(e: CpuError) -> Self {
+        RunError::Cpu(e)
+    }
+}
+
+impl fmt::Display for RunError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RunError::Cpu(e) => write!(f, "{}", e),
+            RunError::Frontend(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl Error for RunError {}
+
+
+// Errors that stop a save state from being loaded
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StateError {
+
+    // The data is not a save state, or it is corrupted
+    InvalidFormat,
+
+    // The save state ends before the whole machine state was read
+    Truncated,
+
+    // The save state was written by an incompatible version of the format
+    UnsupportedVersion { found: u16, expected: u16 },
+
+    // The save state was taken from a different ROM than the one loaded
+    RomMismatch { found: u64, expected: u64 },
+}
+
+impl fmt::Display for StateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            StateError::InvalidFormat => {
+                write!(f, "Invalid save state: the file is not a save state or it is corrupted")
+            },
+            StateError::Truncated => {
+                write!(f, "Invalid save state: the file ends before the whole machine state")
+            },
+            StateError::UnsupportedVersion { found, expected } => {
+                write!(f, "Incompatible save state: format version {} is not supported (expected version {})", found, expected)
+            },
+            StateError::RomMismatch { found, expected } => {
+                write!(f, "Incompatible save state: it was taken from a different ROM (hash {:016X}, expected {:016X})", found, expected)
+            },
+        }
+    }
+}
+
+impl Error for StateError {}
@@ -0,0 +1,405 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::chip8::Chip8;
+use crate::cpu::Register;
+
+
+// Values an expression can refer to, besides numbers
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Variable {
+    Register(Register),
+
+    // How many times the breakpoint the expression belongs to was reached
+    Hits,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum UnaryOp {
+    Not,
+    Negate,
+    Complement,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum BinaryOp {
+    Or,
+    And,
+    BitOr,
+    BitXor,
+    BitAnd,
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    ShiftLeft,
+    ShiftRight,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+}
+
+// Binary operators from the lowest to the highest precedence, as in C
+const BINARY_OPS: [&[(&str, BinaryOp)]; 10] = [
+    &[("||", BinaryOp::Or)],
+    &[("&&", BinaryOp::And)],
+    &[("|", BinaryOp::BitOr)],
+    &[("^", BinaryOp::BitXor)],
+    &[("&", BinaryOp::BitAnd)],
+    &[("==", BinaryOp::Equal), ("!=", BinaryOp::NotEqual)],
+    &[("<=", BinaryOp::LessEqual), (">=", BinaryOp::GreaterEqual), ("<", BinaryOp::Less), (">", BinaryOp::Greater)],
+    &[("<<", BinaryOp::ShiftLeft), (">>", BinaryOp::ShiftRight)],
+    &[("+", BinaryOp::Add), ("-", BinaryOp::Sub)],
+    &[("*", BinaryOp::Mul), ("/", BinaryOp::Div), ("%", BinaryOp::Rem)],
+];
+
+// Tree of a parsed expression
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Node {
+    Number(i64),
+    Variable(Variable),
+
+    // [ADDR]: the byte of memory at the address
+    Memory(Box<Node>),
+
+    Unary(UnaryOp, Box<Node>),
+    Binary(BinaryOp, Box<Node>, Box<Node>),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Token {
+    Number(i64),
+    Name(String),
+    Symbol(&'static str),
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Token::Number(value) => write!(f, "{}", value),
+            Token::Name(name) => write!(f, "{}", name),
+            Token::Symbol(symbol) => write!(f, "{}", symbol),
+        }
+    }
+}
+
+// Symbols of the language, with the longer ones first so "<=" is not read as "<" and "="
+const SYMBOLS: [&str; 22] = [
+    "||", "&&", "==", "!=", "<=", ">=", "<<", ">>",
+    "|", "^", "&", "<", ">", "+", "-", "*", "/", "%", "!", "~", "(", ")",
+];
+
+// Splits an expression into numbers, names and symbols
+fn tokenize(s: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut rest = s.trim_start();
+
+    while let Some(c) = rest.chars().next() {
+        if c.is_ascii_alphanumeric() || c == '_' {
+            let end = rest.find(|c: char| !c.is_ascii_alphanumeric() && c != '_').unwrap_or(rest.len());
+            let word = &rest[..end];
+            tokens.push(if c.is_ascii_digit() {
+                let number = match word.strip_prefix("0x").or_else(|| word.strip_prefix("0X")) {
+                    Some(digits) => i64::from_str_radix(digits, 16),
+                    None => word.parse(),
+                };
+                Token::Number(number.map_err(|_| format!("invalid number '{}'", word))?)
+            } else {
+                Token::Name(word.to_lowercase())
+            });
+            rest = &rest[end..];
+        } else if c == '[' || c == ']' {
+            tokens.push(Token::Symbol(if c == '[' { "[" } else { "]" }));
+            rest = &rest[1..];
+        } else {
+            let symbol = SYMBOLS.iter().find(|symbol| rest.starts_with(*symbol))
+                .ok_or_else(|| format!("unexpected character '{}'", c))?;
+            tokens.push(Token::Symbol(symbol));
+            rest = &rest[symbol.len()..];
+        }
+        rest = rest.trim_start();
+    }
+    Ok(tokens)
+}
+
+// How deep unary operators, parentheses and brackets can be nested, so parsing cannot overflow the stack
+const MAX_NESTING: usize = 64;
+
+// Recursive descent parser over the tokens of an expression
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+
+    // Unary operators, parentheses and brackets the next token is nested in
+    nesting: usize,
+}
+
+impl Parser {
+
+    // Consumes the next token if it is the chosen symbol
+    fn accept(&mut self, symbol: &str) -> bool {
+        if matches!(self.tokens.get(self.position), Some(Token::Symbol(s)) if *s == symbol) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    // Consumes the chosen symbol, failing if the next token is a different one
+    fn expect(&mut self, symbol: &str) -> Result<(), String> {
+        if self.accept(symbol) {
+            Ok(())
+        } else {
+            Err(format!("expected '{}'", symbol))
+        }
+    }
+
+    // Parses an operand nested one level deeper than the current one
+    fn parse_nested(&mut self, parse: fn(&mut Self) -> Result<Node, String>) -> Result<Node, String> {
+        if self.nesting == MAX_NESTING {
+            return Err(format!("more than {} levels of nesting", MAX_NESTING));
+        }
+        self.nesting += 1;
+        let node = parse(self);
+        self.nesting -= 1;
+        node
+    }
+
+    // Parses the binary operators of a precedence level, and the higher levels they apply to
+    fn parse_binary(&mut self, level: usize) -> Result<Node, String> {
+        if level == BINARY_OPS.len() {
+            return self.parse_unary();
+        }
+
+        let mut node = self.parse_binary(level + 1)?;
+        'operators: loop {
+            for (symbol, op) in BINARY_OPS[level] {
+                if self.accept(symbol) {
+                    let right = self.parse_binary(level + 1)?;
+                    node = Node::Binary(*op, Box::new(node), Box::new(right));
+                    continue 'operators;
+                }
+            }
+            return Ok(node);
+        }
+    }
+
+    fn parse_unary(&mut self) -> Result<Node, String> {
+        for (symbol, op) in [("!", UnaryOp::Not), ("-", UnaryOp::Negate), ("~", UnaryOp::Complement)] {
+            if self.accept(symbol) {
+                return Ok(Node::Unary(op, Box::new(self.parse_nested(Self::parse_unary)?)));
+            }
+        }
+        self.parse_primary()
+    }
+
+    fn parse_primary(&mut self) -> Result<Node, String> {
+        if self.accept("(") {
+            let node = self.parse_nested(|parser| parser.parse_binary(0))?;
+            self.expect(")")?;
+            return Ok(node);
+        }
+        if self.accept("[") {
+            let node = self.parse_nested(|parser| parser.parse_binary(0))?;
+            self.expect("]")?;
+            return Ok(Node::Memory(Box::new(node)));
+        }
+
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        match token {
+            Some(Token::Number(value)) => Ok(Node::Number(value)),
+            Some(Token::Name(name)) if name == "hits" => Ok(Node::Variable(Variable::Hits)),
+            Some(Token::Name(name)) => Ok(Node::Variable(Variable::Register(name.parse()?))),
+            Some(token @ Token::Symbol(_)) => Err(format!("unexpected '{}'", token)),
+            None => Err(String::from("unexpected end of the expression")),
+        }
+    }
+}
+
+/* Expression over the machine state, such as "pc == 0x2A4 && v3 > 5" or "[i] == 0xFF".
+ *
+ * It has the operators of C, numbers in decimal or hexadecimal (0x), the registers V0-VF, I, PC, SP,
+ * DT, ST and STACK1-STACKF, bytes of memory as [ADDR], and the hit count of a breakpoint as "hits".
+ * Comparisons and logical operators give 1 when true and 0 when false */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Expression {
+    source: String,
+    root: Node,
+}
+
+impl FromStr for Expression {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = |e: String| format!("invalid expression '{}': {}", s.trim(), e);
+
+        let mut parser = Parser { tokens: tokenize(s).map_err(invalid)?, position: 0, nesting: 0 };
+        let root = parser.parse_binary(0).map_err(invalid)?;
+        if let Some(token) = parser.tokens.get(parser.position) {
+            return Err(invalid(format!("unexpected '{}' after the end of the expression", token)));
+        }
+        Ok(Self { source: s.trim().to_string(), root })
+    }
+}
+
+// Shows the expression as it was written
+impl fmt::Display for Expression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+impl Expression {
+
+    // Evaluates the expression, with the chosen hit count, failing on division by zero or memory out of bounds
+    pub fn evaluate(&self, chip8: &Chip8, hits: u64) -> Result<i64, String> {
+        evaluate(&self.root, chip8, hits)
+    }
+
+    // Returns whether the expression is true (not 0)
+    pub fn is_true(&self, chip8: &Chip8, hits: u64) -> Result<bool, String> {
+        Ok(self.evaluate(chip8, hits)? != 0)
+    }
+}
+
+fn evaluate(node: &Node, chip8: &Chip8, hits: u64) -> Result<i64, String> {
+    let value = match node {
+        Node::Number(value) => *value,
+        Node::Variable(Variable::Register(register)) => chip8.get_register(*register) as i64,
+        Node::Variable(Variable::Hits) => hits as i64,
+        Node::Memory(address) => {
+            let address = evaluate(address, chip8, hits)?;
+            let byte = usize::try_from(address).ok().and_then(|address| chip8.get_memory().get(address));
+            *byte.ok_or_else(|| format!("address {:#X} is out of memory", address))? as i64
+        },
+        Node::Unary(op, operand) => {
+            let operand = evaluate(operand, chip8, hits)?;
+            match op {
+                UnaryOp::Not => (operand == 0) as i64,
+                UnaryOp::Negate => operand.wrapping_neg(),
+                UnaryOp::Complement => !operand,
+            }
+        },
+
+        // Logical operators only evaluate their right side when needed
+        Node::Binary(BinaryOp::Or, left, right) => {
+            (evaluate(left, chip8, hits)? != 0 || evaluate(right, chip8, hits)? != 0) as i64
+        },
+        Node::Binary(BinaryOp::And, left, right) => {
+            (evaluate(left, chip8, hits)? != 0 && evaluate(right, chip8, hits)? != 0) as i64
+        },
+        Node::Binary(op, left, right) => {
+            let left = evaluate(left, chip8, hits)?;
+            let right = evaluate(right, chip8, hits)?;
+            match op {
+                BinaryOp::BitOr => left | right,
+                BinaryOp::BitXor => left ^ right,
+                BinaryOp::BitAnd => left & right,
+                BinaryOp::Equal => (left == right) as i64,
+                BinaryOp::NotEqual => (left != right) as i64,
+                BinaryOp::Less => (left < right) as i64,
+                BinaryOp::LessEqual => (left <= right) as i64,
+                BinaryOp::Greater => (left > right) as i64,
+                BinaryOp::GreaterEqual => (left >= right) as i64,
+                BinaryOp::ShiftLeft => u32::try_from(right).ok().and_then(|right| left.checked_shl(right)).unwrap_or(0),
+                BinaryOp::ShiftRight => u32::try_from(right).ok().and_then(|right| left.checked_shr(right)).unwrap_or(0),
+                BinaryOp::Add => left.wrapping_add(right),
+                BinaryOp::Sub => left.wrapping_sub(right),
+                BinaryOp::Mul => left.wrapping_mul(right),
+                BinaryOp::Div | BinaryOp::Rem if right == 0 => return Err(String::from("division by zero")),
+                BinaryOp::Div => left.wrapping_div(right),
+                BinaryOp::Rem => left.wrapping_rem(right),
+                BinaryOp::Or | BinaryOp::And => unreachable!(),
+            }
+        },
+    };
+    Ok(value)
+}
+
+
+// Piece of a log message: text printed as it is, or an expression printed in decimal or hexadecimal
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Part {
+    Text(String),
+    Value { expression: Expression, hex: bool },
+}
+
+/* Message printed by a logpoint, with expressions between braces replaced by their values,
+ * such as "V3 = {v3}, byte at I = {[i]:x}". Values are decimal, or hexadecimal with ":x".
+ * Braces are written as "{{" and "}}" */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LogMessage {
+    source: String,
+    parts: Vec<Part>,
+}
+
+impl FromStr for LogMessage {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = Vec::new();
+        let mut text = String::new();
+        let mut rest = s;
+
+        while let Some(c) = rest.chars().next() {
+            if rest.starts_with("{{") || rest.starts_with("}}") {
+                text.push(c);
+                rest = &rest[2..];
+            } else if c == '{' {
+                let end = rest.find('}').ok_or_else(|| format!("unclosed '{{' in message '{}'", s))?;
+                let (source, hex) = match rest[1..end].strip_suffix(":x") {
+                    Some(source) => (source, true),
+                    None => (&rest[1..end], false),
+                };
+                if !text.is_empty() {
+                    parts.push(Part::Text(std::mem::take(&mut text)));
+                }
+                parts.push(Part::Value { expression: source.parse()?, hex });
+                rest = &rest[(end + 1)..];
+            } else if c == '}' {
+                return Err(format!("unmatched '}}' in message '{}'", s));
+            } else {
+                text.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+        if !text.is_empty() {
+            parts.push(Part::Text(text));
+        }
+        Ok(Self { source: s.to_string(), parts })
+    }
+}
+
+// Shows the message as it was written
+impl fmt::Display for LogMessage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+impl LogMessage {
+
+    // Returns the message with the values of its expressions, showing the errors of those that fail
+    pub fn format(&self, chip8: &Chip8, hits: u64) -> String {
+        self.parts.iter()
+            .map(|part| match part {
+                Part::Text(text) => text.clone(),
+                Part::Value { expression, hex } => match expression.evaluate(chip8, hits) {
+                    Ok(value) if *hex => format!("{:#X}", value),
+                    Ok(value) => value.to_string(),
+                    Err(e) => format!("<{}>", e),
+                },
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+#[path ="./expression_test.rs"]
+mod expression_test;
@@ -0,0 +1,85 @@
+use super::*;
+use crate::quirks::Quirks;
+
+fn new_chip8() -> Chip8 {
+    let mut chip8 = Chip8::new(Quirks::default());
+    chip8.load_rom(&[0xFF, 0x00]).unwrap();
+    chip8.set_register(Register::V(3), 7).unwrap();
+    chip8.set_register(Register::I, 0x200).unwrap();
+    chip8
+}
+
+fn evaluate(source: &str) -> Result<i64, String> {
+    source.parse::<Expression>()?.evaluate(&new_chip8(), 3)
+}
+
+#[test]
+fn test_evaluate() {
+    assert_eq!(evaluate("pc == 0x200 && v3 > 5"), Ok(1));
+    assert_eq!(evaluate("PC == 0x2A4 && V3 > 5"), Ok(0));
+    assert_eq!(evaluate("[i] == 0xFF"), Ok(1));
+    assert_eq!(evaluate("[i + 1]"), Ok(0));
+    assert_eq!(evaluate("dt == 0"), Ok(1));
+    assert_eq!(evaluate("hits % 3 == 0"), Ok(1));
+
+    // Operators have the precedence of C
+    assert_eq!(evaluate("1 + 2 * 3"), Ok(7));
+    assert_eq!(evaluate("(1 + 2) * 3"), Ok(9));
+    assert_eq!(evaluate("1 << 2 + 1"), Ok(8));
+    assert_eq!(evaluate("v3 & 3 == 3"), Ok(1));
+    assert_eq!(evaluate("10 - 4 - 3"), Ok(3));
+    assert_eq!(evaluate("!0 + -2 + ~0"), Ok(-2));
+    assert_eq!(evaluate("0 || 5 > 4"), Ok(1));
+
+    // The right side of a logical operator is only evaluated when needed
+    assert_eq!(evaluate("0 && 1 / 0"), Ok(0));
+    assert_eq!(evaluate("1 || [0x10000]"), Ok(1));
+}
+
+#[test]
+fn test_evaluate_errors() {
+    assert_eq!(evaluate("v3 / 0"), Err(String::from("division by zero")));
+    assert_eq!(evaluate("[0x10000]"), Err(String::from("address 0x10000 is out of memory")));
+    assert_eq!(evaluate("[-1]"), Err(String::from("address 0xFFFFFFFFFFFFFFFF is out of memory")));
+}
+
+#[test]
+fn test_parse_errors() {
+    assert!("".parse::<Expression>().is_err());
+    assert!("v3 >".parse::<Expression>().is_err());
+    assert!("(v3 > 5".parse::<Expression>().is_err());
+    assert!("[i".parse::<Expression>().is_err());
+    assert!("v16 == 1".parse::<Expression>().is_err());
+    assert!("v3 = 1".parse::<Expression>().is_err());
+    assert!("0xZZ".parse::<Expression>().is_err());
+
+    assert_eq!(
+        "v3 5".parse::<Expression>(),
+        Err(String::from("invalid expression 'v3 5': unexpected '5' after the end of the expression"))
+    );
+    assert_eq!(" v3 > 5 ".parse::<Expression>().unwrap().to_string(), "v3 > 5");
+
+    // Deep nesting is refused instead of overflowing the stack
+    let nested = |open: &str, close: &str, depth: usize| format!("{}1{}", open.repeat(depth), close.repeat(depth));
+    assert!(nested("(", ")", 64).parse::<Expression>().is_ok());
+    assert!(nested("(", ")", 65).parse::<Expression>().unwrap_err().ends_with("more than 64 levels of nesting"));
+    assert!(nested("(", ")", 5000).parse::<Expression>().is_err());
+    assert!(nested("[", "]", 5000).parse::<Expression>().is_err());
+    assert!(nested("-!~", "", 5000).parse::<Expression>().is_err());
+}
+
+#[test]
+fn test_log_message() -> Result<(), String> {
+    let chip8 = new_chip8();
+
+    let message: LogMessage = "V3 = {v3}, [I] = {[i]:x}, hit {hits} {{times}}".parse()?;
+    assert_eq!(message.format(&chip8, 2), "V3 = 7, [I] = 0xFF, hit 2 {times}");
+
+    let message: LogMessage = "{v3 / 0}".parse()?;
+    assert_eq!(message.format(&chip8, 1), "<division by zero>");
+
+    assert!("{v3".parse::<LogMessage>().is_err());
+    assert!("v3}".parse::<LogMessage>().is_err());
+    assert!("{v3 >}".parse::<LogMessage>().is_err());
+    Ok(())
+}
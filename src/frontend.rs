@@ -0,0 +1,818 @@
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::BufWriter;
+use std::str::FromStr;
+
+use crate::audio::{AUDIO_PATTERN_SIZE, SAMPLE_RATE};
+use crate::chip8::{Chip8, DEFAULT_CYCLES_PER_FRAME, FRAME_RATE};
+use crate::debugger::{DebugAction, DebugHook};
+use crate::error::RunError;
+use crate::gif_recorder::GifRecorder;
+use crate::movie::{Movie, MovieEvent};
+use crate::rewind::{RewindBuffer, DEFAULT_REWIND_CAPACITY, DEFAULT_REWIND_INTERVAL};
+use crate::scheduler::{FrameScheduler, FrameStats};
+use crate::screen::{self, Palette, DEFAULT_CAPTURE_SCALE};
+use crate::state;
+use crate::trace::{TraceFilter, Tracer};
+use crate::wav_recorder::WavRecorder;
+
+// Limits of the number of instructions run per frame that can be chosen while running
+pub const MIN_CYCLES_PER_FRAME: u32 = 1;
+pub const MAX_CYCLES_PER_FRAME: u32 = 10000;
+
+// Messages are shown in the window title for 2 seconds
+const MESSAGE_FRAMES: u64 = 2 * FRAME_RATE as u64;
+
+
+// Events delivered by an input source to the main loop
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InputEvent {
+
+    // A CHIP-8 keypad key (0x0 to 0xF) was pressed
+    KeyDown(usize),
+
+    // A CHIP-8 keypad key (0x0 to 0xF) was released
+    KeyUp(usize),
+
+    // The user asked to run more instructions per frame
+    SpeedUp,
+
+    // The user asked to run fewer instructions per frame
+    SpeedDown,
+
+    // The user asked to save the machine state to a numbered slot
+    SaveState(u8),
+
+    // The user asked to load the machine state from a numbered slot
+    LoadState(u8),
+
+    // The user pressed the rewind key, so the emulator steps backwards until it is released
+    RewindStart,
+
+    // The user released the rewind key
+    RewindStop,
+
+    // The user asked to save the display as a PNG image
+    Screenshot,
+
+    // The user asked to start recording the display to an animated GIF, or to stop recording it
+    ToggleGif,
+
+    // The user asked to pause in the debugger before the next instruction
+    Debug,
+
+    // The user asked to quit the emulator
+    Quit,
+}
+
+// Shows the CHIP-8 display to the user
+pub trait VideoSink {
+
+    // Presents the display, which has the chosen width and height in pixels
+    fn present(&mut self, display: &[u8], width: usize, height: usize) -> Result<(), String>;
+
+    // Shows the chosen status text to the user, such as in the window title
+    fn set_title(&mut self, _title: &str) -> Result<(), String> {
+        Ok(())
+    }
+
+    // Returns the colors the display is drawn with, used for screenshots
+    fn get_palette(&self) -> Palette {
+        Palette::default()
+    }
+}
+
+// Plays the CHIP-8 beep to the user
+pub trait AudioSink {
+
+    // Sets the audio pattern and pitch played while beeping
+    fn set_pattern(&mut self, pattern: [u8; AUDIO_PATTERN_SIZE], pitch: u8);
+
+    // Starts beeping, or keeps beeping if it already is
+    fn start_beep(&mut self);
+
+    // Stops beeping, or keeps silent if it already is
+    fn stop_beep(&mut self);
+}
+
+// Delivers keypad presses/releases, hotkeys and quit requests from the user
+pub trait InputSource {
+
+    // Returns the next pending event, if there is one
+    fn poll_event(&mut self) -> Option<InputEvent>;
+}
+
+
+// Returns the status text with the instruction rate, shown in the window title
+pub fn speed_title(cycles_per_frame: u32) -> String {
+    format!("CHIP-8 Emulator - {} instructions/s ({} per frame)", cycles_per_frame * FRAME_RATE, cycles_per_frame)
+}
+
+// Returns the next number of instructions per frame when speeding up, about 25% more
+pub fn speed_up(cycles_per_frame: u32) -> u32 {
+    (cycles_per_frame + 1).max(cycles_per_frame * 5 / 4).min(MAX_CYCLES_PER_FRAME)
+}
+
+// Returns the next number of instructions per frame when slowing down, about 20% less
+pub fn speed_down(cycles_per_frame: u32) -> u32 {
+    (cycles_per_frame - 1).min(cycles_per_frame * 4 / 5).max(MIN_CYCLES_PER_FRAME)
+}
+
+
+// Options of the main loop
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RunOptions {
+
+    // Instructions run per frame when the loop starts
+    pub cycles_per_frame: u32,
+
+    /* Path the numbered save state slots are named after (usually the ROM path).
+     * Without it, saving and loading states is disabled */
+    pub state_path: Option<String>,
+
+    // Path every input is recorded to as an input movie, written when the loop ends
+    pub record_path: Option<String>,
+
+    /* Input movie replayed from the start, ignoring the keypad and speed input of the user until it ends.
+     * CHIP-8 should have been created with the seed, quirks and ROM of the movie */
+    pub replay: Option<Movie>,
+
+    /* Whether frames are paced at 60Hz. Without pacing, frames are run as fast as possible,
+     * which is useful for headless runs */
+    pub paced: bool,
+
+    // Number of frames after which the loop ends, if any
+    pub max_frames: Option<u64>,
+
+    /* Whether snapshots are taken every few frames so the rewind key can step back through them.
+     * Rewinding is disabled without it, which spares saving the state of headless runs */
+    pub rewind: bool,
+
+    /* Path the numbered screenshots and GIF recordings are named after (usually the ROM path).
+     * Without it, they are disabled */
+    pub capture_path: Option<String>,
+
+    // How many times larger than the display screenshots and GIF recordings are
+    pub capture_scale: u32,
+
+    // Path of a GIF recorded from the start of the loop until it ends
+    pub gif_path: Option<String>,
+
+    // Path of a WAV file the buzzer is recorded to from the start of the loop until it ends
+    pub wav_path: Option<String>,
+
+    // Path of a file a line is written to for each instruction run, from the start of the loop until it ends
+    pub trace_path: Option<String>,
+
+    // Instructions written to the trace
+    pub trace_filter: TraceFilter,
+}
+
+impl Default for RunOptions {
+    fn default() -> Self {
+        Self {
+            cycles_per_frame: DEFAULT_CYCLES_PER_FRAME,
+            state_path: None,
+            record_path: None,
+            replay: None,
+            paced: true,
+            max_frames: None,
+            rewind: true,
+            capture_path: None,
+            capture_scale: DEFAULT_CAPTURE_SCALE,
+            gif_path: None,
+            wav_path: None,
+            trace_path: None,
+            trace_filter: TraceFilter::default(),
+        }
+    }
+}
+
+
+// Status text shown in the window title: the instruction rate, then a message or the frame-time statistics
+struct Status {
+    cycles_per_frame: u32,
+    stats: Option<FrameStats>,
+
+    // Message shown to the user, and the frame it stops being shown on
+    message: Option<(String, u64)>,
+
+    // Last title sent to the video sink, so it is only sent again when it changes
+    title: String,
+}
+
+impl Status {
+
+    // Shows the chosen message for a few seconds
+    fn show(&mut self, message: String, frame: u64) {
+        self.message = Some((message, frame + MESSAGE_FRAMES));
+    }
+
+    // Sends the title to the video sink if it changed
+    fn update<V: VideoSink>(&mut self, video: &mut V, frame: u64) -> Result<(), String> {
+        if matches!(self.message, Some((_, expires)) if frame >= expires) {
+            self.message = None;
+        }
+
+        let title = match (&self.message, &self.stats) {
+            (Some((message, _)), _) => format!("{} - {}", speed_title(self.cycles_per_frame), message),
+            (None, Some(stats)) => format!("{} - {}", speed_title(self.cycles_per_frame), stats),
+            (None, None) => speed_title(self.cycles_per_frame),
+        };
+        if title != self.title {
+            video.set_title(&title)?;
+            self.title = title;
+        }
+        Ok(())
+    }
+}
+
+// Saves the machine state to a numbered slot, returning the message shown to the user
+fn save_slot(chip8: &Chip8, state_path: Option<&str>, slot: u8) -> String {
+    let path = match state_path {
+        Some(path) => state::slot_path(path, slot),
+        None => return String::from("Save states are disabled"),
+    };
+
+    match std::fs::write(&path, chip8.save_state()) {
+        Ok(()) => format!("Saved state to slot {}", slot),
+        Err(e) => format!("Could not save state to slot {}: {}", slot, e),
+    }
+}
+
+// Loads the machine state from a numbered slot, returning the message shown to the user
+fn load_slot(chip8: &mut Chip8, state_path: Option<&str>, slot: u8) -> String {
+    let path = match state_path {
+        Some(path) => state::slot_path(path, slot),
+        None => return String::from("Save states are disabled"),
+    };
+
+    let result = std::fs::read(&path)
+        .map_err(|e| e.to_string())
+        .and_then(|data| chip8.load_state(&data).map_err(|e| e.to_string()));
+    match result {
+        Ok(()) => format!("Loaded state from slot {}", slot),
+        Err(e) => format!("Could not load state from slot {}: {}", slot, e),
+    }
+}
+
+// Saves the display as the next numbered PNG screenshot, returning the message shown to the user
+fn save_screenshot(chip8: &Chip8, capture_path: Option<&str>, scale: u32, palette: &Palette) -> String {
+    let path = match capture_path {
+        Some(path) => screen::capture_path(path, "screenshot", "png"),
+        None => return String::from("Screenshots are disabled"),
+    };
+
+    let result = chip8.screenshot_png(scale, palette)
+        .and_then(|image| std::fs::write(&path, image).map_err(|e| e.to_string()));
+    match result {
+        Ok(()) => format!("Saved screenshot to '{}'", path),
+        Err(e) => format!("Could not save screenshot: {}", e),
+    }
+}
+
+// GIF being recorded, and the path it is written to
+struct GifRecording {
+    path: String,
+    recorder: GifRecorder<BufWriter<File>>,
+}
+
+// WAV file the buzzer is being recorded to, and its path
+struct WavRecording {
+    path: String,
+    recorder: WavRecorder<BufWriter<File>>,
+}
+
+// Starts recording the buzzer to a WAV file
+fn start_wav(path: String) -> Result<WavRecording, String> {
+    let file = File::create(&path).map_err(|e| format!("WAV file could not be created on path '{}': {}", path, e))?;
+    let recorder = WavRecorder::new(BufWriter::new(file), SAMPLE_RATE)?;
+    Ok(WavRecording { path, recorder })
+}
+
+// Writes the end of the WAV file the buzzer is being recorded to
+fn finish_wav(wav: WavRecording) -> Result<(), String> {
+    wav.recorder.finish().map_err(|e| format!("WAV file could not be written on path '{}': {}", wav.path, e))
+}
+
+// Trace of the instructions being run, and its path
+struct TraceRecording {
+    path: String,
+    tracer: Tracer<BufWriter<File>>,
+}
+
+// Starts writing the instructions run to a trace
+fn start_trace(path: String, filter: TraceFilter) -> Result<TraceRecording, String> {
+    let file = File::create(&path).map_err(|e| format!("Trace could not be created on path '{}': {}", path, e))?;
+    Ok(TraceRecording { path, tracer: Tracer::new(BufWriter::new(file), filter) })
+}
+
+// Writes the rest of the trace
+fn finish_trace(trace: TraceRecording) -> Result<(), String> {
+    trace.tracer.finish().map(|_| ()).map_err(|e| format!("Trace could not be written on path '{}': {}", trace.path, e))
+}
+
+// Recordings made while the loop runs, which are written when it ends
+struct Recordings {
+    movie: Option<Movie>,
+    gif: Option<GifRecording>,
+    wav: Option<WavRecording>,
+    trace: Option<TraceRecording>,
+}
+
+// Starts recording the display to a GIF, sized after the current display
+fn start_gif(chip8: &Chip8, path: String, scale: u32, palette: &Palette) -> Result<GifRecording, String> {
+    let file = File::create(&path).map_err(|e| format!("GIF could not be created on path '{}': {}", path, e))?;
+    let (width, height) = chip8.get_display_size();
+    let recorder = GifRecorder::new(BufWriter::new(file), width, height, scale, palette)?;
+    Ok(GifRecording { path, recorder })
+}
+
+// Writes the end of a GIF being recorded, returning the message shown to the user
+fn finish_gif(gif: GifRecording) -> Result<String, String> {
+    let frames = gif.recorder.get_frame_count();
+    gif.recorder.finish()
+        .and_then(|mut writer| std::io::Write::flush(&mut writer).map_err(|e| e.to_string()))
+        .map_err(|e| format!("GIF could not be written on path '{}': {}", gif.path, e))?;
+    Ok(format!("Saved GIF of {} frames to '{}'", frames, gif.path))
+}
+
+// Starts recording a GIF named after the capture path, or stops the one being recorded, returning the message shown to the user
+fn toggle_gif(chip8: &Chip8, gif: &mut Option<GifRecording>, options: &RunOptions, palette: &Palette) -> String {
+    if let Some(recording) = gif.take() {
+        return finish_gif(recording).unwrap_or_else(|e| e);
+    }
+
+    let path = match &options.capture_path {
+        Some(path) => screen::capture_path(path, "recording", "gif"),
+        None => return String::from("GIF recordings are disabled"),
+    };
+    match start_gif(chip8, path, options.capture_scale, palette) {
+        Ok(recording) => {
+            let message = format!("Recording GIF to '{}'", recording.path);
+            *gif = Some(recording);
+            message
+        },
+        Err(e) => e,
+    }
+}
+
+// Delivers an input to CHIP-8 before the current frame, recording it if a movie is being recorded
+fn deliver(chip8: &mut Chip8, status: &mut Status, recording: &mut Option<Movie>, event: MovieEvent) {
+    match event {
+        MovieEvent::KeyDown(k) => chip8.press_key(k),
+        MovieEvent::KeyUp(k) => chip8.release_key(k),
+        MovieEvent::Speed(s) => status.cycles_per_frame = s.clamp(MIN_CYCLES_PER_FRAME, MAX_CYCLES_PER_FRAME),
+    }
+    if let Some(movie) = recording {
+        movie.record(chip8.get_frame_count(), event);
+    }
+}
+
+/* Runs CHIP-8 until the user quits or the program exits, presenting the display
+ * and beeping through the chosen frontend.
+ *
+ * The main loop keeps running as long as a quit event has not been received.
+ * Each iteration runs one frame of the chosen number of instructions in a burst, which the user can
+ * change while running, and then sleeps until the deadline of the next frame so frames are run
+ * at a rate of 60Hz. The frame-time statistics are shown every second.
+ *
+ * Snapshots of the machine are taken every few frames. While the rewind key is held, frames are
+ * not run: the snapshots are loaded instead, newest first, stepping backwards through the game.
+ *
+ * When recording, the input movie is written even if the CPU stops with an error, so the error
+ * can be reproduced by replaying it. The same goes for the GIF, WAV and trace files being recorded, if any */
+pub fn run<V, A, I>(chip8: &mut Chip8, video: &mut V, audio: &mut A, input: &mut I, options: &RunOptions) -> Result<(), RunError>
+where
+    V: VideoSink,
+    A: AudioSink,
+    I: InputSource,
+{
+    run_with_debugger(chip8, video, audio, input, options, None)
+}
+
+/* Runs CHIP-8 like run, asking the debugger before each instruction whether to pause.
+ *
+ * While the debugger is paused, the loop stops (the display is not updated and no input is read)
+ * until a command of the prompt resumes running. Pausing in the middle of a frame does not change
+ * the result of the frame */
+pub fn run_with_debugger<V, A, I>(
+    chip8: &mut Chip8,
+    video: &mut V,
+    audio: &mut A,
+    input: &mut I,
+    options: &RunOptions,
+    debugger: Option<&mut dyn DebugHook>,
+) -> Result<(), RunError>
+where
+    V: VideoSink,
+    A: AudioSink,
+    I: InputSource,
+{
+    let mut recordings = Recordings {
+        movie: options.record_path.as_ref().map(|_| {
+            Movie::new(chip8.get_rom_hash(), chip8.get_seed(), options.cycles_per_frame, chip8.get_quirks())
+        }),
+        gif: match &options.gif_path {
+            Some(path) => Some(start_gif(chip8, path.clone(), options.capture_scale, &video.get_palette()).map_err(RunError::Frontend)?),
+            None => None,
+        },
+        wav: match &options.wav_path {
+            Some(path) => Some(start_wav(path.clone()).map_err(RunError::Frontend)?),
+            None => None,
+        },
+        trace: match &options.trace_path {
+            Some(path) => Some(start_trace(path.clone(), options.trace_filter).map_err(RunError::Frontend)?),
+            None => None,
+        },
+    };
+
+    let result = run_loop(chip8, video, audio, input, options, &mut recordings, debugger);
+
+    if let Some(gif) = recordings.gif {
+        if let Err(e) = finish_gif(gif) {
+            return result.and(Err(RunError::Frontend(e)));
+        }
+    }
+    if let Some(wav) = recordings.wav {
+        if let Err(e) = finish_wav(wav) {
+            return result.and(Err(RunError::Frontend(e)));
+        }
+    }
+    if let Some(trace) = recordings.trace {
+        if let Err(e) = finish_trace(trace) {
+            return result.and(Err(RunError::Frontend(e)));
+        }
+    }
+    if let (Some(path), Some(mut movie)) = (&options.record_path, recordings.movie) {
+        movie.length = chip8.get_frame_count();
+        if let Err(e) = std::fs::write(path, movie.to_string()) {
+            return result.and(Err(RunError::Frontend(format!("Input movie could not be written on path '{}': {}", path, e))));
+        }
+    }
+    result
+}
+
+/* Returns whether the debugger pauses before the instruction at the PC. Otherwise the instruction
+ * is about to run, so it is traced, which means it is never traced twice when CHIP-8 pauses */
+fn should_break(chip8: &Chip8, debugger: Option<&mut (dyn DebugHook + '_)>, trace: Option<&mut TraceRecording>) -> bool {
+    if debugger.is_some_and(|debugger| debugger.should_break(chip8)) {
+        return true;
+    }
+    if let Some(trace) = trace {
+        trace.tracer.trace(chip8);
+    }
+    false
+}
+
+fn run_loop<V, A, I>(
+    chip8: &mut Chip8,
+    video: &mut V,
+    audio: &mut A,
+    input: &mut I,
+    options: &RunOptions,
+    recordings: &mut Recordings,
+    mut debugger: Option<&mut dyn DebugHook>,
+) -> Result<(), RunError>
+where
+    V: VideoSink,
+    A: AudioSink,
+    I: InputSource,
+{
+    let mut scheduler = FrameScheduler::new(FRAME_RATE);
+    let state_path = options.state_path.as_deref();
+    let mut status = Status {
+        cycles_per_frame: options.cycles_per_frame.clamp(MIN_CYCLES_PER_FRAME, MAX_CYCLES_PER_FRAME),
+        stats: None,
+        message: None,
+        title: String::new(),
+    };
+
+    // Frames run by this loop, which keeps counting when a save state is loaded
+    let mut frame: u64 = 0;
+
+    let mut rewind = RewindBuffer::new(DEFAULT_REWIND_CAPACITY);
+    let mut rewinding = false;
+
+    // Going back in time would desynchronize the movie being recorded or replayed
+    let movie_active = recordings.movie.is_some() || options.replay.is_some();
+
+    // Snapshots are only taken when they can be rewound to
+    let rewind_enabled = options.rewind && !movie_active;
+
+    while options.max_frames.is_none_or(|max_frames| frame < max_frames) {
+        scheduler.begin_frame();
+        let replaying = options.replay.as_ref().is_some_and(|movie| chip8.get_frame_count() <= movie.length);
+
+        // Key press/release, speed change and save state events that happened since the last frame
+        while let Some(event) = input.poll_event() {
+            let delivered = match event {
+                InputEvent::KeyDown(k) => Some(MovieEvent::KeyDown(k)),
+                InputEvent::KeyUp(k) => Some(MovieEvent::KeyUp(k)),
+                InputEvent::SpeedUp => Some(MovieEvent::Speed(speed_up(status.cycles_per_frame))),
+                InputEvent::SpeedDown => Some(MovieEvent::Speed(speed_down(status.cycles_per_frame))),
+                InputEvent::SaveState(slot) => {
+                    status.show(save_slot(chip8, state_path, slot), frame);
+                    None
+                },
+                InputEvent::LoadState(_) | InputEvent::RewindStart if movie_active => {
+                    status.show(String::from("Going back is disabled while recording or replaying"), frame);
+                    None
+                },
+                InputEvent::RewindStart if !rewind_enabled => {
+                    status.show(String::from("Rewind is disabled"), frame);
+                    None
+                },
+                InputEvent::LoadState(slot) => {
+                    status.show(load_slot(chip8, state_path, slot), frame);
+                    None
+                },
+                InputEvent::RewindStart => {
+                    rewinding = true;
+                    None
+                },
+                InputEvent::RewindStop => {
+                    rewinding = false;
+                    None
+                },
+                InputEvent::Screenshot => {
+                    let message = save_screenshot(chip8, options.capture_path.as_deref(), options.capture_scale, &video.get_palette());
+                    status.show(message, frame);
+                    None
+                },
+                InputEvent::ToggleGif => {
+                    status.show(toggle_gif(chip8, &mut recordings.gif, options, &video.get_palette()), frame);
+                    None
+                },
+                InputEvent::Debug => {
+                    match debugger.as_deref_mut() {
+                        Some(debugger) => debugger.pause(),
+                        None => status.show(String::from("Debugger is disabled"), frame),
+                    }
+                    None
+                },
+                InputEvent::Quit => return Ok(()),
+            };
+
+            // While a movie is replayed, the user input is ignored
+            if let (Some(event), false) = (delivered, replaying) {
+                deliver(chip8, &mut status, &mut recordings.movie, event);
+            }
+        }
+
+        // A debugger controlled by a client handles the requests it sent while running
+        if debugger.as_deref_mut().is_some_and(|d| d.poll(chip8) == DebugAction::Quit) {
+            return Ok(());
+        }
+
+        if let Some(movie) = &options.replay {
+            for event in movie.get_events(chip8.get_frame_count()) {
+                deliver(chip8, &mut status, &mut recordings.movie, event);
+            }
+            if chip8.get_frame_count() == movie.length {
+                status.show(String::from("Replay finished"), frame);
+            }
+        }
+        status.update(video, frame).map_err(RunError::Frontend)?;
+
+        if rewinding {
+            // The keys held in the snapshot are long released, so the keys held now are kept
+            if let Some(snapshot) = rewind.pop() {
+                let keypad = chip8.get_keypad();
+                chip8.load_state(&snapshot).map_err(|e| RunError::Frontend(e.to_string()))?;
+                for (idx, pressed) in keypad.into_iter().enumerate() {
+                    if pressed {
+                        chip8.press_key(idx);
+                    } else {
+                        chip8.release_key(idx);
+                    }
+                }
+            }
+            status.show(format!("Rewinding ({:.1}s left)", rewind.len() as f64 * DEFAULT_REWIND_INTERVAL as f64 / FRAME_RATE as f64), frame);
+        } else {
+            // The prompt is shown every time the debugger pauses, until the frame ends
+            while !chip8.run_frame_until(status.cycles_per_frame, |chip8| should_break(chip8, debugger.as_deref_mut(), recordings.trace.as_mut()))? {
+                let Some(debugger) = debugger.as_deref_mut() else { break };
+
+                let (width, height) = chip8.get_display_size();
+                video.present(chip8.get_display(), width, height).map_err(RunError::Frontend)?;
+                audio.stop_beep();
+                status.show(String::from("Paused in the debugger"), frame);
+                status.update(video, frame).map_err(RunError::Frontend)?;
+
+                if debugger.prompt(chip8) == DebugAction::Quit {
+                    return Ok(());
+                }
+                status.message = None;
+                scheduler.begin_frame();
+            }
+
+            if rewind_enabled && chip8.get_frame_count().is_multiple_of(DEFAULT_REWIND_INTERVAL) {
+                rewind.push(chip8.save_state());
+            }
+        }
+        frame += 1;
+
+        // Updates the display at a rate of 60Hz
+        let (width, height) = chip8.get_display_size();
+        video.present(chip8.get_display(), width, height).map_err(RunError::Frontend)?;
+        if let Some(gif) = &mut recordings.gif {
+            gif.recorder.capture(chip8.get_display(), width, height).map_err(RunError::Frontend)?;
+        }
+
+        // Beeps at a rate of 60Hz
+        if chip8.get_beep_state() {
+            let (pattern, pitch) = chip8.get_audio_pattern();
+            audio.set_pattern(pattern, pitch);
+            audio.start_beep();
+        } else {
+            audio.stop_beep();
+        }
+        if let Some(wav) = &mut recordings.wav {
+            let (pattern, pitch) = chip8.get_audio_pattern();
+            wav.recorder.capture(chip8.get_beep_state(), pattern, pitch).map_err(RunError::Frontend)?;
+        }
+
+        // The program asked the interpreter to exit
+        if chip8.has_exited() {
+            return Ok(());
+        }
+
+        // Waits for the deadline of the next frame
+        if options.paced {
+            scheduler.end_frame();
+        }
+
+        if frame.is_multiple_of(FRAME_RATE as u64) {
+            status.stats = Some(scheduler.take_stats());
+        }
+    }
+    Ok(())
+}
+
+
+// Video sink that discards every frame
+pub struct NullVideo;
+
+impl VideoSink for NullVideo {
+    fn present(&mut self, _display: &[u8], _width: usize, _height: usize) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+// Video sink that keeps the last presented frame in memory
+#[derive(Default)]
+pub struct MemoryVideo {
+    pub display: Vec<u8>,
+    pub width: usize,
+    pub height: usize,
+
+    // How many frames have been presented
+    pub frames: u64,
+
+    // Last status text shown
+    pub title: String,
+}
+
+impl VideoSink for MemoryVideo {
+    fn present(&mut self, display: &[u8], width: usize, height: usize) -> Result<(), String> {
+        self.display.clear();
+        self.display.extend_from_slice(display);
+        self.width = width;
+        self.height = height;
+        self.frames += 1;
+        Ok(())
+    }
+
+    fn set_title(&mut self, title: &str) -> Result<(), String> {
+        self.title = title.to_string();
+        Ok(())
+    }
+}
+
+// Audio sink that stays silent, only keeping track of whether it should be beeping
+#[derive(Default)]
+pub struct NullAudio {
+    pub beeping: bool,
+}
+
+impl AudioSink for NullAudio {
+    fn set_pattern(&mut self, _pattern: [u8; AUDIO_PATTERN_SIZE], _pitch: u8) {}
+
+    fn start_beep(&mut self) {
+        self.beeping = true;
+    }
+
+    fn stop_beep(&mut self) {
+        self.beeping = false;
+    }
+}
+
+// Input source that never delivers any events
+pub struct NullInput;
+
+impl InputSource for NullInput {
+    fn poll_event(&mut self) -> Option<InputEvent> {
+        None
+    }
+}
+
+/* Input source that delivers a scripted list of events, each before the chosen frame.
+ *
+ * The main loop polls events until there are none left once per frame, so the script
+ * moves on to the next frame every time a poll returns no event */
+#[derive(Default)]
+pub struct ScriptedInput {
+    events: VecDeque<(u64, InputEvent)>,
+    frame: u64,
+}
+
+impl ScriptedInput {
+
+    // Creates new input source that delivers the chosen events in order, before the first frame
+    pub fn new(events: &[InputEvent]) -> Self {
+        Self {
+            events: events.iter().map(|e| (0, *e)).collect(),
+            frame: 0,
+        }
+    }
+
+    // Adds an event to the script, delivered before the chosen frame
+    pub fn push_at(&mut self, frame: u64, event: InputEvent) {
+        let idx = self.events.partition_point(|(f, _)| *f <= frame);
+        self.events.insert(idx, (frame, event));
+    }
+
+    // Adds an event to the end of the script
+    pub fn push(&mut self, event: InputEvent) {
+        let frame = self.events.back().map_or(self.frame, |(f, _)| *f);
+        self.events.push_back((frame, event));
+    }
+}
+
+impl InputSource for ScriptedInput {
+    fn poll_event(&mut self) -> Option<InputEvent> {
+        match self.events.front() {
+            Some((frame, _)) if *frame <= self.frame => self.events.pop_front().map(|(_, e)| e),
+            _ => {
+                self.frame += 1;
+                None
+            },
+        }
+    }
+}
+
+
+// Key press of a headless run script: a keypad key held for some frames, starting before a frame
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KeyPress {
+    pub frame: u64,
+    pub key: usize,
+    pub held_frames: u64,
+}
+
+// Key presses are held for 6 frames (0.1s) unless chosen otherwise
+const DEFAULT_HELD_FRAMES: u64 = 6;
+
+impl KeyPress {
+
+    // Adds the press and release events of the key to a script
+    pub fn push_to(&self, script: &mut ScriptedInput) {
+        script.push_at(self.frame, InputEvent::KeyDown(self.key));
+        script.push_at(self.frame.saturating_add(self.held_frames), InputEvent::KeyUp(self.key));
+    }
+}
+
+// Reads a key press written as FRAME:KEY or FRAME:KEY:HELD_FRAMES, with the key in hexadecimal
+impl FromStr for KeyPress {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid key press '{}' (expected FRAME:KEY or FRAME:KEY:HELD_FRAMES, such as 120:A:10)", s);
+        let parts: Vec<&str> = s.split(':').collect();
+
+        let (frame, key, held_frames) = match parts[..] {
+            [frame, key] => (frame, key, None),
+            [frame, key, held_frames] => (frame, key, Some(held_frames)),
+            _ => return Err(invalid()),
+        };
+        let press = Self {
+            frame: frame.parse().map_err(|_| invalid())?,
+            key: usize::from_str_radix(key, 16).ok().filter(|k| *k < 16).ok_or_else(invalid)?,
+            held_frames: match held_frames {
+                Some(h) => h.parse().ok().filter(|h| *h > 0).ok_or_else(invalid)?,
+                None => DEFAULT_HELD_FRAMES,
+            },
+        };
+
+        // The key is released on a frame that must be countable too
+        if press.frame.checked_add(press.held_frames).is_none() {
+            return Err(format!("invalid key press '{}' (the key is released after the last frame that can be counted)", s));
+        }
+        Ok(press)
+    }
+}
+
+#[cfg(test)]
+#[path ="./frontend_test.rs"]
+mod frontend_test;
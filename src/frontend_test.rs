@@ -0,0 +1,440 @@
+use super::*;
+use crate::chip8::DEFAULT_CYCLES_PER_FRAME;
+use crate::debugger::{Debugger, ScriptedConsole};
+use crate::movie::{Movie, MovieEvent};
+use crate::quirks::Quirks;
+
+#[test]
+fn test_scripted_input() {
+    let mut input = ScriptedInput::new(&[InputEvent::KeyDown(0xA), InputEvent::KeyUp(0xA)]);
+    input.push(InputEvent::Quit);
+
+    assert_eq!(input.poll_event(), Some(InputEvent::KeyDown(0xA)));
+    assert_eq!(input.poll_event(), Some(InputEvent::KeyUp(0xA)));
+    assert_eq!(input.poll_event(), Some(InputEvent::Quit));
+    assert_eq!(input.poll_event(), None);
+}
+
+#[test]
+fn test_run_quits() -> Result<(), String> {
+    let mut chip8 = Chip8::new(Quirks::default());
+    chip8.load_rom(&[0x12, 0x00])?; // Jumps to itself forever
+
+    let mut input = ScriptedInput::new(&[InputEvent::Quit]);
+    let result = run(&mut chip8, &mut NullVideo, &mut NullAudio::default(), &mut input, &RunOptions::default());
+    assert_eq!(result, Ok(()));
+    Ok(())
+}
+
+#[test]
+fn test_run_headless() -> Result<(), String> {
+    let mut chip8 = Chip8::new(Quirks::default());
+    chip8.load_rom(&[
+        0x60, 0x02, // V0 = 2
+        0xF0, 0x15, // Delay timer = V0
+        0xF0, 0x29, // I = sprite of character 2
+        0xD1, 0x15, // Draws character 2 at (V1, V1) = (0, 0)
+        0xF0, 0x07, // V0 = delay timer
+        0x30, 0x00, // Skips the jump once the delay timer reaches 0
+        0x12, 0x08, // Jumps back to reading the delay timer
+        0x00, 0xFD, // Exits
+    ])?;
+
+    let mut video = MemoryVideo::default();
+    let result = run(&mut chip8, &mut video, &mut NullAudio::default(), &mut NullInput, &RunOptions::default());
+    assert_eq!(result, Ok(()));
+
+    // The display is presented at least once while waiting for the delay timer
+    assert!(video.frames > 0);
+    assert_eq!((video.width, video.height), (64, 32));
+    assert_eq!(video.display[..4], [1, 1, 1, 1]); // Top row of character 2
+    Ok(())
+}
+
+#[test]
+fn test_run_cpu_error() -> Result<(), String> {
+    let mut chip8 = Chip8::new(Quirks::default());
+    chip8.load_rom(&[0x00, 0xEE])?; // Returns without calling a subroutine
+
+    let result = run(&mut chip8, &mut NullVideo, &mut NullAudio::default(), &mut NullInput, &RunOptions::default());
+    assert!(matches!(result, Err(RunError::Cpu(_))));
+    Ok(())
+}
+
+#[test]
+fn test_speed_steps() {
+    assert_eq!(speed_up(1), 2);
+    assert_eq!(speed_up(9), 11);
+    assert_eq!(speed_up(100), 125);
+    assert_eq!(speed_up(MAX_CYCLES_PER_FRAME), MAX_CYCLES_PER_FRAME);
+
+    assert_eq!(speed_down(11), 8);
+    assert_eq!(speed_down(2), 1);
+    assert_eq!(speed_down(MIN_CYCLES_PER_FRAME), MIN_CYCLES_PER_FRAME);
+}
+
+#[test]
+fn test_run_speed_hotkeys() -> Result<(), String> {
+    let mut chip8 = Chip8::new(Quirks::default());
+    chip8.load_rom(&[0x00, 0xFD])?; // Exits right away
+
+    let mut video = MemoryVideo::default();
+    let mut input = ScriptedInput::new(&[InputEvent::SpeedUp, InputEvent::SpeedUp, InputEvent::SpeedDown]);
+    let options = RunOptions { cycles_per_frame: 20, ..RunOptions::default() };
+    let result = run(&mut chip8, &mut video, &mut NullAudio::default(), &mut input, &options);
+    assert_eq!(result, Ok(()));
+
+    // 20 -> 25 -> 31 -> 24 instructions per frame
+    assert_eq!(video.title, speed_title(24));
+    assert!(video.title.contains("1440 instructions/s"));
+    Ok(())
+}
+
+#[test]
+fn test_run_state_slots() -> Result<(), String> {
+    let rom = [0x60, 0x2A, 0x00, 0xFD]; // V0 = 0x2A, then exits
+    let path = std::env::temp_dir().join(format!("chip8_frontend_test_{}", std::process::id()));
+    let options = RunOptions { state_path: Some(path.to_string_lossy().into_owned()), ..RunOptions::default() };
+
+    let mut chip8 = Chip8::new(Quirks::default());
+    chip8.load_rom(&rom)?;
+    let mut video = MemoryVideo::default();
+    let mut input = ScriptedInput::new(&[InputEvent::SaveState(1), InputEvent::LoadState(2)]);
+    run(&mut chip8, &mut video, &mut NullAudio::default(), &mut input, &options).unwrap();
+
+    // Slot 2 was never saved, so the message is about failing to load it
+    assert!(video.title.contains("Could not load state from slot 2"));
+
+    // Slot 1 holds the state before the first frame
+    let slot = state::slot_path(options.state_path.as_deref().unwrap(), 1);
+    let data = std::fs::read(&slot).map_err(|e| e.to_string())?;
+    std::fs::remove_file(&slot).map_err(|e| e.to_string())?;
+    assert_eq!(chip8.load_state(&data), Ok(()));
+    assert_eq!(chip8.get_frame_count(), 0);
+    assert!(!chip8.has_exited());
+    Ok(())
+}
+
+#[test]
+fn test_run_state_slots_disabled() -> Result<(), String> {
+    let mut chip8 = Chip8::new(Quirks::default());
+    chip8.load_rom(&[0x00, 0xFD])?;
+
+    let mut video = MemoryVideo::default();
+    let mut input = ScriptedInput::new(&[InputEvent::SaveState(1)]);
+    run(&mut chip8, &mut video, &mut NullAudio::default(), &mut input, &RunOptions::default()).unwrap();
+    assert!(video.title.ends_with("Save states are disabled"));
+    Ok(())
+}
+
+#[test]
+fn test_run_rewind_keeps_keypad() -> Result<(), String> {
+    let mut chip8 = Chip8::new(Quirks::default());
+    chip8.load_rom(&[0x12, 0x00])?; // Jumps to itself forever
+
+    // Key 5 is held in the snapshots rewound to, but key 3 is the one held while rewinding
+    let mut input = ScriptedInput::default();
+    input.push_at(0, InputEvent::KeyDown(0x5));
+    input.push_at(4, InputEvent::KeyUp(0x5));
+    input.push_at(5, InputEvent::KeyDown(0x3));
+    input.push_at(6, InputEvent::RewindStart);
+    let options = RunOptions { paced: false, max_frames: Some(10), ..RunOptions::default() };
+    run(&mut chip8, &mut NullVideo, &mut NullAudio::default(), &mut input, &options).unwrap();
+
+    assert_eq!(chip8.get_frame_count(), 2);
+    let mut keypad = [false; 16];
+    keypad[0x3] = true;
+    assert_eq!(chip8.get_keypad(), keypad);
+    Ok(())
+}
+
+#[test]
+fn test_run_rewind_disabled() -> Result<(), String> {
+    let mut chip8 = Chip8::new(Quirks::default());
+    chip8.load_rom(&[0x12, 0x00])?;
+
+    let mut video = MemoryVideo::default();
+    let mut input = ScriptedInput::default();
+    input.push_at(4, InputEvent::RewindStart);
+    let options = RunOptions { paced: false, max_frames: Some(6), rewind: false, ..RunOptions::default() };
+    run(&mut chip8, &mut video, &mut NullAudio::default(), &mut input, &options).unwrap();
+    assert!(video.title.ends_with("Rewind is disabled"));
+    assert_eq!(chip8.get_frame_count(), 6);
+    Ok(())
+}
+
+// Counts the frames key 5 is held in V1 and stores random numbers in V2, exiting after 30 frames
+const KEY_COUNTER_ROM: [u8; 20] = [
+    0x60, 0x1E, // V0 = 30
+    0xF0, 0x15, // Delay timer = V0
+    0x65, 0x05, // V5 = 5
+    0xE5, 0xA1, // Skips the increment if key V5 is not pressed
+    0x71, 0x01, // V1 += 1
+    0xC2, 0xFF, // V2 = random number
+    0xF3, 0x07, // V3 = delay timer
+    0x33, 0x00, // Skips the jump once the delay timer reaches 0
+    0x12, 0x04, // Jumps back to reading key V5
+    0x00, 0xFD, // Exits
+];
+
+#[test]
+fn test_run_record_replay() -> Result<(), String> {
+    let path = std::env::temp_dir().join(format!("chip8_frontend_test_{}.movie", std::process::id()));
+    let path = path.to_string_lossy().into_owned();
+
+    // Recording a session
+    let mut chip8 = Chip8::new(Quirks::default());
+    chip8.load_rom(&KEY_COUNTER_ROM)?;
+    let mut input = ScriptedInput::new(&[InputEvent::KeyDown(0x5), InputEvent::SpeedUp, InputEvent::LoadState(1)]);
+    let options = RunOptions { record_path: Some(path.clone()), ..RunOptions::default() };
+    run(&mut chip8, &mut NullVideo, &mut NullAudio::default(), &mut input, &options).unwrap();
+
+    let movie: Movie = std::fs::read_to_string(&path).map_err(|e| e.to_string())?.parse()?;
+    std::fs::remove_file(&path).map_err(|e| e.to_string())?;
+    assert_eq!(movie.seed, chip8.get_seed());
+    assert_eq!(movie.events, [(0, MovieEvent::KeyDown(0x5)), (0, MovieEvent::Speed(11))]);
+    assert_eq!(movie.length, chip8.get_frame_count());
+
+    // Replaying it on a new machine, with different user input, gives the same result
+    let mut replayed = Chip8::new(movie.quirks);
+    replayed.set_seed(movie.seed);
+    replayed.load_rom(&KEY_COUNTER_ROM)?;
+    movie.check_rom(replayed.get_rom_hash())?;
+
+    let mut input = ScriptedInput::new(&[InputEvent::KeyDown(0x6), InputEvent::SpeedDown]);
+    let options = RunOptions { cycles_per_frame: movie.cycles_per_frame, replay: Some(movie), ..RunOptions::default() };
+    run(&mut replayed, &mut NullVideo, &mut NullAudio::default(), &mut input, &options).unwrap();
+    assert_eq!(replayed.get_machine_state(), chip8.get_machine_state());
+    Ok(())
+}
+
+#[test]
+fn test_scripted_input_frames() {
+    let mut input = ScriptedInput::default();
+    KeyPress { frame: 1, key: 0x5, held_frames: 2 }.push_to(&mut input);
+    input.push_at(0, InputEvent::SpeedUp);
+
+    // Each poll that returns no event ends a frame
+    assert_eq!(input.poll_event(), Some(InputEvent::SpeedUp));
+    assert_eq!(input.poll_event(), None);
+    assert_eq!(input.poll_event(), Some(InputEvent::KeyDown(0x5)));
+    assert_eq!(input.poll_event(), None);
+    assert_eq!(input.poll_event(), None);
+    assert_eq!(input.poll_event(), Some(InputEvent::KeyUp(0x5)));
+    assert_eq!(input.poll_event(), None);
+}
+
+#[test]
+fn test_key_press_parse() {
+    assert_eq!("120:A".parse(), Ok(KeyPress { frame: 120, key: 0xA, held_frames: 6 }));
+    assert_eq!("0:f:30".parse(), Ok(KeyPress { frame: 0, key: 0xF, held_frames: 30 }));
+    assert!("120".parse::<KeyPress>().is_err());
+    assert!("120:G".parse::<KeyPress>().is_err());
+    assert!("120:10".parse::<KeyPress>().is_err());
+    assert!("120:A:0".parse::<KeyPress>().is_err());
+    assert!("1:2:18446744073709551615".parse::<KeyPress>().is_err());
+    assert!("18446744073709551615:2".parse::<KeyPress>().is_err());
+}
+
+#[test]
+fn test_run_unpaced() -> Result<(), String> {
+    let mut chip8 = Chip8::new(Quirks::default());
+    chip8.load_rom(&KEY_COUNTER_ROM)?;
+
+    // Key 5 is held for 10 frames starting before the 5th frame, and the loop ends after 20 frames
+    let mut input = ScriptedInput::default();
+    KeyPress { frame: 4, key: 0x5, held_frames: 10 }.push_to(&mut input);
+    let options = RunOptions { paced: false, max_frames: Some(20), ..RunOptions::default() };
+
+    let start = std::time::Instant::now();
+    run(&mut chip8, &mut NullVideo, &mut NullAudio::default(), &mut input, &options).unwrap();
+    assert!(start.elapsed() < std::time::Duration::from_millis(300));
+
+    assert_eq!(chip8.get_frame_count(), 20);
+
+    // Same as pressing the key directly between the frames
+    let mut expected = Chip8::new(Quirks::default());
+    expected.set_seed(chip8.get_seed());
+    expected.load_rom(&KEY_COUNTER_ROM)?;
+    for frame in 0..20 {
+        match frame {
+            4 => expected.press_key(0x5),
+            14 => expected.release_key(0x5),
+            _ => (),
+        }
+        expected.run_frame(DEFAULT_CYCLES_PER_FRAME).unwrap();
+    }
+    assert_eq!(chip8.get_machine_state(), expected.get_machine_state());
+    assert!(!chip8.get_machine_state().contains("V1: 0x00"));
+    Ok(())
+}
+
+#[test]
+fn test_run_screenshot() -> Result<(), String> {
+    let path = std::env::temp_dir().join(format!("chip8_frontend_test_{}", std::process::id()));
+    let path = path.to_string_lossy().into_owned();
+    let options = RunOptions { capture_path: Some(path.clone()), capture_scale: 1, ..RunOptions::default() };
+
+    let mut chip8 = Chip8::new(Quirks::default());
+    chip8.load_rom(&[0x00, 0xFD])?;
+    let mut video = MemoryVideo::default();
+    let mut input = ScriptedInput::new(&[InputEvent::Screenshot]);
+    run(&mut chip8, &mut video, &mut NullAudio::default(), &mut input, &options).unwrap();
+
+    let screenshot = format!("{}.screenshot1.png", path);
+    assert!(video.title.ends_with(&format!("Saved screenshot to '{}'", screenshot)));
+    let image = std::fs::read(&screenshot).map_err(|e| e.to_string())?;
+    std::fs::remove_file(&screenshot).map_err(|e| e.to_string())?;
+    assert_eq!(image, chip8.screenshot_png(1, &Palette::default())?);
+    Ok(())
+}
+
+#[test]
+fn test_run_gif() -> Result<(), String> {
+    let path = std::env::temp_dir().join(format!("chip8_frontend_test_{}", std::process::id()));
+    let path = path.to_string_lossy().into_owned();
+    let options = RunOptions {
+        capture_path: Some(path.clone()),
+        capture_scale: 1,
+        paced: false,
+        max_frames: Some(20),
+        ..RunOptions::default()
+    };
+
+    // The toggled GIF is written when it is toggled again
+    let mut chip8 = Chip8::new(Quirks::default());
+    chip8.load_rom(&KEY_COUNTER_ROM)?;
+    let mut video = MemoryVideo::default();
+    let mut input = ScriptedInput::default();
+    input.push_at(5, InputEvent::ToggleGif);
+    input.push_at(15, InputEvent::ToggleGif);
+    run(&mut chip8, &mut video, &mut NullAudio::default(), &mut input, &options).unwrap();
+
+    let toggled_gif = format!("{}.recording1.gif", path);
+    assert!(video.title.ends_with(&format!("Saved GIF of 10 frames to '{}'", toggled_gif)));
+
+    // The GIF of the whole run is written when the loop ends
+    let headless_gif = format!("{}.gif", path);
+    let mut chip8 = Chip8::new(Quirks::default());
+    chip8.load_rom(&KEY_COUNTER_ROM)?;
+    let options = RunOptions { gif_path: Some(headless_gif.clone()), ..options };
+    run(&mut chip8, &mut NullVideo, &mut NullAudio::default(), &mut NullInput, &options).unwrap();
+
+    for gif in [&toggled_gif, &headless_gif] {
+        let data = std::fs::read(gif).map_err(|e| e.to_string())?;
+        std::fs::remove_file(gif).map_err(|e| e.to_string())?;
+        assert_eq!(data[..6], *b"GIF89a");
+    }
+    Ok(())
+}
+
+#[test]
+fn test_run_wav() -> Result<(), String> {
+    let path = std::env::temp_dir().join(format!("chip8_frontend_test_{}.wav", std::process::id()));
+    let path = path.to_string_lossy().into_owned();
+
+    let mut chip8 = Chip8::new(Quirks::default());
+    chip8.load_rom(&[
+        0x60, 0x0A, // V0 = 10
+        0xF0, 0x18, // Sound timer = V0
+        0x12, 0x04, // Jumps to itself forever
+    ])?;
+    let options = RunOptions { wav_path: Some(path.clone()), paced: false, max_frames: Some(20), ..RunOptions::default() };
+    run(&mut chip8, &mut NullVideo, &mut NullAudio::default(), &mut NullInput, &options).unwrap();
+
+    let reader = hound::WavReader::open(&path).map_err(|e| e.to_string())?;
+    let samples = reader.into_samples::<i16>().collect::<Result<Vec<i16>, _>>().map_err(|e| e.to_string())?;
+    std::fs::remove_file(&path).map_err(|e| e.to_string())?;
+
+    // The buzzer sounds during the 10 frames the sound timer runs, then stays silent
+    let beeping: Vec<bool> = samples.chunks(735).map(|frame| frame.iter().any(|s| *s != 0)).collect();
+    assert_eq!(beeping.len(), 20);
+    assert!(beeping[..10].iter().all(|b| *b));
+    assert!(beeping[10..].iter().all(|b| !*b));
+    Ok(())
+}
+
+#[test]
+fn test_run_debugger() -> Result<(), String> {
+    let options = RunOptions { paced: false, max_frames: Some(5), ..RunOptions::default() };
+
+    // The debugger pauses before the 3rd frame, steps twice and continues
+    let mut chip8 = Chip8::new(Quirks::default());
+    chip8.load_rom(&KEY_COUNTER_ROM)?;
+    let mut input = ScriptedInput::default();
+    input.push_at(2, InputEvent::Debug);
+    let mut console = ScriptedConsole::new(&["s", "", "c"]);
+    let mut debugger = Debugger::new(&mut console);
+    run_with_debugger(&mut chip8, &mut NullVideo, &mut NullAudio::default(), &mut input, &options, Some(&mut debugger)).unwrap();
+    assert_eq!(chip8.get_frame_count(), 5);
+    assert_eq!(console.output.matches("Paused at ").count(), 1);
+    assert_eq!(console.output.matches("Stepped at ").count(), 2);
+
+    // Pausing does not change the result of the frames
+    let mut expected = Chip8::new(Quirks::default());
+    expected.set_seed(chip8.get_seed());
+    expected.load_rom(&KEY_COUNTER_ROM)?;
+    run(&mut expected, &mut NullVideo, &mut NullAudio::default(), &mut NullInput, &options).unwrap();
+    assert_eq!(chip8.get_machine_state(), expected.get_machine_state());
+
+    // Quitting from the prompt ends the loop before the first instruction
+    let mut chip8 = Chip8::new(Quirks::default());
+    chip8.load_rom(&KEY_COUNTER_ROM)?;
+    let mut console = ScriptedConsole::new(&["q"]);
+    let mut debugger = Debugger::new(&mut console);
+    debugger.pause();
+    run_with_debugger(&mut chip8, &mut NullVideo, &mut NullAudio::default(), &mut NullInput, &options, Some(&mut debugger)).unwrap();
+    assert_eq!(chip8.get_frame_count(), 0);
+
+    // Without a debugger, the hotkey only shows a message
+    let mut video = MemoryVideo::default();
+    let mut input = ScriptedInput::new(&[InputEvent::Debug]);
+    run(&mut chip8, &mut video, &mut NullAudio::default(), &mut input, &options).unwrap();
+    assert!(video.title.ends_with("Debugger is disabled"));
+    Ok(())
+}
+
+#[test]
+fn test_run_trace() -> Result<(), String> {
+    let path = std::env::temp_dir().join(format!("chip8_frontend_test_{}.trace", std::process::id()));
+    let path = path.to_string_lossy().into_owned();
+    let options = RunOptions { trace_path: Some(path.clone()), paced: false, max_frames: Some(5), ..RunOptions::default() };
+
+    let mut chip8 = Chip8::new(Quirks::default());
+    chip8.load_rom(&[
+        0x60, 0x0A, // V0 = 10
+        0x12, 0x02, // Jumps to itself forever
+    ])?;
+    run(&mut chip8, &mut NullVideo, &mut NullAudio::default(), &mut NullInput, &options).unwrap();
+    let trace = std::fs::read_to_string(&path).map_err(|e| e.to_string())?;
+    assert_eq!(trace.lines().count(), 5 * DEFAULT_CYCLES_PER_FRAME as usize);
+    assert!(trace.lines().nth(1).unwrap().starts_with("00000001 0202 1202 JP 0x202"));
+
+    // Instructions the debugger pauses on are only traced once, when they run
+    let mut chip8 = Chip8::new(Quirks::default());
+    chip8.load_rom(&KEY_COUNTER_ROM)?;
+    let mut input = ScriptedInput::default();
+    input.push_at(2, InputEvent::Debug);
+    let mut console = ScriptedConsole::new(&["s", "", "c"]);
+    let mut debugger = Debugger::new(&mut console);
+    run_with_debugger(&mut chip8, &mut NullVideo, &mut NullAudio::default(), &mut input, &options, Some(&mut debugger)).unwrap();
+    let debugged = std::fs::read_to_string(&path).map_err(|e| e.to_string())?;
+
+    let mut expected = Chip8::new(Quirks::default());
+    expected.set_seed(chip8.get_seed());
+    expected.load_rom(&KEY_COUNTER_ROM)?;
+    run(&mut expected, &mut NullVideo, &mut NullAudio::default(), &mut NullInput, &options).unwrap();
+    assert_eq!(debugged, std::fs::read_to_string(&path).map_err(|e| e.to_string())?);
+
+    // Only the window of frames is traced, with the cycles counted from the start
+    let filter = TraceFilter { addresses: None, frames: Some((2, 3)) };
+    let options = RunOptions { trace_filter: filter, ..options };
+    let mut chip8 = Chip8::new(Quirks::default());
+    chip8.load_rom(&KEY_COUNTER_ROM)?;
+    run(&mut chip8, &mut NullVideo, &mut NullAudio::default(), &mut NullInput, &options).unwrap();
+    let trace = std::fs::read_to_string(&path).map_err(|e| e.to_string())?;
+    std::fs::remove_file(&path).map_err(|e| e.to_string())?;
+    assert_eq!(trace.lines().count(), 2 * DEFAULT_CYCLES_PER_FRAME as usize);
+    assert!(trace.starts_with(&format!("{:08} ", 2 * DEFAULT_CYCLES_PER_FRAME)));
+    Ok(())
+}
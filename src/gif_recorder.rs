@@ -0,0 +1,124 @@
+use std::io::Write;
+
+use crate::chip8::FRAME_RATE;
+use crate::screen::Palette;
+
+/* Records the presented frames as an animated GIF.
+ *
+ * GIF frame delays are in hundredths of a second, and most viewers play delays below 2 as 10,
+ * so the GIF runs at 50 frames per second: each frame lasts a multiple of 2 hundredths, rounded
+ * so the total stays in sync, and a frame shown for less than that is dropped (1 in 6 frames
+ * when every frame changes). Identical consecutive frames are merged into one longer frame,
+ * so a still screen only takes a few bytes */
+pub struct GifRecorder<W: Write> {
+    encoder: gif::Encoder<W>,
+
+    // Size of the GIF in CHIP-8 pixels, which is the size of the display when the recording started
+    width: usize,
+    height: usize,
+    scale: usize,
+
+    // Last captured frame, written once the next different frame arrives, and the frame it started on
+    pending: Option<(Vec<u8>, u64)>,
+
+    // How many frames have been captured
+    frames: u64,
+}
+
+impl<W: Write> GifRecorder<W> {
+
+    // Starts a GIF of the chosen display size, scaled up by the chosen factor and drawn with the colors of the palette
+    pub fn new(writer: W, width: usize, height: usize, scale: u32, palette: &Palette) -> Result<Self, String> {
+        let scale = scale.max(1) as usize;
+        let colors: Vec<u8> = (0..4).flat_map(|pixel| palette.get_color(pixel)).collect();
+
+        let mut encoder = gif::Encoder::new(writer, (width * scale) as u16, (height * scale) as u16, &colors)
+            .map_err(|e| e.to_string())?;
+        encoder.set_repeat(gif::Repeat::Infinite).map_err(|e| e.to_string())?;
+
+        Ok(Self {
+            encoder,
+            width,
+            height,
+            scale,
+            pending: None,
+            frames: 0,
+        })
+    }
+
+    /* Captures a presented display. A display of another size than the GIF, after the resolution
+     * changed while recording, is stretched or shrunk to fit */
+    pub fn capture(&mut self, display: &[u8], width: usize, height: usize) -> Result<(), String> {
+        let (gif_width, gif_height) = (self.width, self.height);
+        let frame: Vec<u8> = (0..gif_height)
+            .flat_map(|y| (0..gif_width).map(move |x| (y * height / gif_height) * width + x * width / gif_width))
+            .map(|idx| display.get(idx).map_or(0, |pixel| pixel & 0x3))
+            .collect();
+
+        match &self.pending {
+            Some((pending, _)) if *pending == frame => (),
+            _ => {
+                self.flush(false)?;
+                self.pending = Some((frame, self.frames));
+            },
+        }
+        self.frames += 1;
+        Ok(())
+    }
+
+    // Writes the last captured frame and the end of the GIF, returning the writer
+    pub fn finish(mut self) -> Result<W, String> {
+        self.flush(true)?;
+        self.encoder.into_inner().map_err(|e| e.to_string())
+    }
+
+    // Returns how many frames have been captured
+    pub fn get_frame_count(&self) -> u64 {
+        self.frames
+    }
+
+    /* Writes the pending frame, lasting until the current frame. A frame too short to be shown is
+     * dropped, unless it is the last one, which is shown for the shortest delay */
+    fn flush(&mut self, last: bool) -> Result<(), String> {
+        let (pixels, start) = match self.pending.take() {
+            Some(pending) => pending,
+            None => return Ok(()),
+        };
+        let delay = match frame_delay(start, self.frames) {
+            0 if last => MIN_FRAME_DELAY,
+            0 => return Ok(()),
+            delay => delay,
+        };
+
+        let mut buffer = Vec::with_capacity(pixels.len() * self.scale * self.scale);
+        for row in pixels.chunks(self.width.max(1)) {
+            let scaled_row: Vec<u8> = row.iter().flat_map(|pixel| std::iter::repeat_n(*pixel, self.scale)).collect();
+            for _ in 0..self.scale {
+                buffer.extend_from_slice(&scaled_row);
+            }
+        }
+
+        let frame = gif::Frame {
+            width: (self.width * self.scale) as u16,
+            height: (self.height * self.scale) as u16,
+            delay: delay.min(u16::MAX as u64) as u16,
+            buffer: buffer.into(),
+            ..gif::Frame::default()
+        };
+        self.encoder.write_frame(&frame).map_err(|e| e.to_string())
+    }
+}
+
+// Shortest GIF frame delay in hundredths of a second that viewers play as it is written
+pub const MIN_FRAME_DELAY: u64 = 2;
+
+/* Returns the delay in hundredths of a second of a GIF frame lasting from the start frame until
+ * the end frame, a multiple of the shortest delay, or 0 if the frame is too short to be shown */
+pub fn frame_delay(start: u64, end: u64) -> u64 {
+    let ticks = |frame: u64| frame * 100 / (MIN_FRAME_DELAY * FRAME_RATE as u64);
+    (ticks(end) - ticks(start)) * MIN_FRAME_DELAY
+}
+
+#[cfg(test)]
+#[path ="./gif_recorder_test.rs"]
+mod gif_recorder_test;
@@ -0,0 +1,63 @@
+use super::*;
+
+// Delay and indexed pixels of a GIF frame
+type Frame = (u16, Vec<u8>);
+
+// Decodes a GIF, returning its size and every frame
+fn decode(data: &[u8]) -> Result<((u16, u16), Vec<Frame>), String> {
+    let mut options = gif::DecodeOptions::new();
+    options.set_color_output(gif::ColorOutput::Indexed);
+    let mut decoder = options.read_info(data).map_err(|e| e.to_string())?;
+
+    let size = (decoder.width(), decoder.height());
+    let mut frames = Vec::new();
+    while let Some(frame) = decoder.read_next_frame().map_err(|e| e.to_string())? {
+        frames.push((frame.delay, frame.buffer.to_vec()));
+    }
+    Ok((size, frames))
+}
+
+#[test]
+fn test_frame_delay() {
+    assert_eq!(frame_delay(0, 1), 0);
+    assert_eq!(frame_delay(1, 2), 2);
+    assert_eq!(frame_delay(2, 3), 2);
+    assert_eq!(frame_delay(0, 3), 4);
+    assert_eq!(frame_delay(0, 60), 100);
+    assert_eq!(frame_delay(59, 60), 2);
+}
+
+#[test]
+fn test_gif_recorder_delays() -> Result<(), String> {
+    // Every frame changes, so frames too short to be shown are dropped
+    let mut recorder = GifRecorder::new(Vec::new(), 1, 1, 1, &Palette::default())?;
+    for frame in 0..120 {
+        recorder.capture(&[frame % 2], 1, 1)?;
+    }
+
+    let (_, frames) = decode(&recorder.finish()?)?;
+    assert_eq!(frames.len(), 100);
+    assert!(frames.iter().all(|(delay, _)| *delay as u64 >= MIN_FRAME_DELAY));
+    assert_eq!(frames.iter().map(|(delay, _)| *delay as u64).sum::<u64>(), 200);
+    Ok(())
+}
+
+#[test]
+fn test_gif_recorder() -> Result<(), String> {
+    let mut recorder = GifRecorder::new(Vec::new(), 2, 1, 2, &Palette::default())?;
+    recorder.capture(&[0, 1], 2, 1)?;
+    recorder.capture(&[0, 1], 2, 1)?;
+    recorder.capture(&[0, 1], 2, 1)?;
+    recorder.capture(&[3, 2], 2, 1)?;
+    recorder.capture(&[1, 1, 0, 0, 1, 1, 0, 0], 4, 2)?; // A larger display is shrunk
+    assert_eq!(recorder.get_frame_count(), 5);
+
+    let (size, frames) = decode(&recorder.finish()?)?;
+    assert_eq!(size, (4, 2));
+    assert_eq!(frames, [
+        (4, vec![0, 0, 1, 1, 0, 0, 1, 1]), // 3 identical frames merged into one
+        (2, vec![3, 3, 2, 2, 3, 3, 2, 2]),
+        (2, vec![1, 1, 0, 0, 1, 1, 0, 0]),
+    ]);
+    Ok(())
+}
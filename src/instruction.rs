@@ -0,0 +1,253 @@
+use crate::error::DecodeError;
+
+/* CHIP-8, SUPER-CHIP and XO-CHIP instructions, decoded from their opcodes.
+ *
+ * X and Y are indexes of the Vx and Vy registers, N is a 4-bit value, NN a byte and NNN a 12-bit address.
+ * Decoding does not depend on the machine state, so an instruction can be decoded once and
+ * executed many times, or decoded without being executed by a disassembler or debugger */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Instruction {
+
+    // 00CN: Scrolls the display down by N pixels
+    ScrollDown { n: u8 },
+
+    // 00DN: Scrolls the display up by N pixels
+    ScrollUp { n: u8 },
+
+    // 00E0: Clears the display
+    ClearScreen,
+
+    // 00EE: Returns from a subroutine
+    Return,
+
+    // 00FB: Scrolls the display right by 4 pixels
+    ScrollRight,
+
+    // 00FC: Scrolls the display left by 4 pixels
+    ScrollLeft,
+
+    // 00FD: Exits the interpreter
+    Exit,
+
+    // 00FE: Switches to the low-resolution mode (64x32)
+    LowRes,
+
+    // 00FF: Switches to the high-resolution mode (128x64)
+    HighRes,
+
+    // 1NNN: Jumps to address NNN
+    Jump { nnn: u16 },
+
+    // 2NNN: Calls the subroutine at address NNN
+    Call { nnn: u16 },
+
+    // 3XNN: Skips the next instruction if Vx equals NN
+    SkipIfEqualByte { x: u8, nn: u8 },
+
+    // 4XNN: Skips the next instruction if Vx does not equal NN
+    SkipIfNotEqualByte { x: u8, nn: u8 },
+
+    // 5XY0: Skips the next instruction if Vx equals Vy
+    SkipIfEqual { x: u8, y: u8 },
+
+    // 5XY2: Stores registers Vx through Vy in memory starting at location I
+    StoreRange { x: u8, y: u8 },
+
+    // 5XY3: Reads registers Vx through Vy from memory starting at location I
+    LoadRange { x: u8, y: u8 },
+
+    // 6XNN: Sets Vx to NN
+    SetByte { x: u8, nn: u8 },
+
+    // 7XNN: Adds NN to Vx, without changing the carry flag
+    AddByte { x: u8, nn: u8 },
+
+    // 8XY0: Sets Vx to Vy
+    Set { x: u8, y: u8 },
+
+    // 8XY1: Sets Vx to Vx OR Vy
+    Or { x: u8, y: u8 },
+
+    // 8XY2: Sets Vx to Vx AND Vy
+    And { x: u8, y: u8 },
+
+    // 8XY3: Sets Vx to Vx XOR Vy
+    Xor { x: u8, y: u8 },
+
+    // 8XY4: Adds Vy to Vx, setting VF to the carry
+    Add { x: u8, y: u8 },
+
+    // 8XY5: Subtracts Vy from Vx, setting VF to NOT borrow
+    Sub { x: u8, y: u8 },
+
+    // 8XY6: Shifts Vx (or Vy, depending on the quirks) right by one, setting VF to the shifted out bit
+    ShiftRight { x: u8, y: u8 },
+
+    // 8XY7: Sets Vx to Vy minus Vx, setting VF to NOT borrow
+    SubReverse { x: u8, y: u8 },
+
+    // 8XYE: Shifts Vx (or Vy, depending on the quirks) left by one, setting VF to the shifted out bit
+    ShiftLeft { x: u8, y: u8 },
+
+    // 9XY0: Skips the next instruction if Vx does not equal Vy
+    SkipIfNotEqual { x: u8, y: u8 },
+
+    // ANNN: Sets I to address NNN
+    SetIndex { nnn: u16 },
+
+    // BNNN: Jumps to address NNN plus V0 (or Vx, depending on the quirks)
+    JumpOffset { x: u8, nnn: u16 },
+
+    // CXNN: Sets Vx to a random number AND NN
+    Random { x: u8, nn: u8 },
+
+    // DXYN: Draws an N rows tall sprite at (Vx, Vy), setting VF on collision
+    Draw { x: u8, y: u8, n: u8 },
+
+    // EX9E: Skips the next instruction if the key Vx is pressed
+    SkipIfKey { x: u8 },
+
+    // EXA1: Skips the next instruction if the key Vx is not pressed
+    SkipIfNotKey { x: u8 },
+
+    // F000 NNNN: Sets I to the 16-bit address stored in the two bytes after the opcode
+    SetIndexLong,
+
+    // FN01: Selects the bitplanes N (0 to 3)
+    SelectPlanes { n: u8 },
+
+    // F002: Loads the 16 bytes at location I into the audio pattern buffer
+    LoadAudioPattern,
+
+    // FX07: Sets Vx to the delay timer
+    GetDelayTimer { x: u8 },
+
+    // FX0A: Waits for a key press, storing the key in Vx
+    WaitKey { x: u8 },
+
+    // FX15: Sets the delay timer to Vx
+    SetDelayTimer { x: u8 },
+
+    // FX18: Sets the sound timer to Vx
+    SetSoundTimer { x: u8 },
+
+    // FX1E: Adds Vx to I
+    AddIndex { x: u8 },
+
+    // FX29: Sets I to the small font character of Vx
+    SetIndexFont { x: u8 },
+
+    // FX30: Sets I to the big font character of Vx
+    SetIndexBigFont { x: u8 },
+
+    // FX33: Stores the binary-coded decimal representation of Vx at locations I, I+1 and I+2
+    StoreBcd { x: u8 },
+
+    // FX3A: Sets the pitch register to Vx
+    SetPitch { x: u8 },
+
+    // FX55: Stores registers V0 through Vx in memory starting at location I
+    Store { x: u8 },
+
+    // FX65: Reads registers V0 through Vx from memory starting at location I
+    Load { x: u8 },
+
+    // FX75: Saves registers V0 through Vx to the RPL user flags
+    SaveFlags { x: u8 },
+
+    // FX85: Loads registers V0 through Vx from the RPL user flags
+    LoadFlags { x: u8 },
+}
+
+impl Instruction {
+
+    // Decodes an opcode, failing if it does not match any instruction
+    pub fn decode(opcode: u16) -> Result<Instruction, DecodeError> {
+
+        // The fourth nibble of the instruction (lowest 4 bits)
+        let n = (opcode & 0x000F) as u8;
+        // The second byte of the instruction (lowest 8 bits)
+        let nn = (opcode & 0x00FF) as u8;
+        // The second, third and fourth nibbles of the instruction (lowest 12 bits)
+        let nnn = opcode & 0x0FFF;
+
+        // The second nibble. Used as index for one of the 16 registers (Vx)
+        let x = ((opcode & 0x0F00) >> 8) as u8;
+
+        // The third nibble. Used as index for one of the 16 registers (Vy)
+        let y = ((opcode & 0x00F0) >> 4) as u8;
+
+        // Dividing opcode in nibbles to select the instruction based on them
+        let op1 = ((opcode & 0xF000) >> 12) as u8;
+        let op2 = ((opcode & 0x0F00) >> 8) as u8;
+        let op3 = ((opcode & 0x00F0) >> 4) as u8;
+        let op4 = (opcode & 0x000F) as u8;
+
+        let instruction = match (op1, op2, op3, op4) {
+            (0x0, 0x0, 0xC, _) => Instruction::ScrollDown { n },
+            (0x0, 0x0, 0xD, _) => Instruction::ScrollUp { n },
+            (0x0, 0x0, 0xE, 0x0) => Instruction::ClearScreen,
+            (0x0, 0x0, 0xE, 0xE) => Instruction::Return,
+            (0x0, 0x0, 0xF, 0xB) => Instruction::ScrollRight,
+            (0x0, 0x0, 0xF, 0xC) => Instruction::ScrollLeft,
+            (0x0, 0x0, 0xF, 0xD) => Instruction::Exit,
+            (0x0, 0x0, 0xF, 0xE) => Instruction::LowRes,
+            (0x0, 0x0, 0xF, 0xF) => Instruction::HighRes,
+            (0x1, _, _, _) => Instruction::Jump { nnn },
+            (0x2, _, _, _) => Instruction::Call { nnn },
+            (0x3, _, _, _) => Instruction::SkipIfEqualByte { x, nn },
+            (0x4, _, _, _) => Instruction::SkipIfNotEqualByte { x, nn },
+            (0x5, _, _, 0x0) => Instruction::SkipIfEqual { x, y },
+            (0x5, _, _, 0x2) => Instruction::StoreRange { x, y },
+            (0x5, _, _, 0x3) => Instruction::LoadRange { x, y },
+            (0x6, _, _, _) => Instruction::SetByte { x, nn },
+            (0x7, _, _, _) => Instruction::AddByte { x, nn },
+            (0x8, _, _, 0x0) => Instruction::Set { x, y },
+            (0x8, _, _, 0x1) => Instruction::Or { x, y },
+            (0x8, _, _, 0x2) => Instruction::And { x, y },
+            (0x8, _, _, 0x3) => Instruction::Xor { x, y },
+            (0x8, _, _, 0x4) => Instruction::Add { x, y },
+            (0x8, _, _, 0x5) => Instruction::Sub { x, y },
+            (0x8, _, _, 0x6) => Instruction::ShiftRight { x, y },
+            (0x8, _, _, 0x7) => Instruction::SubReverse { x, y },
+            (0x8, _, _, 0xE) => Instruction::ShiftLeft { x, y },
+            (0x9, _, _, 0x0) => Instruction::SkipIfNotEqual { x, y },
+            (0xA, _, _, _) => Instruction::SetIndex { nnn },
+            (0xB, _, _, _) => Instruction::JumpOffset { x, nnn },
+            (0xC, _, _, _) => Instruction::Random { x, nn },
+            (0xD, _, _, _) => Instruction::Draw { x, y, n },
+            (0xE, _, 0x9, 0xE) => Instruction::SkipIfKey { x },
+            (0xE, _, 0xA, 0x1) => Instruction::SkipIfNotKey { x },
+            (0xF, 0x0, 0x0, 0x0) => Instruction::SetIndexLong,
+            (0xF, _, 0x0, 0x1) => Instruction::SelectPlanes { n: x },
+            (0xF, 0x0, 0x0, 0x2) => Instruction::LoadAudioPattern,
+            (0xF, _, 0x0, 0x7) => Instruction::GetDelayTimer { x },
+            (0xF, _, 0x0, 0xA) => Instruction::WaitKey { x },
+            (0xF, _, 0x1, 0x5) => Instruction::SetDelayTimer { x },
+            (0xF, _, 0x1, 0x8) => Instruction::SetSoundTimer { x },
+            (0xF, _, 0x1, 0xE) => Instruction::AddIndex { x },
+            (0xF, _, 0x2, 0x9) => Instruction::SetIndexFont { x },
+            (0xF, _, 0x3, 0x0) => Instruction::SetIndexBigFont { x },
+            (0xF, _, 0x3, 0x3) => Instruction::StoreBcd { x },
+            (0xF, _, 0x3, 0xA) => Instruction::SetPitch { x },
+            (0xF, _, 0x5, 0x5) => Instruction::Store { x },
+            (0xF, _, 0x6, 0x5) => Instruction::Load { x },
+            (0xF, _, 0x7, 0x5) => Instruction::SaveFlags { x },
+            (0xF, _, 0x8, 0x5) => Instruction::LoadFlags { x },
+            _ => return Err(DecodeError { opcode }),
+        };
+        Ok(instruction)
+    }
+
+    // Returns the length of the instruction in bytes, which is 4 for F000 NNNN and 2 for every other one
+    pub fn get_length(&self) -> u16 {
+        match self {
+            Instruction::SetIndexLong => 4,
+            _ => 2,
+        }
+    }
+}
+
+#[cfg(test)]
+#[path ="./instruction_test.rs"]
+mod instruction_test;
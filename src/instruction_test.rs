@@ -0,0 +1,33 @@
+use super::*;
+
+#[test]
+fn test_decode() {
+    assert_eq!(Instruction::decode(0x00C5), Ok(Instruction::ScrollDown { n: 5 }));
+    assert_eq!(Instruction::decode(0x00E0), Ok(Instruction::ClearScreen));
+    assert_eq!(Instruction::decode(0x00EE), Ok(Instruction::Return));
+    assert_eq!(Instruction::decode(0x1ABC), Ok(Instruction::Jump { nnn: 0xABC }));
+    assert_eq!(Instruction::decode(0x3A42), Ok(Instruction::SkipIfEqualByte { x: 0xA, nn: 0x42 }));
+    assert_eq!(Instruction::decode(0x5123), Ok(Instruction::LoadRange { x: 1, y: 2 }));
+    assert_eq!(Instruction::decode(0x8AB6), Ok(Instruction::ShiftRight { x: 0xA, y: 0xB }));
+    assert_eq!(Instruction::decode(0xB234), Ok(Instruction::JumpOffset { x: 2, nnn: 0x234 }));
+    assert_eq!(Instruction::decode(0xD12F), Ok(Instruction::Draw { x: 1, y: 2, n: 0xF }));
+    assert_eq!(Instruction::decode(0xE5A1), Ok(Instruction::SkipIfNotKey { x: 5 }));
+    assert_eq!(Instruction::decode(0xF000), Ok(Instruction::SetIndexLong));
+    assert_eq!(Instruction::decode(0xF301), Ok(Instruction::SelectPlanes { n: 3 }));
+    assert_eq!(Instruction::decode(0xF002), Ok(Instruction::LoadAudioPattern));
+    assert_eq!(Instruction::decode(0xF785), Ok(Instruction::LoadFlags { x: 7 }));
+}
+
+#[test]
+fn test_decode_unknown() {
+    for opcode in [0x0000, 0x00E1, 0x5121, 0x8008, 0x9001, 0xE000, 0xF102, 0xFFFF] {
+        assert_eq!(Instruction::decode(opcode), Err(DecodeError { opcode }));
+    }
+}
+
+#[test]
+fn test_length() {
+    assert_eq!(Instruction::SetIndexLong.get_length(), 4);
+    assert_eq!(Instruction::ClearScreen.get_length(), 2);
+    assert_eq!(Instruction::Jump { nnn: 0x200 }.get_length(), 2);
+}
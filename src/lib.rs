@@ -0,0 +1,32 @@
+/* CHIP-8 emulator core.
+ *
+ * The core has no dependency on SDL2, so it can be used by other tools and frontends.
+ * The SDL2 frontend lives in the chip-8 binary, behind the "sdl" feature. */
+
+pub mod audio;
+pub mod chip8;
+pub mod dap;
+pub mod cpu;
+pub mod debugger;
+pub mod disasm;
+pub mod error;
+pub mod expression;
+pub mod frontend;
+pub mod gif_recorder;
+pub mod instruction;
+pub mod movie;
+pub mod octo;
+pub mod quirks;
+pub mod rewind;
+pub mod scheduler;
+pub mod screen;
+pub mod state;
+pub mod trace;
+pub mod watchpoint;
+pub mod wav_recorder;
+
+pub use chip8::{Chip8, DEFAULT_CYCLES_PER_FRAME, FRAME_RATE};
+pub use cpu::{Cpu, Register, DISPLAY_HEIGHT, DISPLAY_WIDTH, HIRES_DISPLAY_HEIGHT, HIRES_DISPLAY_WIDTH};
+pub use error::{CpuError, DecodeError, RunError, StateError};
+pub use instruction::Instruction;
+pub use quirks::{LoadStoreIncrement, Quirks};
@@ -1,68 +1,214 @@
-mod chip8;
-mod cpu;
+#[cfg(feature = "sdl")]
 mod drivers;
 mod args;
 
-use chip8::Chip8;
-use drivers::{DisplayDriver, KeypadDriver, AudioDriver};
+use chip_8::{frontend, screen, Chip8, RunError};
+use chip_8::frontend::{NullAudio, NullVideo, RunOptions, ScriptedInput};
+use chip_8::dap::{DapConnection, DapServer};
+use chip_8::debugger::{DebugHook, Debugger, StdConsole};
+use chip_8::disasm::Disassembly;
+use chip_8::movie::Movie;
+use chip_8::screen::Palette;
+use chip_8::trace::TraceFilter;
 
-const CHIP8_RATE: u64 = 1851;
-
-use args::Chip8Args;
+use args::{Chip8Args, Command, DisasmArgs, RunArgs};
 use clap::Parser;
 
 fn main() -> Result<(), String> {
     let args = Chip8Args::parse();
 
+    match args.command {
+        Some(Command::Run(run_args)) => run(*run_args),
+        Some(Command::Disasm(disasm_args)) => disasm(disasm_args),
+        None => run(args.run),
+    }
+}
+
+// Runs a ROM in a window, or headless
+fn run(args: RunArgs) -> Result<(), String> {
+    if let Some(port) = args.dap {
+        return run_dap(args, port);
+    }
+    let (mut chip8, options) = load(&args)?;
+
+    // The debugger reads commands from the terminal, pausing before the first instruction with --debug
+    let mut console = StdConsole;
+    let mut debugger = Debugger::new(&mut console);
+    if args.debug {
+        debugger.pause();
+    }
+
+    let result = run_chip8(&mut chip8, options, &args, &mut debugger)?;
+    finish(&chip8, result)
+}
+
+/* Waits for a Debug Adapter Protocol client, then runs the ROM it launches, letting it
+ * debug CHIP-8. The ROM given on the command line is run if the client does not choose one */
+fn run_dap(mut args: RunArgs, port: u16) -> Result<(), String> {
+    eprintln!("Waiting for a debug adapter client on port {}", port);
+    let connection = DapConnection::listen(port)?;
+    let mut console = connection.console();
+    let mut server = DapServer::new(connection, &mut console);
+
+    let (mut chip8, options) = server.launch(|program| {
+        args.rom = program.or(args.rom.take());
+        load(&args)
+    })?;
+
+    let result = run_chip8(&mut chip8, options, &args, &mut server)?;
+    server.exit(&mut chip8, &result);
+    if let Some(e) = server.get_connection_error() {
+        return Err(e.to_string());
+    }
+    result.map_err(|e| e.to_string())
+}
+
+// Creates CHIP-8 with the ROM, quirks, seed and state of the arguments, and the options of the main loop
+#[allow(clippy::needless_late_init)]
+fn load(args: &RunArgs) -> Result<(Chip8, RunOptions), String> {
+    let rom_path = args.rom.clone().unwrap_or_default();
+
     // Reading ROM file
     let rom_data;
-    match std::fs::read(&args.rom) {
+    match std::fs::read(&rom_path) {
         Ok(data) => rom_data = data,
+        Err(_e) => return Err(format!(".ch8 file could not be found or read on path '{}'", &rom_path)),
+    };
+
+    // A replayed movie brings its own seed, speed and quirks
+    let replay = match &args.replay {
+        Some(path) => {
+            let text = std::fs::read_to_string(path).map_err(|e| format!("Input movie could not be read on path '{}': {}", path, e))?;
+            let movie: Movie = text.parse()?;
+            movie.check_rom(chip_8::state::rom_hash(&rom_data))?;
+            Some(movie)
+        },
+        None => None,
+    };
+    let (quirks, seed, speed) = match &replay {
+        Some(movie) => (movie.quirks, Some(movie.seed), movie.cycles_per_frame),
+        None => (args.quirks, args.seed, args.speed),
+    };
+
+    // The seed is logged so a run with a random seed can be reproduced later
+    let seed = seed.unwrap_or_else(rand::random);
+    eprintln!("Random seed: {} (use --seed {} to reproduce this run)", seed, seed);
+
+    let mut chip8 = Chip8::new(quirks);
+    chip8.set_seed(seed);
+    chip8.load_rom(&rom_data)?;
+
+    // Restoring the chosen save state, which should have been taken from the same ROM
+    if let Some(path) = &args.load_state {
+        let state = std::fs::read(path).map_err(|e| format!("Save state could not be read on path '{}': {}", path, e))?;
+        chip8.load_state(&state).map_err(|e| format!("{} ('{}')", e, path))?;
+    }
+
+    // Save state slots and screenshots are stored next to the ROM
+    let options = RunOptions {
+        cycles_per_frame: speed,
+        state_path: Some(rom_path.clone()),
+        capture_path: Some(rom_path),
+        capture_scale: args.scale.unwrap_or(screen::DEFAULT_CAPTURE_SCALE),
+        wav_path: args.record_wav.clone(),
+        record_path: args.record.clone(),
+        replay,
+        trace_path: args.trace.clone(),
+        trace_filter: TraceFilter { addresses: args.trace_range, frames: args.trace_frames },
+        ..RunOptions::default()
+    };
+
+    Ok((chip8, options))
+}
+
+/* Runs CHIP-8 in a window, or headless, returning how the main loop ended.
+ * The error is only returned if the frontend could not be started */
+fn run_chip8(chip8: &mut Chip8, options: RunOptions, args: &RunArgs, debugger: &mut dyn DebugHook) -> Result<Result<(), RunError>, String> {
+    if args.headless {
+        run_headless(chip8, options, args, debugger)
+    } else {
+        run_window(chip8, options, debugger)
+    }
+}
+
+// Prints the instructions and data of a ROM
+fn disasm(args: DisasmArgs) -> Result<(), String> {
+    let rom_data = match std::fs::read(&args.rom) {
+        Ok(data) => data,
         Err(_e) => return Err(format!(".ch8 file could not be found or read on path '{}'", &args.rom)),
     };
 
+    print!("{}", Disassembly::new(&rom_data).to_listing(args.syntax));
+    Ok(())
+}
+
+// Runs CHIP-8 in a SDL2 window
+#[cfg(feature = "sdl")]
+fn run_window(chip8: &mut Chip8, options: RunOptions, debugger: &mut dyn DebugHook) -> Result<Result<(), RunError>, String> {
+    use drivers::{DisplayDriver, KeypadDriver, AudioDriver};
+
     // Initiating drivers
     let sdl_context = sdl2::init()?;
     let mut display_driver = DisplayDriver::new(&sdl_context, None, None)?;
     let mut keypad_driver = KeypadDriver::new(&sdl_context)?;
-    let audio_driver = AudioDriver::new(&sdl_context)?;
-    
+    let mut audio_driver = AudioDriver::new(&sdl_context)?;
 
-    let mut chip8 = Chip8::new();
-    chip8.load_rom(&rom_data)?;
+    Ok(frontend::run_with_debugger(chip8, &mut display_driver, &mut audio_driver, &mut keypad_driver, &options, Some(debugger)))
+}
 
-    // Keep the CHIP-8 running as long as a quit event 'Err(())' has not been received
-    while let Ok(k) = keypad_driver.poll_event() {
-        
-        // Key press/release event
-        if let Some(k) = k {
-            if keypad_driver.key_pressed {
-                chip8.press_key(k);
-            } else {
-                chip8.release_key(k);
-            }
-        }
-        
-        // Ensures that CHIP-8 runs at a rate of 540Hz (1s / 540Hz = 1851 microseconds)
-        if chip8.tick_period.elapsed() >= std::time::Duration::from_micros(CHIP8_RATE) {
-            chip8.run();
-            
-            // Updates the display at a rate of 60Hz
-            if chip8.get_display_state() {
-                if let Err(c) = display_driver.draw_display(chip8.get_display()) {
-                    return Err(c);
-                }
-            }
-            
-            // Beeps at a rate of 60Hz
-            if chip8.get_beep_state() {
-                audio_driver.start_beep();
+#[cfg(not(feature = "sdl"))]
+fn run_window(_chip8: &mut Chip8, _options: RunOptions, _debugger: &mut dyn DebugHook) -> Result<Result<(), RunError>, String> {
+    Err(String::from("This build has no window, since it was built without the \"sdl\" feature. Use --headless instead"))
+}
+
+/* Runs CHIP-8 without any driver for the chosen number of frames, as fast as possible, then
+ * prints the display or writes it to a file. The display is shown even if the CPU stops with an error */
+fn run_headless(chip8: &mut Chip8, options: RunOptions, args: &RunArgs, debugger: &mut dyn DebugHook) -> Result<Result<(), RunError>, String> {
+    let options = RunOptions {
+        state_path: None,
+        capture_path: None,
+        gif_path: args.record_gif.clone(),
+        paced: false,
+        max_frames: args.frames,
+        rewind: false,
+        ..options
+    };
+
+    let mut input = ScriptedInput::default();
+    for press in &args.keys {
+        press.push_to(&mut input);
+    }
+
+    let result = frontend::run_with_debugger(chip8, &mut NullVideo, &mut NullAudio::default(), &mut input, &options, Some(debugger));
+
+    let (width, height) = chip8.get_display_size();
+    match &args.output {
+        None => print!("{}", screen::to_ascii(chip8.get_display(), width)),
+        Some(path) => {
+            let data = if path.ends_with(".txt") {
+                screen::to_ascii(chip8.get_display(), width).into_bytes()
+            } else if path.ends_with(".pgm") {
+                screen::to_pgm(chip8.get_display(), width, height, args.scale.unwrap_or(1) as usize)
+            } else if path.ends_with(".png") {
+                chip8.screenshot_png(args.scale.unwrap_or(1), &Palette::default())?
             } else {
-                audio_driver.stop_beep();
-            }
+                return Err(format!("Unknown output format of '{}' (expected a .txt, .pgm or .png file)", path));
+            };
+            std::fs::write(path, data).map_err(|e| format!("Display could not be written on path '{}': {}", path, e))?;
+        },
+    }
 
-            chip8.tick_period = std::time::Instant::now();
-        }
+    Ok(result)
+}
+
+// Exits with an error code if the CPU stopped with an error, showing the machine state
+fn finish(chip8: &Chip8, result: Result<(), RunError>) -> Result<(), String> {
+    match result {
+        Ok(()) => Ok(()),
+        Err(RunError::Cpu(e)) => {
+            eprintln!("CHIP-8 stopped: {}\n\n{}", e, chip8.get_machine_state());
+            std::process::exit(1);
+        },
+        Err(RunError::Frontend(e)) => Err(e),
     }
-    Ok(())
-}
\ No newline at end of file
+}
@@ -0,0 +1,226 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::quirks::{LoadStoreIncrement, Quirks};
+
+// Input movies start with this line, followed by the format version
+pub const MOVIE_MAGIC: &str = "CHIP-8 MOVIE";
+
+// Version of the input movie format, which should be increased whenever the format changes
+pub const MOVIE_VERSION: u32 = 1;
+
+
+// Input events recorded in a movie
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MovieEvent {
+
+    // A keypad key was pressed
+    KeyDown(usize),
+
+    // A keypad key was released
+    KeyUp(usize),
+
+    // The number of instructions run per frame changed
+    Speed(u32),
+}
+
+
+/* Input movie: every input delivered to CHIP-8, keyed by the frame it was delivered before,
+ * along with everything else the emulation depends on (the ROM, the random seed, the speed and
+ * the quirks), so a play session can be replayed exactly.
+ *
+ * Movies are stored as text, one line per setting or event:
+ *
+ *   CHIP-8 MOVIE 1
+ *   rom 5A3D8C1F00E2B6A4
+ *   seed 1234
+ *   speed 9
+ *   quirks shift_uses_vy load_store_increments_i
+ *   120 down 5
+ *   131 up 5
+ *   400 speed 20
+ *   end 1800 */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Movie {
+    pub rom_hash: u64,
+    pub seed: u64,
+    pub cycles_per_frame: u32,
+    pub quirks: Quirks,
+
+    // Events in the order they were delivered, with the frame they were delivered before
+    pub events: Vec<(u64, MovieEvent)>,
+
+    // How many frames the movie lasts
+    pub length: u64,
+}
+
+impl Movie {
+
+    // Creates new movie without events, for the chosen ROM and settings
+    pub fn new(rom_hash: u64, seed: u64, cycles_per_frame: u32, quirks: Quirks) -> Self {
+        Self {
+            rom_hash,
+            seed,
+            cycles_per_frame,
+            quirks,
+            events: Vec::new(),
+            length: 0,
+        }
+    }
+
+    // Records an event delivered before the chosen frame
+    pub fn record(&mut self, frame: u64, event: MovieEvent) {
+        self.events.push((frame, event));
+        self.length = self.length.max(frame);
+    }
+
+    // Checks that the movie was recorded with the chosen ROM
+    pub fn check_rom(&self, rom_hash: u64) -> Result<(), String> {
+        if self.rom_hash == rom_hash {
+            Ok(())
+        } else {
+            Err(format!("Incompatible input movie: it was recorded with a different ROM (hash {:016X}, expected {:016X})", self.rom_hash, rom_hash))
+        }
+    }
+
+    // Returns the events delivered before the chosen frame
+    pub fn get_events(&self, frame: u64) -> impl Iterator<Item = MovieEvent> + '_ {
+        let start = self.events.partition_point(|(f, _)| *f < frame);
+        self.events[start..].iter().take_while(move |(f, _)| *f == frame).map(|(_, e)| *e)
+    }
+}
+
+// Names of the quirks turned on in a movie, in the order of the fields of Quirks
+fn quirk_names(quirks: &Quirks) -> impl Iterator<Item = &'static str> {
+    let load_store = match quirks.load_store_increment {
+        LoadStoreIncrement::None => None,
+        LoadStoreIncrement::X => Some("load_store_increments_i_by_x"),
+        LoadStoreIncrement::XPlusOne => Some("load_store_increments_i"),
+    };
+    [
+        quirks.shift_uses_vy.then_some("shift_uses_vy"),
+        load_store,
+        quirks.jump_uses_vx.then_some("jump_uses_vx"),
+        quirks.vf_reset.then_some("vf_reset"),
+        quirks.clip_sprites.then_some("clip_sprites"),
+        quirks.display_wait.then_some("display_wait"),
+        quirks.extended_memory.then_some("extended_memory"),
+    ].into_iter().flatten()
+}
+
+// Turns on the quirk of a movie with the chosen name, returning false if there is no such quirk
+fn set_quirk(quirks: &mut Quirks, name: &str) -> bool {
+    match name {
+        "shift_uses_vy" => quirks.shift_uses_vy = true,
+        "load_store_increments_i_by_x" => quirks.load_store_increment = LoadStoreIncrement::X,
+        "load_store_increments_i" => quirks.load_store_increment = LoadStoreIncrement::XPlusOne,
+        "jump_uses_vx" => quirks.jump_uses_vx = true,
+        "vf_reset" => quirks.vf_reset = true,
+        "clip_sprites" => quirks.clip_sprites = true,
+        "display_wait" => quirks.display_wait = true,
+        "extended_memory" => quirks.extended_memory = true,
+        _ => return false,
+    }
+    true
+}
+
+impl fmt::Display for Movie {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{} {}", MOVIE_MAGIC, MOVIE_VERSION)?;
+        writeln!(f, "rom {:016X}", self.rom_hash)?;
+        writeln!(f, "seed {}", self.seed)?;
+        writeln!(f, "speed {}", self.cycles_per_frame)?;
+
+        write!(f, "quirks")?;
+        for name in quirk_names(&self.quirks) {
+            write!(f, " {}", name)?;
+        }
+        writeln!(f)?;
+
+        for (frame, event) in &self.events {
+            match event {
+                MovieEvent::KeyDown(k) => writeln!(f, "{} down {:X}", frame, k)?,
+                MovieEvent::KeyUp(k) => writeln!(f, "{} up {:X}", frame, k)?,
+                MovieEvent::Speed(s) => writeln!(f, "{} speed {}", frame, s)?,
+            }
+        }
+        writeln!(f, "end {}", self.length)
+    }
+}
+
+impl FromStr for Movie {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut lines = s.lines().enumerate().map(|(idx, line)| (idx + 1, line.split_whitespace().collect::<Vec<_>>()));
+        let invalid = |line: usize| format!("Invalid input movie: line {} could not be read", line);
+
+        // The header has a fixed order
+        match lines.next() {
+            Some((_, words)) if words.len() == 3 && words[..2].join(" ") == MOVIE_MAGIC => {
+                if words[2] != MOVIE_VERSION.to_string() {
+                    return Err(format!("Incompatible input movie: format version {} is not supported (expected version {})", words[2], MOVIE_VERSION));
+                }
+            },
+            _ => return Err(String::from("Invalid input movie: the file is not an input movie")),
+        }
+
+        let mut header = |name: &str| -> Result<Vec<&str>, String> {
+            match lines.next() {
+                Some((_, words)) if words.first() == Some(&name) => Ok(words[1..].to_vec()),
+                Some((line, _)) => Err(invalid(line)),
+                None => Err(String::from("Invalid input movie: the file ends before the header")),
+            }
+        };
+        let number = |words: &[&str], radix: u32| -> Option<u64> {
+            match words {
+                [word] => u64::from_str_radix(word, radix).ok(),
+                _ => None,
+            }
+        };
+
+        let rom_hash = number(&header("rom")?, 16).ok_or_else(|| invalid(2))?;
+        let seed = number(&header("seed")?, 10).ok_or_else(|| invalid(3))?;
+        let cycles_per_frame = number(&header("speed")?, 10).and_then(|n| u32::try_from(n).ok()).ok_or_else(|| invalid(4))?;
+
+        let mut quirks = Quirks::modern();
+        for name in header("quirks")? {
+            if !set_quirk(&mut quirks, name) {
+                return Err(invalid(5));
+            }
+        }
+
+        let mut movie = Movie::new(rom_hash, seed, cycles_per_frame, quirks);
+        let mut previous_frame = 0;
+        for (line, words) in lines {
+            match words[..] {
+                [] => (),
+                ["end", length] => {
+                    movie.length = length.parse().map_err(|_| invalid(line))?;
+                    return Ok(movie);
+                },
+                [frame, kind, value] => {
+                    let frame: u64 = frame.parse().map_err(|_| invalid(line))?;
+                    if frame < previous_frame {
+                        return Err(invalid(line));
+                    }
+                    previous_frame = frame;
+
+                    let event = match kind {
+                        "down" => usize::from_str_radix(value, 16).ok().filter(|k| *k < 16).map(MovieEvent::KeyDown),
+                        "up" => usize::from_str_radix(value, 16).ok().filter(|k| *k < 16).map(MovieEvent::KeyUp),
+                        "speed" => value.parse().ok().map(MovieEvent::Speed),
+                        _ => None,
+                    };
+                    movie.record(frame, event.ok_or_else(|| invalid(line))?);
+                },
+                _ => return Err(invalid(line)),
+            }
+        }
+        Err(String::from("Invalid input movie: the file ends before its end line"))
+    }
+}
+
+#[cfg(test)]
+#[path ="./movie_test.rs"]
+mod movie_test;
@@ -0,0 +1,57 @@
+use super::*;
+
+#[test]
+fn test_movie_text() {
+    let mut movie = Movie::new(0x5A3D_8C1F_00E2_B6A4, 1234, 9, Quirks::cosmac_vip());
+    movie.record(120, MovieEvent::KeyDown(0x5));
+    movie.record(131, MovieEvent::KeyUp(0x5));
+    movie.record(131, MovieEvent::KeyDown(0xA));
+    movie.record(400, MovieEvent::Speed(20));
+    movie.length = 1800;
+
+    let text = movie.to_string();
+    assert_eq!(text, "\
+CHIP-8 MOVIE 1
+rom 5A3D8C1F00E2B6A4
+seed 1234
+speed 9
+quirks shift_uses_vy load_store_increments_i vf_reset clip_sprites display_wait
+120 down 5
+131 up 5
+131 down A
+400 speed 20
+end 1800
+");
+    assert_eq!(text.parse::<Movie>(), Ok(movie));
+
+    // CHIP-48 increments I by X only
+    let movie = Movie::new(0, 0, 9, Quirks::chip48());
+    assert!(movie.to_string().contains("quirks load_store_increments_i_by_x jump_uses_vx clip_sprites\n"));
+    assert_eq!(movie.to_string().parse::<Movie>(), Ok(movie));
+}
+
+#[test]
+fn test_movie_events() {
+    let mut movie = Movie::new(0, 0, 9, Quirks::default());
+    movie.record(3, MovieEvent::KeyDown(0x1));
+    movie.record(3, MovieEvent::KeyDown(0x2));
+    movie.record(5, MovieEvent::KeyUp(0x1));
+
+    assert_eq!(movie.get_events(0).count(), 0);
+    assert_eq!(movie.get_events(3).collect::<Vec<_>>(), [MovieEvent::KeyDown(0x1), MovieEvent::KeyDown(0x2)]);
+    assert_eq!(movie.get_events(5).collect::<Vec<_>>(), [MovieEvent::KeyUp(0x1)]);
+    assert_eq!(movie.length, 5);
+}
+
+#[test]
+fn test_movie_parse_errors() {
+    let header = "CHIP-8 MOVIE 1\nrom 00\nseed 1\nspeed 9\nquirks\n";
+
+    assert!("not a movie".parse::<Movie>().unwrap_err().contains("not an input movie"));
+    assert!("CHIP-8 MOVIE 2\n".parse::<Movie>().unwrap_err().contains("version 2 is not supported"));
+    assert!(format!("{}3 down G\nend 3\n", header).parse::<Movie>().unwrap_err().contains("line 6"));
+    assert!(format!("{}5 down 1\n3 up 1\nend 5\n", header).parse::<Movie>().unwrap_err().contains("line 7"));
+    assert!(format!("{}3 down 1\n", header).parse::<Movie>().unwrap_err().contains("before its end"));
+    assert!("CHIP-8 MOVIE 1\nrom 00\nseed 1\nquirks\n".parse::<Movie>().unwrap_err().contains("line 4"));
+    assert_eq!(format!("{}end 0\n", header).parse::<Movie>().map(|m| m.length), Ok(0));
+}
@@ -0,0 +1,279 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+// Octo programs are loaded at 0x200, which holds a jump to main unless main comes first
+const PROGRAM_START: u32 = 0x200;
+const JUMP_TO_MAIN_SIZE: u32 = 2;
+
+
+// Word of an Octo source and the line it is on (the first line is 1)
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Token {
+    line: usize,
+    text: String,
+}
+
+// Splits an Octo source into words, leaving out the comments, which start with # and end with the line
+fn tokenize(source: &str) -> Vec<Token> {
+    source.lines().enumerate()
+        .flat_map(|(idx, line)| {
+            let code = line.split('#').next().unwrap_or_default();
+            code.split_whitespace().map(move |text| Token { line: idx + 1, text: text.to_string() })
+        })
+        .collect()
+}
+
+// Returns whether the word is a number, which is a byte of data when it starts a statement
+fn is_number(text: &str) -> bool {
+    let text = text.strip_prefix('-').unwrap_or(text);
+    if let Some(digits) = text.strip_prefix("0x") {
+        !digits.is_empty() && digits.chars().all(|c| c.is_ascii_hexdigit())
+    } else if let Some(digits) = text.strip_prefix("0b") {
+        !digits.is_empty() && digits.chars().all(|c| c == '0' || c == '1')
+    } else {
+        !text.is_empty() && text.chars().all(|c| c.is_ascii_digit())
+    }
+}
+
+// Parses the address of an :org directive
+fn parse_address(token: &Token) -> Result<u32, String> {
+    let text = &token.text;
+    let value = match text.strip_prefix("0x") {
+        Some(digits) => u32::from_str_radix(digits, 16),
+        None => text.parse(),
+    };
+    value.map_err(|_| format!("line {}: unsupported address '{}' (expected a number)", token.line, text))
+}
+
+// Macro of an Octo source: the names of its arguments and the words of its body
+#[derive(Clone, Debug)]
+struct Macro {
+    arguments: Vec<String>,
+    body: Vec<Token>,
+}
+
+/* Finds the addresses of an Octo source by measuring the bytes of each statement, without assembling them.
+ *
+ * Statements are sized after their first words, so the values of expressions are never needed,
+ * except for the addresses of :org */
+struct Measurer {
+    address: u32,
+
+    // Address of the main label, once it is defined
+    main: Option<u32>,
+
+    // Register aliases, constants and macros defined so far
+    aliases: HashSet<String>,
+    constants: HashSet<String>,
+    macros: HashMap<String, Macro>,
+
+    // Bytes assembled from each line: address of the first one and how many there are
+    lines: BTreeMap<usize, (u16, u32)>,
+}
+
+impl Measurer {
+
+    // Creates new measurer of a program starting at the chosen address
+    fn new(address: u32) -> Self {
+        Self {
+            address,
+            main: None,
+            aliases: HashSet::new(),
+            constants: HashSet::new(),
+            macros: HashMap::new(),
+            lines: BTreeMap::new(),
+        }
+    }
+
+    // Counts bytes assembled from the chosen line
+    fn emit(&mut self, line: usize, size: u32) -> Result<(), String> {
+        if self.address + size > 0x10000 {
+            return Err(format!("line {}: the program does not fit in memory", line));
+        }
+        let (_, length) = self.lines.entry(line).or_insert((self.address as u16, 0));
+        *length += size;
+        self.address += size;
+        Ok(())
+    }
+
+    // Returns whether the word names a register, such as "v3", "i" or an alias
+    fn is_register(&self, text: &str) -> bool {
+        let lower = text.to_lowercase();
+        let is_v = lower.len() == 2 && lower.starts_with('v') && lower.chars().nth(1).is_some_and(|c| c.is_ascii_hexdigit());
+        is_v || matches!(lower.as_str(), "i" | "delay" | "buzzer" | "pitch") || self.aliases.contains(text)
+    }
+
+    /* Measures the statements of the chosen words. The bytes of a macro body are counted
+     * on the line of the statement that invoked the macro */
+    fn measure(&mut self, tokens: &[Token], invoked_on: Option<usize>) -> Result<(), String> {
+        let mut idx = 0;
+        while let Some(token) = tokens.get(idx) {
+            let line = invoked_on.unwrap_or(token.line);
+            let word = |offset: usize| -> Result<&Token, String> {
+                tokens.get(idx + offset).ok_or_else(|| format!("line {}: '{}' is missing a word after it", token.line, token.text))
+            };
+
+            let (size, words) = match token.text.as_str() {
+                ":" | ":next" => {
+                    if word(1)?.text == "main" {
+                        self.main = Some(self.address);
+                    }
+                    (0, 2)
+                },
+                ":breakpoint" => (0, 2),
+                ":alias" | ":const" => {
+                    let name = word(1)?.text.clone();
+                    let words = if word(2)?.text == "{" { 2 + block_length(tokens, idx + 2)? } else { 3 };
+                    if token.text == ":alias" {
+                        self.aliases.insert(name);
+                    } else {
+                        self.constants.insert(name);
+                    }
+                    (0, words)
+                },
+                ":calc" => {
+                    self.constants.insert(word(1)?.text.clone());
+                    (0, 2 + block_length(tokens, idx + 2)?)
+                },
+                ":org" => {
+                    self.address = parse_address(word(1)?)?;
+                    (0, 2)
+                },
+                ":byte" | ":pointer" => {
+                    let size = if token.text == ":byte" { 1 } else { 2 };
+                    let words = if word(1)?.text == "{" { 1 + block_length(tokens, idx + 1)? } else { 2 };
+                    (size, words)
+                },
+                ":call" => (2, 2),
+                ":unpack" => (4, 3),
+                ":monitor" => (0, 3),
+                ":macro" => {
+                    let name = word(1)?.text.clone();
+                    let mut arguments = Vec::new();
+                    let mut start = idx + 2;
+                    while tokens.get(start).is_some_and(|t| t.text != "{") {
+                        arguments.push(tokens[start].text.clone());
+                        start += 1;
+                    }
+                    let length = block_length(tokens, start)?;
+                    let body = tokens[start + 1..start + length - 1].to_vec();
+                    self.macros.insert(name, Macro { arguments, body });
+                    (0, start + length - idx)
+                },
+                ";" | "return" | "clear" | "hires" | "lores" | "exit" | "scroll-left" | "scroll-right" | "audio" | "else" | "again" => (2, 1),
+                "loop" | "end" => (0, 1),
+                "scroll-down" | "scroll-up" | "bcd" | "saveflags" | "loadflags" | "jump" | "jump0" | "native" | "plane" => (2, 2),
+                "save" | "load" => match tokens.get(idx + 2) {
+                    Some(t) if t.text == "-" => (2, 4),
+                    _ => (2, 2),
+                },
+                "sprite" => (2, 4),
+
+                // Conditions skip the next instruction, or jump over a block
+                "if" => {
+                    let length = tokens[idx..].iter().position(|t| t.text == "then" || t.text == "begin")
+                        .ok_or_else(|| format!("line {}: 'if' has no 'then' or 'begin'", token.line))?;
+                    let size = if tokens[idx + length].text == "begin" { 4 } else { 2 };
+                    (size, length + 1)
+                },
+                "while" => match word(2)?.text.as_str() {
+                    "key" | "-key" => (4, 3),
+                    _ => (4, 4),
+                },
+
+                text if text.starts_with(':') || text.starts_with('{') || text.starts_with('"') => {
+                    return Err(format!("line {}: unsupported statement '{}'", token.line, text));
+                },
+                text if self.is_register(text) => match word(2)?.text.as_str() {
+                    "long" => (4, 4),
+                    "hex" | "bighex" | "random" => (2, 4),
+                    _ => (2, 3),
+                },
+                text if is_number(text) || self.constants.contains(text) => (1, 1),
+                text if self.macros.contains_key(text) => {
+                    let invoked = self.macros[text].clone();
+                    let arguments: HashMap<&str, &Token> = invoked.arguments.iter()
+                        .enumerate()
+                        .map(|(offset, name)| word(offset + 1).map(|argument| (name.as_str(), argument)))
+                        .collect::<Result<_, _>>()?;
+                    let body: Vec<Token> = invoked.body.iter()
+                        .map(|t| arguments.get(t.text.as_str()).map_or_else(|| t.clone(), |argument| Token { line: t.line, ..(*argument).clone() }))
+                        .collect();
+                    self.measure(&body, Some(line))?;
+                    (0, 1 + invoked.arguments.len())
+                },
+
+                // Any other word is a label, which is called
+                _ => (2, 1),
+            };
+
+            word(words - 1)?;
+            if size > 0 {
+                self.emit(line, size)?;
+            }
+            idx += words;
+        }
+        Ok(())
+    }
+}
+
+// Returns how many words the block starting at the chosen opening brace has, both braces included
+fn block_length(tokens: &[Token], start: usize) -> Result<usize, String> {
+    let mut depth = 0;
+    for (offset, token) in tokens[start..].iter().enumerate() {
+        match token.text.as_str() {
+            "{" => depth += 1,
+            "}" => depth -= 1,
+            _ => (),
+        }
+        if depth == 0 {
+            return Ok(offset + 1);
+        }
+    }
+    let line = tokens.get(start).map_or(0, |t| t.line);
+    Err(format!("line {}: '{{' is never closed", line))
+}
+
+/* Lines of an Octo source, and the addresses of the bytes each line assembles to.
+ *
+ * It is used to set breakpoints on lines of the source of a ROM, and to show where the PC is
+ * in the source. Every Octo statement is supported except strings (:stringmode) */
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SourceMap {
+
+    // Address of the first byte each line assembles to, and the number of bytes, for the lines that assemble to any
+    lines: BTreeMap<usize, (u16, u32)>,
+}
+
+impl SourceMap {
+
+    // Measures the statements of an Octo source, as it would be assembled by Octo
+    pub fn from_octo(source: &str) -> Result<Self, String> {
+        let tokens = tokenize(source);
+        let mut measurer = Measurer::new(PROGRAM_START + JUMP_TO_MAIN_SIZE);
+        measurer.measure(&tokens, None)?;
+
+        // The jump to main is left out when main comes first, so the program starts 2 bytes earlier
+        if measurer.main == Some(PROGRAM_START + JUMP_TO_MAIN_SIZE) {
+            measurer = Measurer::new(PROGRAM_START);
+            measurer.measure(&tokens, None)?;
+        }
+        Ok(Self { lines: measurer.lines })
+    }
+
+    /* Returns the first line at or after the chosen one that assembles to any bytes, and
+     * the address of its first byte */
+    pub fn get_address(&self, line: usize) -> Option<(usize, u16)> {
+        self.lines.range(line..).next().map(|(line, (address, _))| (*line, *address))
+    }
+
+    // Returns the line the byte at the chosen address was assembled from
+    pub fn get_line(&self, address: u16) -> Option<usize> {
+        self.lines.iter()
+            .find(|(_, (start, length))| (*start as u32..*start as u32 + length).contains(&(address as u32)))
+            .map(|(line, _)| *line)
+    }
+}
+
+#[cfg(test)]
+#[path ="./octo_test.rs"]
+mod octo_test;
@@ -0,0 +1,112 @@
+use super::*;
+
+const COUNTER: &str = "\
+# Draws a counter
+:alias counter v3
+:const STEP 2
+
+: main
+  clear
+  counter := 0
+  loop
+    i := hex counter
+    draw-digit
+    counter += STEP
+    if counter == 10 then counter := 0
+  again
+
+: draw-digit
+  sprite v0 v0 5  # the font is 5 lines high
+  return
+
+: digits
+  0xF0 0x90 0x90
+  0x90 0xF0
+";
+
+#[test]
+fn test_main_first() -> Result<(), String> {
+    let map = SourceMap::from_octo(COUNTER)?;
+
+    assert_eq!(map.get_address(6), Some((6, 0x200)));
+    assert_eq!(map.get_address(7), Some((7, 0x202)));
+    assert_eq!(map.get_address(10), Some((10, 0x206)));
+    assert_eq!(map.get_address(12), Some((12, 0x20A)));
+    assert_eq!(map.get_address(13), Some((13, 0x20E)));
+    assert_eq!(map.get_address(17), Some((17, 0x212)));
+    assert_eq!(map.get_address(20), Some((20, 0x214)));
+    assert_eq!(map.get_address(21), Some((21, 0x217)));
+
+    // Lines without any code move to the next line that has some
+    assert_eq!(map.get_address(1), Some((6, 0x200)));
+    assert_eq!(map.get_address(8), Some((9, 0x204)));
+    assert_eq!(map.get_address(15), Some((16, 0x210)));
+    assert_eq!(map.get_address(22), None);
+
+    // Every byte of a line maps back to it
+    assert_eq!(map.get_line(0x20A), Some(12));
+    assert_eq!(map.get_line(0x20C), Some(12));
+    assert_eq!(map.get_line(0x216), Some(20));
+    assert_eq!(map.get_line(0x219), None);
+    Ok(())
+}
+
+#[test]
+fn test_jump_to_main() -> Result<(), String> {
+    let source = "\
+: draw
+  sprite v0 v1 8
+;
+: main
+  draw
+  if v0 != 0 begin
+    v1 := random 0xFF
+  else
+    i := long draw
+  end
+  while v2 -key
+  save v0 - v3
+  jump main
+";
+    let map = SourceMap::from_octo(source)?;
+
+    // The program starts after the jump to main
+    assert_eq!(map.get_address(2), Some((2, 0x202)));
+    assert_eq!(map.get_address(5), Some((5, 0x206)));
+    assert_eq!(map.get_address(6), Some((6, 0x208)));
+    assert_eq!(map.get_address(7), Some((7, 0x20C)));
+    assert_eq!(map.get_address(8), Some((8, 0x20E)));
+    assert_eq!(map.get_address(9), Some((9, 0x210)));
+    assert_eq!(map.get_address(11), Some((11, 0x214)));
+    assert_eq!(map.get_address(12), Some((12, 0x218)));
+    assert_eq!(map.get_address(13), Some((13, 0x21A)));
+    Ok(())
+}
+
+#[test]
+fn test_macros() -> Result<(), String> {
+    let source = "\
+:macro add-both a b { a += 1 b += 1 :byte 7 }
+: main
+  add-both v0 v1
+  :org 0x300
+  :calc twice { 2 * 2 }
+  twice
+";
+    let map = SourceMap::from_octo(source)?;
+
+    // The bytes of a macro are counted on the line it is invoked on
+    assert_eq!(map.get_address(1), Some((3, 0x200)));
+    assert_eq!(map.get_line(0x204), Some(3));
+    assert_eq!(map.get_line(0x205), None);
+    assert_eq!(map.get_address(4), Some((6, 0x300)));
+    Ok(())
+}
+
+#[test]
+fn test_errors() {
+    assert_eq!(SourceMap::from_octo(": main\n:stringmode x \"ab\" { }"), Err(String::from("line 2: unsupported statement ':stringmode'")));
+    assert_eq!(SourceMap::from_octo(": main\n  if v0 == 1\n"), Err(String::from("line 2: 'if' has no 'then' or 'begin'")));
+    assert!(SourceMap::from_octo(":calc x { 1 ").is_err());
+    assert!(SourceMap::from_octo(": main\n  sprite").is_err());
+}
@@ -0,0 +1,135 @@
+use std::str::FromStr;
+
+
+/* Behaviours that differ between CHIP-8 interpreters.
+ *
+ * The original COSMAC VIP interpreter, CHIP-48 and SUPER-CHIP each interpret some of the
+ * ambiguous opcodes differently, and ROMs written for one of them often break on the others. */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Quirks {
+
+    // 8XY6/8XYE shift Vy and store the result in Vx, instead of shifting Vx in place
+    pub shift_uses_vy: bool,
+
+    // How far FX55/FX65 move I once the registers are stored/loaded
+    pub load_store_increment: LoadStoreIncrement,
+
+    // BNNN jumps to XNN plus Vx (BXNN), instead of NNN plus V0
+    pub jump_uses_vx: bool,
+
+    // 8XY1/8XY2/8XY3 reset VF to 0
+    pub vf_reset: bool,
+
+    // DXYN clips sprites at the edges of the screen, instead of wrapping them around
+    pub clip_sprites: bool,
+
+    // DXYN waits for the display refresh, so at most one sprite is drawn per frame
+    pub display_wait: bool,
+
+    // Memory is 64KB instead of 4KB, as in XO-CHIP
+    pub extended_memory: bool,
+}
+
+// Increments of I by FX55/FX65, which differ between all three original interpreters
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LoadStoreIncrement {
+
+    // I is left unchanged (SUPER-CHIP 1.1)
+    None,
+
+    // I points to the last register stored/loaded, I = I + X (CHIP-48)
+    X,
+
+    // I points past the last register stored/loaded, I = I + X + 1 (COSMAC VIP)
+    XPlusOne,
+}
+
+impl Quirks {
+
+    // Behaviour most modern interpreters (and ROMs tested on them) agree on
+    pub const fn modern() -> Self {
+        Self {
+            shift_uses_vy: false,
+            load_store_increment: LoadStoreIncrement::None,
+            jump_uses_vx: false,
+            vf_reset: false,
+            clip_sprites: false,
+            display_wait: false,
+            extended_memory: false,
+        }
+    }
+
+    // The original CHIP-8 interpreter on the COSMAC VIP
+    pub const fn cosmac_vip() -> Self {
+        Self {
+            shift_uses_vy: true,
+            load_store_increment: LoadStoreIncrement::XPlusOne,
+            jump_uses_vx: false,
+            vf_reset: true,
+            clip_sprites: true,
+            display_wait: true,
+            extended_memory: false,
+        }
+    }
+
+    // CHIP-48 on the HP-48 calculators
+    pub const fn chip48() -> Self {
+        Self {
+            shift_uses_vy: false,
+            load_store_increment: LoadStoreIncrement::X,
+            jump_uses_vx: true,
+            vf_reset: false,
+            clip_sprites: true,
+            display_wait: false,
+            extended_memory: false,
+        }
+    }
+
+    // SUPER-CHIP 1.1
+    pub const fn super_chip() -> Self {
+        Self {
+            shift_uses_vy: false,
+            load_store_increment: LoadStoreIncrement::None,
+            jump_uses_vx: true,
+            vf_reset: false,
+            clip_sprites: true,
+            display_wait: false,
+            extended_memory: false,
+        }
+    }
+
+    // XO-CHIP, as implemented by Octo
+    pub const fn xo_chip() -> Self {
+        Self {
+            shift_uses_vy: true,
+            load_store_increment: LoadStoreIncrement::XPlusOne,
+            jump_uses_vx: false,
+            vf_reset: false,
+            clip_sprites: false,
+            display_wait: false,
+            extended_memory: true,
+        }
+    }
+}
+
+impl Default for Quirks {
+    fn default() -> Self {
+        Self::modern()
+    }
+}
+
+// Selects a quirk profile by its name, as used by the command line arguments
+impl FromStr for Quirks {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "modern" => Ok(Self::modern()),
+            "vip" | "cosmac-vip" => Ok(Self::cosmac_vip()),
+            "chip48" | "chip-48" => Ok(Self::chip48()),
+            "schip" | "super-chip" => Ok(Self::super_chip()),
+            "xochip" | "xo-chip" => Ok(Self::xo_chip()),
+            _ => Err(format!("unknown quirk profile '{}' (expected modern, vip, chip48, schip or xochip)", s)),
+        }
+    }
+}
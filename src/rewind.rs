@@ -0,0 +1,145 @@
+use std::collections::VecDeque;
+
+/* By default, a snapshot is taken every 2 frames and 600 of them are kept,
+ * so the last 20 seconds can be rewound */
+pub const DEFAULT_REWIND_INTERVAL: u64 = 2;
+pub const DEFAULT_REWIND_CAPACITY: usize = 600;
+
+
+/* Ring buffer of save state snapshots, used to step backwards through the last frames.
+ *
+ * Only the latest snapshot is kept whole. Older snapshots are stored as the difference to the
+ * snapshot that follows them, which is compressed since most of the memory and display does not
+ * change between frames. Once the buffer is full, the oldest snapshot is dropped */
+pub struct RewindBuffer {
+    latest: Option<Vec<u8>>,
+    deltas: VecDeque<Vec<u8>>,
+    capacity: usize,
+}
+
+impl RewindBuffer {
+
+    // Creates new buffer that keeps at most the chosen number of snapshots
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            latest: None,
+            deltas: VecDeque::new(),
+            capacity: capacity.max(1),
+        }
+    }
+
+    // Adds a snapshot as the latest one
+    pub fn push(&mut self, snapshot: Vec<u8>) {
+        if let Some(latest) = self.latest.take() {
+
+            // Snapshots of different sizes cannot be diffed, so older ones are dropped
+            if latest.len() == snapshot.len() {
+                self.deltas.push_back(encode_delta(&snapshot, &latest));
+            } else {
+                self.deltas.clear();
+            }
+        }
+        self.latest = Some(snapshot);
+
+        while self.len() > self.capacity {
+            self.deltas.pop_front();
+        }
+    }
+
+    // Removes and returns the latest snapshot, so the next one returned is the one before it
+    pub fn pop(&mut self) -> Option<Vec<u8>> {
+        let latest = self.latest.take()?;
+        if let Some(delta) = self.deltas.pop_back() {
+            self.latest = Some(apply_delta(&latest, &delta));
+        }
+        Some(latest)
+    }
+
+    // Removes every snapshot
+    pub fn clear(&mut self) {
+        self.latest = None;
+        self.deltas.clear();
+    }
+
+    // Returns how many snapshots are kept
+    pub fn len(&self) -> usize {
+        self.latest.iter().count() + self.deltas.len()
+    }
+
+    // Returns whether there are no snapshots to rewind to
+    pub fn is_empty(&self) -> bool {
+        self.latest.is_none()
+    }
+
+    // Returns how many bytes the snapshots take
+    pub fn get_size_bytes(&self) -> usize {
+        self.latest.as_ref().map_or(0, |s| s.len()) + self.deltas.iter().map(|d| d.len()).sum::<usize>()
+    }
+}
+
+
+/* Encodes the difference between two snapshots of the same size as runs of unchanged bytes and
+ * runs of changed bytes. Each pair of runs is stored as the length of the unchanged run, the
+ * length of the changed run and the changed bytes XORed with the base snapshot */
+pub fn encode_delta(base: &[u8], target: &[u8]) -> Vec<u8> {
+    let mut delta = Vec::new();
+    let mut idx = 0;
+
+    while idx < base.len() {
+        let unchanged = base[idx..].iter().zip(&target[idx..]).take_while(|(a, b)| a == b).count();
+        idx += unchanged;
+        let changed = base[idx..].iter().zip(&target[idx..]).take_while(|(a, b)| a != b).count();
+
+        write_varint(&mut delta, unchanged);
+        write_varint(&mut delta, changed);
+        delta.extend(base[idx..(idx + changed)].iter().zip(&target[idx..]).map(|(a, b)| a ^ b));
+        idx += changed;
+    }
+    delta
+}
+
+// Returns the snapshot that the delta was encoded to, applying it to the base snapshot
+pub fn apply_delta(base: &[u8], delta: &[u8]) -> Vec<u8> {
+    let mut target = base.to_vec();
+    let mut idx = 0;
+    let mut position = 0;
+
+    while position < delta.len() {
+        idx += read_varint(delta, &mut position);
+        let changed = read_varint(delta, &mut position);
+        for (byte, xor) in target[idx..(idx + changed)].iter_mut().zip(&delta[position..]) {
+            *byte ^= xor;
+        }
+        idx += changed;
+        position += changed;
+    }
+    target
+}
+
+// Writes a length as a variable number of bytes, 7 bits at a time
+fn write_varint(out: &mut Vec<u8>, mut value: usize) {
+    while value >= 0x80 {
+        out.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+// Reads a length written by write_varint, moving the position past it
+fn read_varint(bytes: &[u8], position: &mut usize) -> usize {
+    let mut value = 0;
+    let mut shift = 0;
+    loop {
+        let byte = bytes[*position];
+        *position += 1;
+        value |= ((byte & 0x7F) as usize) << shift;
+        if byte & 0x80 == 0 {
+            return value;
+        }
+        shift += 7;
+    }
+}
+
+#[cfg(test)]
+#[path ="./rewind_test.rs"]
+mod rewind_test;
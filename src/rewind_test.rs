@@ -0,0 +1,78 @@
+use super::*;
+use crate::chip8::Chip8;
+use crate::quirks::Quirks;
+
+#[test]
+fn test_delta() {
+    let base = vec![0u8; 1000];
+    let mut target = base.clone();
+    target[0] = 1;
+    target[500..503].copy_from_slice(&[7, 8, 9]);
+    target[999] = 0xFF;
+
+    let delta = encode_delta(&base, &target);
+    assert!(delta.len() < 20);
+    assert_eq!(apply_delta(&base, &delta), target);
+    assert_eq!(apply_delta(&target, &encode_delta(&target, &base)), base);
+
+    // Identical snapshots
+    assert_eq!(apply_delta(&base, &encode_delta(&base, &base)), base);
+}
+
+#[test]
+fn test_rewind_buffer() {
+    let mut buffer = RewindBuffer::new(3);
+    assert!(buffer.is_empty());
+    assert_eq!(buffer.pop(), None);
+
+    for value in 1..=5u8 {
+        buffer.push(vec![value; 4]);
+    }
+
+    // Only the last 3 snapshots are kept, and they are returned newest first
+    assert_eq!(buffer.len(), 3);
+    assert_eq!(buffer.pop(), Some(vec![5; 4]));
+    assert_eq!(buffer.pop(), Some(vec![4; 4]));
+    assert_eq!(buffer.pop(), Some(vec![3; 4]));
+    assert_eq!(buffer.pop(), None);
+
+    // A snapshot of a different size drops the older ones
+    buffer.push(vec![1; 4]);
+    buffer.push(vec![2; 8]);
+    assert_eq!(buffer.len(), 1);
+
+    buffer.clear();
+    assert!(buffer.is_empty());
+}
+
+#[test]
+fn test_rewind_machine_state() -> Result<(), String> {
+    let mut chip8 = Chip8::new(Quirks::default());
+    chip8.set_seed(1);
+    chip8.load_rom(&[
+        0xC0, 0xFF, // V0 = random number
+        0xF0, 0x29, // I = sprite of the last digit of V0
+        0xD1, 0x25, // Draws it at (V1, V2)
+        0x71, 0x03, // V1 += 3
+        0x12, 0x00, // Jumps back to the start
+    ])?;
+
+    let mut buffer = RewindBuffer::new(DEFAULT_REWIND_CAPACITY);
+    let mut machine_states = Vec::new();
+    for _ in 0..50 {
+        chip8.run_frame(10).unwrap();
+        buffer.push(chip8.save_state());
+        machine_states.push(chip8.get_machine_state());
+    }
+
+    // The snapshots are compact: far smaller than 50 whole save states
+    assert!(buffer.get_size_bytes() < 3 * chip8.save_state().len());
+
+    // Stepping backwards goes through every frame in reverse
+    while let Some(snapshot) = buffer.pop() {
+        chip8.load_state(&snapshot).unwrap();
+        assert_eq!(chip8.get_machine_state(), machine_states.pop().unwrap());
+    }
+    assert_eq!(chip8.get_frame_count(), 1);
+    Ok(())
+}
@@ -0,0 +1,135 @@
+use std::fmt;
+use std::{thread, time};
+
+/* If the emulator falls this many frames behind (for example, after the host was suspended),
+ * the missed frames are dropped instead of being run in a burst to catch up */
+const MAX_FRAMES_BEHIND: u32 = 4;
+
+
+// Frame-time statistics: how long the frames took to run, not counting the time spent sleeping
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FrameStats {
+
+    // How many frames were recorded
+    pub frames: u64,
+
+    // Total and longest time spent running a frame
+    pub busy_total: time::Duration,
+    pub busy_max: time::Duration,
+
+    // How many frames finished after their deadline
+    pub late_frames: u64,
+}
+
+impl FrameStats {
+
+    // Records a frame that took the chosen time to run
+    pub fn record(&mut self, busy: time::Duration, late: bool) {
+        self.frames += 1;
+        self.busy_total += busy;
+        self.busy_max = self.busy_max.max(busy);
+        if late {
+            self.late_frames += 1;
+        }
+    }
+
+    // Returns the average time spent running a frame
+    pub fn get_busy_average(&self) -> time::Duration {
+        if self.frames == 0 {
+            time::Duration::ZERO
+        } else {
+            self.busy_total / self.frames as u32
+        }
+    }
+}
+
+impl fmt::Display for FrameStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "frame time {:.2} ms avg, {:.2} ms max, {} late",
+            self.get_busy_average().as_secs_f64() * 1000.0,
+            self.busy_max.as_secs_f64() * 1000.0,
+            self.late_frames)
+    }
+}
+
+
+/* Paces frames at a fixed rate by sleeping until the deadline of the next frame.
+ *
+ * Deadlines are fixed steps from the first frame, rather than from when the last frame ended,
+ * so oversleeping on one frame is made up for on the next ones and the rate does not drift */
+pub struct FrameScheduler {
+    frame_duration: time::Duration,
+
+    // When the current frame should end
+    deadline: time::Instant,
+
+    // When the current frame started running
+    frame_start: time::Instant,
+
+    // Statistics since they were last taken, and since the scheduler was created
+    stats: FrameStats,
+    total_stats: FrameStats,
+}
+
+impl FrameScheduler {
+
+    // Creates new scheduler that runs the chosen number of frames per second, starting now
+    pub fn new(frame_rate: u32) -> Self {
+        let frame_duration = time::Duration::from_secs(1) / frame_rate;
+        let now = time::Instant::now();
+
+        Self {
+            frame_duration,
+            deadline: now + frame_duration,
+            frame_start: now,
+            stats: FrameStats::default(),
+            total_stats: FrameStats::default(),
+        }
+    }
+
+    // Marks the start of the work of a frame
+    pub fn begin_frame(&mut self) {
+        self.frame_start = time::Instant::now();
+    }
+
+    // Ends the current frame, sleeping until its deadline
+    pub fn end_frame(&mut self) {
+        let now = time::Instant::now();
+        let busy = now.saturating_duration_since(self.frame_start);
+        let late = now > self.deadline;
+
+        self.stats.record(busy, late);
+        self.total_stats.record(busy, late);
+
+        if !late {
+            thread::sleep(self.deadline - now);
+        }
+        self.deadline = next_deadline(self.deadline, now, self.frame_duration);
+    }
+
+    // Returns the statistics since they were last taken, and starts recording them again
+    pub fn take_stats(&mut self) -> FrameStats {
+        std::mem::take(&mut self.stats)
+    }
+
+    // Returns the statistics since the scheduler was created
+    pub fn get_total_stats(&self) -> FrameStats {
+        self.total_stats
+    }
+}
+
+/* Returns the deadline of the frame after the one that ends at the chosen deadline.
+ * When it is too far behind the current time, the schedule restarts from now */
+pub fn next_deadline(deadline: time::Instant, now: time::Instant, frame_duration: time::Duration) -> time::Instant {
+    let next = deadline + frame_duration;
+
+    if now > next + frame_duration * MAX_FRAMES_BEHIND {
+        now + frame_duration
+    } else {
+        next
+    }
+}
+
+#[cfg(test)]
+#[path ="./scheduler_test.rs"]
+mod scheduler_test;
@@ -0,0 +1,44 @@
+use super::*;
+
+#[test]
+fn test_frame_stats() {
+    let mut stats = FrameStats::default();
+    assert_eq!(stats.get_busy_average(), time::Duration::ZERO);
+
+    stats.record(time::Duration::from_millis(2), false);
+    stats.record(time::Duration::from_millis(4), false);
+    stats.record(time::Duration::from_millis(20), true);
+
+    assert_eq!(stats.frames, 3);
+    assert_eq!(stats.get_busy_average(), time::Duration::from_millis(26) / 3);
+    assert_eq!(stats.busy_max, time::Duration::from_millis(20));
+    assert_eq!(stats.late_frames, 1);
+    assert_eq!(stats.to_string(), "frame time 8.67 ms avg, 20.00 ms max, 1 late");
+}
+
+#[test]
+fn test_next_deadline() {
+    let frame = time::Duration::from_millis(10);
+    let start = time::Instant::now();
+
+    // Deadlines are fixed steps, whether the frame ended early or a little late
+    assert_eq!(next_deadline(start, start, frame), start + frame);
+    assert_eq!(next_deadline(start, start + frame + frame / 2, frame), start + frame);
+
+    // Being far behind restarts the schedule from the current time
+    let now = start + frame * 10;
+    assert_eq!(next_deadline(start, now, frame), now + frame);
+}
+
+#[test]
+fn test_scheduler_stats() {
+    let mut scheduler = FrameScheduler::new(1000);
+
+    for _ in 0..3 {
+        scheduler.begin_frame();
+        scheduler.end_frame();
+    }
+    assert_eq!(scheduler.take_stats().frames, 3);
+    assert_eq!(scheduler.take_stats().frames, 0);
+    assert_eq!(scheduler.get_total_stats().frames, 3);
+}
@@ -0,0 +1,136 @@
+/* Renders the display as text or as an image, for headless runs and tests.
+ *
+ * Each pixel holds the bits of both XO-CHIP planes (0 to 3) */
+
+// Characters of the pixels in text: unset, first plane, second plane and both planes
+const ASCII_PIXELS: [char; 4] = ['.', '#', '+', '@'];
+
+// Gray levels of the pixels in images, in the same order
+const GRAY_PIXELS: [u8; 4] = [0, 255, 170, 85];
+
+// Screenshots and GIF recordings are 10 times larger than the display by default (640x320 in low resolution)
+pub const DEFAULT_CAPTURE_SCALE: u32 = 10;
+
+
+// RGB colors the pixels are drawn with, matching the window colors by default
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Palette {
+    pub background: [u8; 3],
+    pub main: [u8; 3],
+
+    // XO-CHIP colors of pixels set only on the second plane, and on both planes
+    pub second_plane: [u8; 3],
+    pub overlap: [u8; 3],
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Self {
+            background: [0, 0, 0],
+            main: [255, 255, 255],
+            second_plane: [170, 170, 170],
+            overlap: [85, 85, 85],
+        }
+    }
+}
+
+impl Palette {
+
+    // Returns the color of a pixel holding the bits of both planes
+    pub fn get_color(&self, pixel: u8) -> [u8; 3] {
+        match pixel & 0x3 {
+            0 => self.background,
+            1 => self.main,
+            2 => self.second_plane,
+            _ => self.overlap,
+        }
+    }
+}
+
+
+// Returns the display as text, one line per row
+pub fn to_ascii(display: &[u8], width: usize) -> String {
+    let mut text = String::with_capacity(display.len() + display.len() / width.max(1));
+    for row in display.chunks(width.max(1)) {
+        text.extend(row.iter().map(|pixel| ASCII_PIXELS[(*pixel & 0x3) as usize]));
+        text.push('\n');
+    }
+    text
+}
+
+// Reads a display written by to_ascii, returning its pixels, width and height
+pub fn from_ascii(text: &str) -> Result<(Vec<u8>, usize, usize), String> {
+    let mut display = Vec::new();
+    let mut width = 0;
+    let mut height = 0;
+
+    for (idx, line) in text.lines().enumerate() {
+        let row = line.chars()
+            .map(|c| ASCII_PIXELS.iter().position(|p| *p == c).map(|pixel| pixel as u8))
+            .collect::<Option<Vec<u8>>>()
+            .ok_or_else(|| format!("line {} has a character that is not a pixel", idx + 1))?;
+
+        if idx == 0 {
+            width = row.len();
+        } else if row.len() != width {
+            return Err(format!("line {} is {} pixels wide instead of {}", idx + 1, row.len(), width));
+        }
+        display.extend(row);
+        height += 1;
+    }
+    Ok((display, width, height))
+}
+
+// Returns the display as a binary PGM (grayscale netpbm) image, scaled up by the chosen factor
+pub fn to_pgm(display: &[u8], width: usize, height: usize, scale: usize) -> Vec<u8> {
+    let scale = scale.max(1);
+    let mut image = format!("P5\n{} {}\n255\n", width * scale, height * scale).into_bytes();
+
+    for row in display.chunks(width.max(1)).take(height) {
+        let scaled_row: Vec<u8> = row.iter()
+            .flat_map(|pixel| std::iter::repeat_n(GRAY_PIXELS[(*pixel & 0x3) as usize], scale))
+            .collect();
+        for _ in 0..scale {
+            image.extend_from_slice(&scaled_row);
+        }
+    }
+    image
+}
+
+// Returns the display as a PNG image with the colors of the palette, scaled up by the chosen factor
+pub fn to_png(display: &[u8], width: usize, height: usize, scale: u32, palette: &Palette) -> Result<Vec<u8>, String> {
+    let scale = scale.max(1) as usize;
+    let mut pixels = Vec::with_capacity(width * height * scale * scale * 3);
+
+    for row in display.chunks(width.max(1)).take(height) {
+        let scaled_row: Vec<u8> = row.iter()
+            .flat_map(|pixel| std::iter::repeat_n(palette.get_color(*pixel), scale))
+            .flatten()
+            .collect();
+        for _ in 0..scale {
+            pixels.extend_from_slice(&scaled_row);
+        }
+    }
+
+    let mut image = Vec::new();
+    let mut encoder = png::Encoder::new(&mut image, (width * scale) as u32, (height * scale) as u32);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header().map_err(|e| e.to_string())?;
+    writer.write_image_data(&pixels).map_err(|e| e.to_string())?;
+    writer.finish().map_err(|e| e.to_string())?;
+    Ok(image)
+}
+
+/* Returns the first numbered capture named after the chosen path that does not exist yet,
+ * such as "<path>.screenshot1.png" for the "screenshot" kind and the "png" extension */
+pub fn capture_path(path: &str, kind: &str, extension: &str) -> String {
+    (1..)
+        .map(|n| format!("{}.{}{}.{}", path, kind, n, extension))
+        .find(|p| !std::path::Path::new(p).exists())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+#[path ="./screen_test.rs"]
+mod screen_test;
@@ -0,0 +1,47 @@
+use super::*;
+
+#[test]
+fn test_ascii() {
+    let display = [0, 1, 2, 3, 1, 0];
+    let text = to_ascii(&display, 3);
+    assert_eq!(text, ".#+\n@#.\n");
+    assert_eq!(from_ascii(&text), Ok((display.to_vec(), 3, 2)));
+}
+
+#[test]
+fn test_ascii_errors() {
+    assert_eq!(from_ascii("..\n.x\n"), Err(String::from("line 2 has a character that is not a pixel")));
+    assert_eq!(from_ascii("..\n...\n"), Err(String::from("line 2 is 3 pixels wide instead of 2")));
+}
+
+#[test]
+fn test_pgm() {
+    let image = to_pgm(&[0, 1, 3, 2], 2, 2, 2);
+    let header = b"P5\n4 4\n255\n";
+    assert_eq!(image[..header.len()], header[..]);
+    assert_eq!(image[header.len()..], [
+        0, 0, 255, 255,
+        0, 0, 255, 255,
+        85, 85, 170, 170,
+        85, 85, 170, 170,
+    ]);
+}
+
+#[test]
+fn test_png() -> Result<(), String> {
+    let palette = Palette { background: [10, 20, 30], main: [200, 100, 0], ..Palette::default() };
+    let image = to_png(&[0, 1, 3, 2], 2, 2, 3, &palette)?;
+
+    let decoder = png::Decoder::new(image.as_slice());
+    let mut reader = decoder.read_info().map_err(|e| e.to_string())?;
+    let mut pixels = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut pixels).map_err(|e| e.to_string())?;
+    assert_eq!((info.width, info.height), (6, 6));
+    assert_eq!(info.color_type, png::ColorType::Rgb);
+
+    // Top-left and top-right pixels of the first row, and bottom-left pixel of the last row
+    assert_eq!(pixels[..3], [10, 20, 30]);
+    assert_eq!(pixels[15..18], [200, 100, 0]);
+    assert_eq!(pixels[5 * 18..5 * 18 + 3], [85, 85, 85]);
+    Ok(())
+}
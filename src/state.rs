@@ -0,0 +1,135 @@
+use crate::error::StateError;
+
+/* Save states start with this magic number, followed by the format version and the hash of
+ * the ROM the state was taken from. The machine state follows, in the order it is written */
+pub const STATE_MAGIC: [u8; 4] = *b"CH8S";
+
+// Version of the save state format, which should be increased whenever the format changes
+pub const STATE_VERSION: u16 = 2;
+
+
+/* Returns the 64-bit FNV-1a hash of a ROM, used to check that a save state is loaded
+ * into the same ROM it was taken from */
+pub fn rom_hash(rom_data: &[u8]) -> u64 {
+    let mut hash: u64 = 0xCBF2_9CE4_8422_2325;
+    for byte in rom_data {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01B3);
+    }
+    hash
+}
+
+// Returns the path of a numbered save state slot, which is the chosen path followed by ".state" and the slot
+pub fn slot_path(path: &str, slot: u8) -> String {
+    format!("{}.state{}", path, slot)
+}
+
+
+// Writes values to a save state, in little-endian order
+#[derive(Default)]
+pub(crate) struct StateWriter {
+    bytes: Vec<u8>,
+}
+
+impl StateWriter {
+
+    pub fn write_u8(&mut self, value: u8) {
+        self.bytes.push(value);
+    }
+
+    pub fn write_bool(&mut self, value: bool) {
+        self.bytes.push(value as u8);
+    }
+
+    pub fn write_u16(&mut self, value: u16) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub fn write_u64(&mut self, value: u64) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub fn write_u128(&mut self, value: u128) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub fn write_bytes(&mut self, bytes: &[u8]) {
+        self.bytes.extend_from_slice(bytes);
+    }
+
+    // Returns the written save state
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+
+// Reads values from a save state, in the order they were written
+pub(crate) struct StateReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> StateReader<'a> {
+
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self {
+            bytes,
+            position: 0,
+        }
+    }
+
+    // Reads the chosen number of bytes, failing if the save state ends before them
+    pub fn read_bytes(&mut self, length: usize) -> Result<&'a [u8], StateError> {
+        if self.bytes.len() - self.position < length {
+            return Err(StateError::Truncated);
+        }
+        let bytes = &self.bytes[self.position..(self.position + length)];
+        self.position += length;
+        Ok(bytes)
+    }
+
+    // Reads exactly as many bytes as the array has
+    pub fn read_array<const N: usize>(&mut self) -> Result<[u8; N], StateError> {
+        let mut array = [0; N];
+        array.copy_from_slice(self.read_bytes(N)?);
+        Ok(array)
+    }
+
+    pub fn read_u8(&mut self) -> Result<u8, StateError> {
+        Ok(self.read_array::<1>()?[0])
+    }
+
+    pub fn read_bool(&mut self) -> Result<bool, StateError> {
+        match self.read_u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(StateError::InvalidFormat),
+        }
+    }
+
+    pub fn read_u16(&mut self) -> Result<u16, StateError> {
+        Ok(u16::from_le_bytes(self.read_array()?))
+    }
+
+    pub fn read_u64(&mut self) -> Result<u64, StateError> {
+        Ok(u64::from_le_bytes(self.read_array()?))
+    }
+
+    pub fn read_u128(&mut self) -> Result<u128, StateError> {
+        Ok(u128::from_le_bytes(self.read_array()?))
+    }
+
+    // Checks that the whole save state was read
+    pub fn finish(self) -> Result<(), StateError> {
+        if self.position == self.bytes.len() {
+            Ok(())
+        } else {
+            Err(StateError::InvalidFormat)
+        }
+    }
+}
+
+#[cfg(test)]
+#[path ="./state_test.rs"]
+mod state_test;
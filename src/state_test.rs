@@ -0,0 +1,47 @@
+use super::*;
+
+#[test]
+fn test_rom_hash() {
+    // Reference values of the FNV-1a 64-bit hash
+    assert_eq!(rom_hash(b""), 0xCBF2_9CE4_8422_2325);
+    assert_eq!(rom_hash(b"a"), 0xAF63_DC4C_8601_EC8C);
+    assert_ne!(rom_hash(&[0x12, 0x00]), rom_hash(&[0x12, 0x02]));
+}
+
+#[test]
+fn test_slot_path() {
+    assert_eq!(slot_path("roms/PONG", 3), "roms/PONG.state3");
+}
+
+#[test]
+fn test_state_writer_reader() {
+    let mut writer = StateWriter::default();
+    writer.write_u8(0xAB);
+    writer.write_bool(true);
+    writer.write_u16(0x1234);
+    writer.write_u64(u64::MAX - 1);
+    writer.write_u128(1 << 100);
+    writer.write_bytes(&[1, 2, 3]);
+    let bytes = writer.into_bytes();
+    assert_eq!(bytes.len(), 1 + 1 + 2 + 8 + 16 + 3);
+
+    let mut reader = StateReader::new(&bytes);
+    assert_eq!(reader.read_u8(), Ok(0xAB));
+    assert_eq!(reader.read_bool(), Ok(true));
+    assert_eq!(reader.read_u16(), Ok(0x1234));
+    assert_eq!(reader.read_u64(), Ok(u64::MAX - 1));
+    assert_eq!(reader.read_u128(), Ok(1 << 100));
+    assert_eq!(reader.read_array::<3>(), Ok([1, 2, 3]));
+    assert_eq!(reader.finish(), Ok(()));
+}
+
+#[test]
+fn test_state_reader_errors() {
+    let mut reader = StateReader::new(&[0x02, 0x01]);
+    assert_eq!(reader.read_bool(), Err(StateError::InvalidFormat));
+    assert_eq!(reader.read_u16(), Err(StateError::Truncated));
+
+    // Bytes left after the state was read
+    let reader = StateReader::new(&[0x00]);
+    assert_eq!(reader.finish(), Err(StateError::InvalidFormat));
+}
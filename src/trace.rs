@@ -0,0 +1,108 @@
+use std::io::{self, Write};
+
+use crate::chip8::Chip8;
+use crate::cpu::Register;
+use crate::debugger::{format_instruction_at, read_word};
+
+
+// Instructions written to a trace: those at an address of a range, run during a window of frames (both ends included)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TraceFilter {
+    pub addresses: Option<(u16, u16)>,
+    pub frames: Option<(u64, u64)>,
+}
+
+impl TraceFilter {
+
+    // Returns whether the instruction at the PC, which is about to run, is written to the trace
+    pub fn matches(&self, chip8: &Chip8) -> bool {
+        let pc = chip8.get_register(Register::Pc);
+        let frame = chip8.get_frame_count();
+        self.addresses.is_none_or(|(start, end)| (start..=end).contains(&pc))
+            && self.frames.is_none_or(|(start, end)| (start..=end).contains(&frame))
+    }
+}
+
+// Parses a window of frames written as FRAME or START-END, in decimal
+pub fn parse_frame_range(s: &str) -> Result<(u64, u64), String> {
+    let invalid = || format!("invalid frames '{}' (expected FRAME or START-END, such as 120-180)", s);
+    let (start, end) = match s.split_once('-') {
+        Some((start, end)) => (start.parse().map_err(|_| invalid())?, end.parse().map_err(|_| invalid())?),
+        None => (s.parse().map_err(|_| invalid())?, s.parse().map_err(|_| invalid())?),
+    };
+    if start > end {
+        return Err(format!("invalid frames '{}' (the end is lower than the start)", s));
+    }
+    Ok((start, end))
+}
+
+/* Returns the trace line of the instruction at the PC, before it runs: the cycle number, the PC,
+ * the opcode and mnemonic of the instruction, then V0-VF, I, SP and the timers, all in hexadecimal
+ * except the cycle number. Columns have a fixed width, so traces can be compared line by line */
+pub fn format_trace_line(cycle: u64, chip8: &Chip8) -> String {
+    let pc = chip8.get_register(Register::Pc);
+    let registers: Vec<String> = (0..16).map(|idx| format!("{:02X}", chip8.get_register(Register::V(idx)))).collect();
+    format!(
+        "{:08} {:04X} {:04X} {:<18} V={} I={:04X} SP={:X} DT={:02X} ST={:02X}",
+        cycle,
+        pc,
+        read_word(chip8, pc),
+        format_instruction_at(chip8, pc),
+        registers.join(" "),
+        chip8.get_register(Register::I),
+        chip8.get_register(Register::Sp),
+        chip8.get_register(Register::Dt),
+        chip8.get_register(Register::St),
+    )
+}
+
+/* Writes a line for each instruction run that the filter keeps.
+ *
+ * The frontend calls it right before each instruction runs. Cycles are counted from the first
+ * instruction traced, including those left out by the filter. After a write fails, nothing
+ * else is written, and the error is returned when the trace is finished */
+pub struct Tracer<W: Write> {
+    writer: W,
+    filter: TraceFilter,
+
+    // Number of the next instruction
+    cycle: u64,
+
+    error: Option<io::Error>,
+}
+
+impl<W: Write> Tracer<W> {
+
+    // Creates new tracer writing the instructions the filter keeps
+    pub fn new(writer: W, filter: TraceFilter) -> Self {
+        Self { writer, filter, cycle: 0, error: None }
+    }
+
+    // Writes the instruction at the PC, which is about to run, if the filter keeps it
+    pub fn trace(&mut self, chip8: &Chip8) {
+        if self.error.is_none() && self.filter.matches(chip8) {
+            if let Err(e) = writeln!(self.writer, "{}", format_trace_line(self.cycle, chip8)) {
+                self.error = Some(e);
+            }
+        }
+        self.cycle += 1;
+    }
+
+    // Returns how many instructions have been run since the trace started
+    pub fn get_cycle_count(&self) -> u64 {
+        self.cycle
+    }
+
+    // Writes the rest of the trace, returning the writer
+    pub fn finish(mut self) -> Result<W, String> {
+        if let Some(e) = self.error {
+            return Err(e.to_string());
+        }
+        self.writer.flush().map_err(|e| e.to_string())?;
+        Ok(self.writer)
+    }
+}
+
+#[cfg(test)]
+#[path ="./trace_test.rs"]
+mod trace_test;
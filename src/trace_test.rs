@@ -0,0 +1,55 @@
+use super::*;
+use crate::quirks::Quirks;
+
+// Sets VE and I, then jumps to itself forever
+const ROM: [u8; 6] = [0x6E, 0x05, 0xA3, 0x00, 0x12, 0x04];
+
+// Runs frames of 3 instructions, tracing them
+fn run_traced(frames: u64, filter: TraceFilter) -> Result<String, String> {
+    let mut chip8 = Chip8::new(Quirks::default());
+    chip8.load_rom(&ROM)?;
+    let mut tracer = Tracer::new(Vec::new(), filter);
+
+    for _ in 0..frames {
+        chip8.run_frame_until(3, |chip8| {
+            tracer.trace(chip8);
+            false
+        }).map_err(|e| e.to_string())?;
+    }
+    assert_eq!(tracer.get_cycle_count(), frames * 3);
+    Ok(String::from_utf8(tracer.finish()?).unwrap())
+}
+
+#[test]
+fn test_trace_lines() -> Result<(), String> {
+    let trace = run_traced(1, TraceFilter::default())?;
+    let lines: Vec<&str> = trace.lines().collect();
+
+    assert_eq!(lines.len(), 3);
+    assert_eq!(lines[0], "00000000 0200 6E05 LD VE, 0x05        V=00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 I=0000 SP=0 DT=00 ST=00");
+    assert_eq!(lines[2], "00000002 0204 1204 JP 0x204           V=00 00 00 00 00 00 00 00 00 00 00 00 00 00 05 00 I=0300 SP=0 DT=00 ST=00");
+    Ok(())
+}
+
+#[test]
+fn test_trace_filter() -> Result<(), String> {
+    // Only the jump, which runs once in the first frame and 3 times in the next ones
+    let filter = TraceFilter { addresses: Some((0x204, 0x2FF)), frames: None };
+    assert_eq!(run_traced(2, filter)?.lines().count(), 4);
+
+    // Cycles keep being counted while instructions are left out
+    let filter = TraceFilter { addresses: None, frames: Some((1, 1)) };
+    let trace = run_traced(3, filter)?;
+    assert_eq!(trace.lines().count(), 3);
+    assert!(trace.starts_with("00000003 0204"));
+    Ok(())
+}
+
+#[test]
+fn test_parse_frame_range() {
+    assert_eq!(parse_frame_range("120-180"), Ok((120, 180)));
+    assert_eq!(parse_frame_range("60"), Ok((60, 60)));
+    assert!(parse_frame_range("180-120").is_err());
+    assert!(parse_frame_range("1-").is_err());
+    assert!(parse_frame_range("a").is_err());
+}
@@ -0,0 +1,100 @@
+use std::fmt;
+use std::str::FromStr;
+
+
+// Kinds of memory access made by instructions
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Access {
+
+    // The byte is read as data, such as by FX65 or a sprite drawn by DXYN
+    Read,
+
+    // The byte is written, such as by FX33 or FX55
+    Write,
+
+    // The byte is fetched as part of an instruction
+    Execute,
+}
+
+// Parses "r", "w" or "x", or the whole name of the access
+impl FromStr for Access {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "r" | "read" => Ok(Access::Read),
+            "w" | "write" => Ok(Access::Write),
+            "x" | "execute" => Ok(Access::Execute),
+            _ => Err(format!("invalid access '{}' (expected r, w or x)", s)),
+        }
+    }
+}
+
+impl fmt::Display for Access {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Access::Read => write!(f, "read"),
+            Access::Write => write!(f, "write"),
+            Access::Execute => write!(f, "execute"),
+        }
+    }
+}
+
+
+// Range of memory addresses (both ends included) whose accesses of one kind halt emulation
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Watchpoint {
+    pub start: u16,
+    pub end: u16,
+    pub access: Access,
+}
+
+impl Watchpoint {
+
+    // Returns whether the access of the chosen address is watched
+    pub fn matches(&self, access: Access, address: u16) -> bool {
+        self.access == access && (self.start..=self.end).contains(&address)
+    }
+}
+
+impl fmt::Display for Watchpoint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.start == self.end {
+            write!(f, "{} {:#06X}", self.access, self.start)
+        } else {
+            write!(f, "{} {:#06X}-{:#06X}", self.access, self.start, self.end)
+        }
+    }
+}
+
+
+/* Watched access made by an instruction.
+ * The old and new values are the same for reads and executions */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WatchHit {
+    pub access: Access,
+    pub address: u16,
+
+    // Address and opcode of the instruction that made the access
+    pub pc: u16,
+    pub opcode: u16,
+
+    // Byte before and after the access
+    pub old: u8,
+    pub new: u8,
+}
+
+impl fmt::Display for WatchHit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Watchpoint: instruction {:#06X} at address {:#06X} ", self.opcode, self.pc)?;
+        match self.access {
+            Access::Read => write!(f, "read {:#04X} at address {:#06X}", self.old, self.address),
+            Access::Write => write!(f, "changed address {:#06X} from {:#04X} to {:#04X}", self.address, self.old, self.new),
+            Access::Execute => write!(f, "was fetched from address {:#06X} ({:#04X})", self.address, self.old),
+        }
+    }
+}
+
+#[cfg(test)]
+#[path ="./watchpoint_test.rs"]
+mod watchpoint_test;
@@ -0,0 +1,30 @@
+use super::*;
+
+#[test]
+fn test_access_parse() {
+    assert_eq!("r".parse(), Ok(Access::Read));
+    assert_eq!("W".parse(), Ok(Access::Write));
+    assert_eq!("execute".parse(), Ok(Access::Execute));
+    assert!("rw".parse::<Access>().is_err());
+}
+
+#[test]
+fn test_matches() {
+    let watchpoint = Watchpoint { start: 0x300, end: 0x30F, access: Access::Write };
+
+    assert!(watchpoint.matches(Access::Write, 0x300));
+    assert!(watchpoint.matches(Access::Write, 0x30F));
+    assert!(!watchpoint.matches(Access::Write, 0x2FF));
+    assert!(!watchpoint.matches(Access::Write, 0x310));
+    assert!(!watchpoint.matches(Access::Read, 0x305));
+    assert_eq!(watchpoint.to_string(), "write 0x0300-0x030F");
+}
+
+#[test]
+fn test_hit_display() {
+    let hit = WatchHit { access: Access::Write, address: 0x300, pc: 0x210, opcode: 0xF355, old: 0, new: 5 };
+    assert_eq!(hit.to_string(), "Watchpoint: instruction 0xF355 at address 0x0210 changed address 0x0300 from 0x00 to 0x05");
+
+    let hit = WatchHit { access: Access::Read, ..hit };
+    assert_eq!(hit.to_string(), "Watchpoint: instruction 0xF355 at address 0x0210 read 0x00 at address 0x0300");
+}
@@ -0,0 +1,86 @@
+use std::io::{Seek, Write};
+
+use crate::audio::{PatternPlayer, AUDIO_PATTERN_SIZE};
+use crate::chip8::FRAME_RATE;
+
+// The buzzer is recorded at a quarter of the full volume, since square waves sound very loud
+const RECORDING_VOLUME: f32 = 0.25;
+
+
+/* Records the buzzer as a 16-bit mono WAV file.
+ *
+ * The buzzer is rendered from the machine state of each frame (whether the sound timer is
+ * running, and the XO-CHIP pattern and pitch) rather than from what the audio device plays,
+ * so a recording is the same on every machine and only depends on the program. Frames that
+ * do not beep are recorded as silence */
+pub struct WavRecorder<W: Write + Seek> {
+    writer: hound::WavWriter<W>,
+    player: PatternPlayer,
+    sample_rate: u32,
+
+    // Samples of the current frame, reused between frames
+    samples: Vec<f32>,
+
+    // How many frames have been captured
+    frames: u64,
+}
+
+impl<W: Write + Seek> WavRecorder<W> {
+
+    // Starts a WAV file with samples generated at the chosen rate
+    pub fn new(writer: W, sample_rate: u32) -> Result<Self, String> {
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let writer = hound::WavWriter::new(writer, spec).map_err(|e| e.to_string())?;
+
+        Ok(Self {
+            writer,
+            player: PatternPlayer::new(sample_rate, RECORDING_VOLUME),
+            sample_rate,
+            samples: Vec::new(),
+            frames: 0,
+        })
+    }
+
+    // Captures a frame of audio: the pattern played at the chosen pitch if the buzzer is beeping, or silence
+    pub fn capture(&mut self, beeping: bool, pattern: [u8; AUDIO_PATTERN_SIZE], pitch: u8) -> Result<(), String> {
+        let count = frame_samples(self.frames, self.sample_rate);
+        self.samples.clear();
+        self.samples.resize(count, 0.0);
+
+        if beeping {
+            self.player.set_pattern(pattern, pitch);
+            self.player.fill(&mut self.samples);
+        }
+        for sample in &self.samples {
+            self.writer.write_sample((sample * i16::MAX as f32) as i16).map_err(|e| e.to_string())?;
+        }
+        self.frames += 1;
+        Ok(())
+    }
+
+    // Writes the sizes in the WAV header, which are only known once the recording ends
+    pub fn finish(self) -> Result<(), String> {
+        self.writer.finalize().map_err(|e| e.to_string())
+    }
+
+    // Returns how many frames have been captured
+    pub fn get_frame_count(&self) -> u64 {
+        self.frames
+    }
+}
+
+/* Returns how many samples a frame lasts at the chosen sample rate. When the rate does not divide
+ * evenly, frames alternate between lengths so the total stays in sync */
+pub fn frame_samples(frame: u64, sample_rate: u32) -> usize {
+    let start = |frame: u64| frame * sample_rate as u64 / FRAME_RATE as u64;
+    (start(frame + 1) - start(frame)) as usize
+}
+
+#[cfg(test)]
+#[path ="./wav_recorder_test.rs"]
+mod wav_recorder_test;
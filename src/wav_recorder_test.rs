@@ -0,0 +1,35 @@
+use super::*;
+use crate::audio::{DEFAULT_AUDIO_PATTERN, DEFAULT_PITCH, SAMPLE_RATE};
+use std::io::Cursor;
+
+#[test]
+fn test_frame_samples() {
+    assert_eq!(frame_samples(0, SAMPLE_RATE), 735);
+    assert_eq!(frame_samples(0, 100), 1);
+    assert_eq!(frame_samples(1, 100), 2);
+    assert_eq!(frame_samples(2, 100), 2);
+    assert_eq!((0..60).map(|frame| frame_samples(frame, 100)).sum::<usize>(), 100);
+}
+
+#[test]
+fn test_wav_recorder() -> Result<(), String> {
+    let mut data = Cursor::new(Vec::new());
+    let mut recorder = WavRecorder::new(&mut data, SAMPLE_RATE)?;
+    recorder.capture(false, DEFAULT_AUDIO_PATTERN, DEFAULT_PITCH)?;
+    recorder.capture(true, DEFAULT_AUDIO_PATTERN, DEFAULT_PITCH)?;
+    recorder.capture(false, DEFAULT_AUDIO_PATTERN, DEFAULT_PITCH)?;
+    assert_eq!(recorder.get_frame_count(), 3);
+    recorder.finish()?;
+
+    let reader = hound::WavReader::new(Cursor::new(data.into_inner())).map_err(|e| e.to_string())?;
+    assert_eq!(reader.spec().sample_rate, SAMPLE_RATE);
+    let samples = reader.into_samples::<i16>().collect::<Result<Vec<i16>, _>>().map_err(|e| e.to_string())?;
+    assert_eq!(samples.len(), 3 * 735);
+
+    // Only the second frame beeps, with the 250Hz square wave of the default pattern
+    assert!(samples[..735].iter().all(|s| *s == 0));
+    assert!(samples[735..2 * 735].iter().all(|s| s.abs() == (0.25 * i16::MAX as f32) as i16));
+    assert_eq!(samples[735..2 * 735].windows(2).filter(|w| w[0] != w[1]).count(), 8); // A 250Hz square wave flips about 8 times in 1/60s
+    assert!(samples[2 * 735..].iter().all(|s| *s == 0));
+    Ok(())
+}
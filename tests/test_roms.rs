@@ -0,0 +1,217 @@
+/* Conformance tests: boots the test ROMs headless for a fixed number of frames and compares
+ * the display with the golden framebuffers in tests/goldens.
+ *
+ * Goldens are text files written by screen::to_ascii. To create or update them after checking
+ * that the new output is right, run the tests with UPDATE_GOLDENS=1 */
+
+use std::path::{Path, PathBuf};
+
+use chip_8::frontend::{self, KeyPress, NullAudio, NullVideo, RunOptions, ScriptedInput};
+use chip_8::{screen, Chip8, Quirks};
+
+struct RomTest {
+
+    // Name of the golden file, without extension
+    name: &'static str,
+
+    // Path of the ROM, from the root of the repository
+    rom: &'static str,
+
+    quirks: Quirks,
+    frames: u64,
+
+    // Key presses written as FRAME:KEY or FRAME:KEY:HELD_FRAMES
+    keys: &'static [&'static str],
+}
+
+fn repo_path(path: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join(path)
+}
+
+// Runs the ROM headless, returning the display as text
+fn run_rom(test: &RomTest, rom_data: &[u8]) -> String {
+    let mut chip8 = Chip8::new(test.quirks);
+    chip8.set_seed(0);
+    chip8.load_rom(rom_data).unwrap();
+
+    let mut input = ScriptedInput::default();
+    for key in test.keys {
+        key.parse::<KeyPress>().unwrap().push_to(&mut input);
+    }
+    let options = RunOptions {
+        state_path: None,
+        paced: false,
+        max_frames: Some(test.frames),
+        rewind: false,
+        ..RunOptions::default()
+    };
+
+    if let Err(e) = frontend::run(&mut chip8, &mut NullVideo, &mut NullAudio::default(), &mut input, &options) {
+        panic!("{} stopped: {}\n\n{}", test.name, e, chip8.get_machine_state());
+    }
+    let (width, _) = chip8.get_display_size();
+    screen::to_ascii(chip8.get_display(), width)
+}
+
+/* Shows the expected and actual displays side by side, followed by the pixels that differ (X).
+ * Displays of different sizes are shown one after the other */
+fn visual_diff(expected: &str, actual: &str) -> String {
+    let expected_lines: Vec<&str> = expected.lines().collect();
+    let actual_lines: Vec<&str> = actual.lines().collect();
+
+    let same_size = expected_lines.len() == actual_lines.len()
+        && expected_lines.iter().zip(&actual_lines).all(|(e, a)| e.len() == a.len());
+    if !same_size {
+        return format!("expected:\n{}\nactual:\n{}", expected, actual);
+    }
+
+    let mut diff = String::from("expected | actual | diff\n");
+    for (e, a) in expected_lines.iter().zip(&actual_lines) {
+        let marks: String = e.chars().zip(a.chars()).map(|(ec, ac)| if ec == ac { '.' } else { 'X' }).collect();
+        diff.push_str(&format!("{} | {} | {}\n", e, a, marks));
+    }
+    diff
+}
+
+// Checks the display of the ROM against its golden, or writes the golden with UPDATE_GOLDENS=1
+fn check_golden(test: &RomTest, rom_data: &[u8]) {
+    let actual = run_rom(test, rom_data);
+    let golden_path = repo_path(&format!("tests/goldens/{}.txt", test.name));
+
+    if std::env::var_os("UPDATE_GOLDENS").is_some() {
+        std::fs::write(&golden_path, &actual).unwrap();
+        return;
+    }
+
+    let expected = std::fs::read_to_string(&golden_path).unwrap_or_else(|_| {
+        panic!("{} has no golden at {}; run the tests with UPDATE_GOLDENS=1 to create it\n\n{}", test.name, golden_path.display(), actual)
+    });
+    screen::from_ascii(&expected).unwrap_or_else(|e| panic!("invalid golden {}: {}", golden_path.display(), e));
+
+    if expected != actual {
+        panic!("{} does not match its golden after {} frames\n\n{}", test.name, test.frames, visual_diff(&expected, &actual));
+    }
+}
+
+fn check_rom(test: &RomTest) {
+    let rom_data = std::fs::read(repo_path(test.rom)).unwrap_or_else(|e| panic!("{} could not be read: {}", test.rom, e));
+    check_golden(test, &rom_data);
+}
+
+#[test]
+fn test_ibm_logo() {
+    check_rom(&RomTest {
+        name: "ibm_logo",
+        rom: "roms/test_roms/IBM Logo.ch8",
+        quirks: Quirks::modern(),
+        frames: 60,
+        keys: &[],
+    });
+}
+
+#[test]
+fn test_opcode() {
+    check_rom(&RomTest {
+        name: "test_opcode",
+        rom: "roms/test_roms/test_opcode.ch8",
+        quirks: Quirks::modern(),
+        frames: 120,
+        keys: &[],
+    });
+}
+
+#[test]
+fn test_c8_test() {
+    check_rom(&RomTest {
+        name: "c8_test",
+        rom: "roms/test_roms/c8_test.c8",
+        quirks: Quirks::modern(),
+        frames: 120,
+        keys: &[],
+    });
+}
+
+#[test]
+fn test_delay_timer_increment() {
+
+    // Holding 2 increases the value shown
+    check_rom(&RomTest {
+        name: "delay_timer_increment",
+        rom: "roms/test_roms/delay_timer_test.ch8",
+        quirks: Quirks::modern(),
+        frames: 60,
+        keys: &["0:2:30"],
+    });
+}
+
+#[test]
+fn test_delay_timer_countdown() {
+
+    // Pressing 5 loads the value into the delay timer, which then counts down once per frame
+    check_rom(&RomTest {
+        name: "delay_timer_countdown",
+        rom: "roms/test_roms/delay_timer_test.ch8",
+        quirks: Quirks::modern(),
+        frames: 160,
+        keys: &["0:2:120", "130:5:1"],
+    });
+}
+
+/* Timendus' CHIP-8 test suite (https://github.com/Timendus/chip8-test-suite).
+ * Its ROMs and goldens are not in the repository yet, so these tests are ignored by default.
+ * To run them:
+ *   1. Download bin/3-corax+.ch8, bin/4-flags.ch8 and bin/5-quirks.ch8 of the suite
+ *      to roms/test_roms/timendus
+ *   2. Run `UPDATE_GOLDENS=1 cargo test --test test_roms -- --ignored`, which writes the goldens
+ *   3. Check every golden by eye: corax+ and flags must show a checkmark next to every opcode and
+ *      flag, and each quirks golden must show every quirk as ON/OFF as its platform expects
+ *   4. Run `cargo test --test test_roms -- --ignored`, which fails if a display changes */
+const TIMENDUS_URL: &str = "https://github.com/Timendus/chip8-test-suite/tree/main/bin";
+
+const TIMENDUS_TESTS: [RomTest; 4] = [
+    RomTest {
+        name: "timendus_corax_plus",
+        rom: "roms/test_roms/timendus/3-corax+.ch8",
+        quirks: Quirks::modern(),
+        frames: 120,
+        keys: &[],
+    },
+    RomTest {
+        name: "timendus_flags",
+        rom: "roms/test_roms/timendus/4-flags.ch8",
+        quirks: Quirks::modern(),
+        frames: 120,
+        keys: &[],
+    },
+    RomTest {
+        name: "timendus_quirks_chip8",
+        rom: "roms/test_roms/timendus/5-quirks.ch8",
+        quirks: Quirks::cosmac_vip(),
+        frames: 600,
+        keys: &["0:1"], // Selects the CHIP-8 platform
+    },
+    RomTest {
+        name: "timendus_quirks_schip",
+        rom: "roms/test_roms/timendus/5-quirks.ch8",
+        quirks: Quirks::super_chip(),
+        frames: 600,
+        keys: &["0:2"], // Selects the SUPER-CHIP platform
+    },
+];
+
+#[test]
+#[ignore = "needs the ROMs of Timendus' test suite in roms/test_roms/timendus and their goldens (see the README)"]
+fn test_timendus_suite() {
+    for test in &TIMENDUS_TESTS {
+        if !repo_path(test.rom).exists() {
+            panic!("{} is missing: download it from {} (see the README)", test.rom, TIMENDUS_URL);
+        }
+        check_rom(test);
+    }
+}
+
+#[test]
+fn test_visual_diff() {
+    let diff = visual_diff("#.\n..\n", "##\n..\n");
+    assert_eq!(diff, "expected | actual | diff\n#. | ## | .X\n.. | .. | ..\n");
+}